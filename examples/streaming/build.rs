@@ -10,7 +10,9 @@ fn main() -> Result<(), Box<dyn Error>> {
         .custom_state_type("Greeter", "crate::Greeter")?
         .value_suffix("")
         .type_suffix("")
-        .generate_openapi(true);
+        .generate_openapi(true)
+        .protobuf_streams(true)
+        .collect_streams(true);
 
     // 3. tonic2axum_build: Compile the proto files and return the file descriptor set and its raw bytes.
     let (fds, fds_bytes) = builder.compile_protos(&["proto/hello/v1/hello.proto"], &["proto"])?;
@@ -2,6 +2,7 @@ use std::collections::HashMap;
 use std::env;
 use std::error::Error;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use flexstr::LocalStr;
 use flexstr::str::LocalStrRef;
@@ -9,6 +10,7 @@ use proc_macro2::Span;
 use prost_build::ServiceGenerator;
 use prost_reflect::prost_types::FileDescriptorSet;
 
+use crate::http::{MethodOptions, validate_status_code};
 use crate::message::DocComments;
 use crate::{ProstConfig, TonicBuilder, codegen::Generator};
 
@@ -34,8 +36,22 @@ pub(crate) struct GeneratorConfig {
 
     pub generate_openapi: bool,
     pub generate_web_sockets: bool,
+    pub legacy_ws_routes: bool,
+    pub unary_web_sockets: bool,
+    pub generate_ws_mux: bool,
+    pub ws_max_message_size: Option<usize>,
+    pub ws_max_frame_size: Option<usize>,
+    pub empty_no_content: bool,
+    pub generate_sse: bool,
+    pub stream_envelope: bool,
+    pub protobuf_streams: bool,
+    pub json_array_streams: bool,
+    pub collect_streams: bool,
     pub streaming_content_type: &'static str,
     pub openapi_security: Option<OpenApiSecurity>,
+    pub default_timeout: Option<Duration>,
+    // Service name -> Method name -> MethodOptions
+    pub method_options: HashMap<LocalStr, HashMap<LocalStr, MethodOptions>>,
 
     // Code gen naming and comments
     pub value_suffix: &'static str,
@@ -53,7 +69,20 @@ impl Default for GeneratorConfig {
             state_types: HashMap::new(),
             generate_openapi: false,
             generate_web_sockets: false,
+            legacy_ws_routes: true,
+            unary_web_sockets: false,
+            generate_ws_mux: false,
+            ws_max_message_size: None,
+            ws_max_frame_size: None,
+            empty_no_content: false,
+            generate_sse: false,
+            stream_envelope: false,
+            protobuf_streams: false,
+            json_array_streams: false,
+            collect_streams: false,
             openapi_security: None,
+            default_timeout: None,
+            method_options: HashMap::new(),
             streaming_content_type: "application/x-ndjson",
             value_suffix: "__",
             type_suffix: "__",
@@ -131,12 +160,104 @@ impl Builder {
         self
     }
 
-    /// Set whether to generate WebSocket handlers for streaming RPCs (default: false).
+    /// Set whether to generate WebSocket handlers for streaming RPCs (default: false). These are routed at the method
+    /// path with a `/ws` suffix, and the message encoding is negotiated via the `Sec-WebSocket-Protocol` header
+    /// (`tonic2axum.json` or `tonic2axum.proto`, defaulting to JSON). Methods streaming raw bodies (`google.api.HttpBody`
+    /// or raw bytes fields) have no WebSocket handler.
     pub fn generate_web_sockets(mut self, enable: bool) -> Self {
         self.config.generate_web_sockets = enable;
         self
     }
 
+    /// Set whether to also route WebSocket handlers at the legacy `/ws/json` and `/ws/proto` suffixes, which select the
+    /// message encoding when no subprotocol is negotiated (default: true).
+    pub fn legacy_ws_routes(mut self, enable: bool) -> Self {
+        self.config.legacy_ws_routes = enable;
+        self
+    }
+
+    /// Set whether to also generate WebSocket handlers for unary RPCs (default: false). Each message received on the
+    /// socket is a request, so several calls can be made sequentially on one socket. This only applies when
+    /// [Self::generate_web_sockets] is enabled.
+    pub fn unary_web_sockets(mut self, enable: bool) -> Self {
+        self.config.unary_web_sockets = enable;
+        self
+    }
+
+    /// Set whether to generate a WebSocket handler per service that multiplexes calls to its WebSocket enabled methods
+    /// over a single socket (default: false). This is routed at `/{package}.{Service}/ws` and only applies when
+    /// [Self::generate_web_sockets] is enabled. Messages are always JSON (see `tonic2axum::upgrade_to_ws_mux`).
+    pub fn generate_ws_mux(mut self, enable: bool) -> Self {
+        self.config.generate_ws_mux = enable;
+        self
+    }
+
+    /// Set the maximum size in bytes of a message received by the generated WebSocket handlers (default: the socket's
+    /// own limit). Larger messages close the socket with code 1009. A `tonic2axum::WsLimits` installed on the router
+    /// replaces the limits set here.
+    pub fn ws_max_message_size(mut self, max_message_size: usize) -> Self {
+        self.config.ws_max_message_size = Some(max_message_size);
+        self
+    }
+
+    /// Set the maximum size in bytes of a frame received by the generated WebSocket handlers (default: the socket's own
+    /// limit). See [Self::ws_max_message_size].
+    pub fn ws_max_frame_size(mut self, max_frame_size: usize) -> Self {
+        self.config.ws_max_frame_size = Some(max_frame_size);
+        self
+    }
+
+    /// Set whether to generate Server-Sent Events handlers for server streaming RPCs (default: false). These are routed
+    /// at the method path with an `/sse` suffix. As browsers' `EventSource` can only issue GET requests, they are always
+    /// routed as GET, with every request field not bound by the path taken from the query string.
+    pub fn generate_sse(mut self, enable: bool) -> Self {
+        self.config.generate_sse = enable;
+        self
+    }
+
+    /// Set whether JSON Lines stream responses use a grpc-gateway style envelope (default: false). Each item is sent as
+    /// `{"result": ...}` and a failed stream ends with a final `{"error": ...}` line holding the gRPC status.
+    pub fn stream_envelope(mut self, enable: bool) -> Self {
+        self.config.stream_envelope = enable;
+        self
+    }
+
+    /// Set whether HTTP streaming handlers also support length-prefixed protobuf messages (default: false). The format
+    /// is negotiated per request: request bodies with a `Content-Type` of `application/x-protobuf-stream` and responses
+    /// to requests that `Accept` it use protobuf, and everything else uses JSON Lines.
+    pub fn protobuf_streams(mut self, enable: bool) -> Self {
+        self.config.protobuf_streams = enable;
+        self
+    }
+
+    /// Set whether HTTP streaming responses are sent as a JSON array instead of JSON Lines by default (default: false).
+    /// Either format can still be requested with the `Accept` header (`application/json` or `application/x-ndjson`),
+    /// though with `collect_streams` a `$stream=false` query parameter collects the response instead (see
+    /// `tonic2axum::collect_requested` for the precedence). Streaming request bodies are also read as a JSON array when
+    /// their `Content-Type` is `application/json`. When disabled, request bodies are always read as JSON Lines (or
+    /// protobuf, see `protobuf_streams`).
+    pub fn json_array_streams(mut self, enable: bool) -> Self {
+        self.config.json_array_streams = enable;
+        self
+    }
+
+    /// Set whether server streaming handlers can collect the stream into a single JSON array response (default: false).
+    /// This is done when the client prefers `application/json` over the stream formats in the `Accept` header (unless
+    /// `json_array_streams` is enabled), or when a `$stream=false` query parameter is passed and the header doesn't
+    /// prefer a stream format (see `tonic2axum::collect_requested` for the precedence). Collection is subject to the
+    /// `tonic2axum::CollectLimits` installed on the router.
+    pub fn collect_streams(mut self, enable: bool) -> Self {
+        self.config.collect_streams = enable;
+        self
+    }
+
+    /// Set whether unary methods returning `google.protobuf.Empty` respond with `204 No Content` and no body instead of
+    /// `200 OK` with a `null` body (default: false). A status code set for the method takes precedence.
+    pub fn empty_no_content(mut self, enable: bool) -> Self {
+        self.config.empty_no_content = enable;
+        self
+    }
+
     /// Set the streaming content type for the generated streaming responses (default: "application/x-ndjson").
     pub fn streaming_content_type(
         mut self,
@@ -155,9 +276,100 @@ impl Builder {
         self
     }
 
+    /// Set the timeout applied to calls when the client doesn't send a `grpc-timeout` or `x-request-timeout` header
+    /// and the method has no timeout of its own (default: none). Calls that exceed it fail with `DeadlineExceeded`.
+    pub fn default_timeout(mut self, timeout: Duration) -> Self {
+        self.config.default_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the timeout for a given method, overriding any timeout set via the `tonic2axum.method` proto option. The
+    /// service and method names are the names used in the proto file.
+    pub fn method_timeout(
+        mut self,
+        service_name: impl AsRef<str>,
+        method_name: impl AsRef<str>,
+        timeout: Duration,
+    ) -> Result<Self, Box<dyn Error>> {
+        self.method_options_mut(service_name.as_ref(), method_name.as_ref())?
+            .timeout = Some(timeout);
+        Ok(self)
+    }
+
+    /// Set the HTTP status code returned for successful calls to a given unary method (ie. `201` or `204`), overriding
+    /// any status code set via the `tonic2axum.method` proto option. Must be a 2xx or 3xx code. Services can still
+    /// override the status code at runtime via the `x-http-code` response metadata key.
+    pub fn method_status_code(
+        mut self,
+        service_name: impl AsRef<str>,
+        method_name: impl AsRef<str>,
+        status_code: u16,
+    ) -> Result<Self, Box<dyn Error>> {
+        validate_status_code(status_code.into())?;
+        self.method_options_mut(service_name.as_ref(), method_name.as_ref())?
+            .status_code = Some(status_code.into());
+        Ok(self)
+    }
+
+    /// Send the given `bytes` field of a streaming method's messages as a raw HTTP body instead of JSON, overriding any
+    /// field set via the `tonic2axum.method` proto option. Server streaming responses write the field of each message as
+    /// is (services can set the `content-type` and `content-disposition` headers via initial metadata), and client
+    /// streaming requests turn each chunk of the body into a message, so the HTTP rule's body must be this field.
+    pub fn method_raw_bytes(
+        mut self,
+        service_name: impl AsRef<str>,
+        method_name: impl AsRef<str>,
+        field_name: impl AsRef<str>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let field_name: LocalStrRef = field_name.as_ref().into();
+        if field_name.is_empty() {
+            return Err("Raw bytes field name cannot be empty".into());
+        }
+        self.method_options_mut(service_name.as_ref(), method_name.as_ref())?
+            .raw_bytes_field = Some(field_name.into_owned());
+        Ok(self)
+    }
+
+    /// Set the content type of a method's raw body (default: "application/octet-stream"), overriding any content type
+    /// set via the `tonic2axum.method` proto option. See [Builder::method_raw_bytes].
+    pub fn method_raw_content_type(
+        mut self,
+        service_name: impl AsRef<str>,
+        method_name: impl AsRef<str>,
+        content_type: impl AsRef<str>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let content_type: LocalStrRef = content_type.as_ref().into();
+        if content_type.is_empty() {
+            return Err("Raw content type cannot be empty".into());
+        }
+        self.method_options_mut(service_name.as_ref(), method_name.as_ref())?
+            .raw_content_type = Some(content_type.into_owned());
+        Ok(self)
+    }
+
+    fn method_options_mut(
+        &mut self,
+        service_name: &str,
+        method_name: &str,
+    ) -> Result<&mut MethodOptions, Box<dyn Error>> {
+        if service_name.is_empty() || method_name.is_empty() {
+            return Err("Both service name and method name must be provided".into());
+        }
+
+        let service_name: LocalStrRef = service_name.into();
+        let method_name: LocalStrRef = method_name.into();
+        Ok(self
+            .config
+            .method_options
+            .entry(service_name.into_owned())
+            .or_default()
+            .entry(method_name.into_owned())
+            .or_default())
+    }
+
     /// Set the value suffix for the generated value bindings (default: "__"). It can be empty to avoid the suffix,
     /// if you are sure the names will not conflict with any field names used in your proto messages
-    /// (ie. req, headers, extensions, state).
+    /// (ie. req, headers, extensions, state, options, timeout, uri).
     pub fn value_suffix(mut self, suffix: &'static str) -> Self {
         self.config.value_suffix = suffix;
         self
@@ -289,6 +501,11 @@ impl Builder {
 
         if self.config.generate_openapi {
             prost_config.type_attribute(".", "#[derive(utoipa::ToSchema)]");
+            // HttpBody extensions are `prost_types::Any`, which has no schema
+            prost_config.field_attribute(
+                ".google.api.HttpBody.extensions",
+                "#[schema(value_type = Vec<Object>)]",
+            );
         }
 
         let service_generator = self.make_service_generator(fds_bytes)?;
@@ -1,22 +1,33 @@
 use std::error::Error;
 
 use heck::ToSnakeCase as _;
-use proc_macro2::TokenStream;
+use proc_macro2::{Literal, TokenStream};
 use prost_build::ServiceGenerator;
-use prost_reflect::{DescriptorPool, DynamicMessage};
+use prost_reflect::{DescriptorPool, DynamicMessage, Kind};
 use quote::{format_ident, quote};
 
 use crate::{
     builder::{GeneratorConfig, OpenApiSecurity},
     codegen::helpers::{FunctionParts, ServiceType, ValueNames, ident},
-    http::{HttpOptions, MessageDetails, MethodDetails},
-    message::{ExistingMessages, Message, NewMessages},
+    http::{
+        HTTP_BODY_PROTO_TYPE, HttpOptions, MessageDetails, MessageHandling, MethodDetails,
+        MethodOptions, validate_status_code,
+    },
+    message::{ExistingMessages, Field, Message, NewMessages},
 };
 
+const SSE_CONTENT_TYPE: &str = "text/event-stream";
+const JSON_CONTENT_TYPE: &str = "application/json";
+const ANY_CONTENT_TYPE: &str = "*/*";
+
+// A generated handler function, its route and the path fields of the method
+type HandlerFunc = (TokenStream, TokenStream, Vec<Field>);
+
 pub(crate) struct Generator {
     service_generator: Box<dyn ServiceGenerator>,
 
     options: HttpOptions,
+    pool: DescriptorPool,
     new_messages: NewMessages,
     existing_messages: ExistingMessages,
     modules: Vec<TokenStream>,
@@ -31,13 +42,15 @@ impl Generator {
         bytes: Vec<u8>,
         config: GeneratorConfig,
     ) -> Result<Self, Box<dyn Error>> {
-        let dynamic_fds = Self::decode_fds(&bytes)?;
+        let pool = DescriptorPool::decode(bytes.as_slice())?;
+        let dynamic_fds = Self::decode_fds(&pool, &bytes)?;
         let mut options = HttpOptions::default();
         options.parse_http_options(&dynamic_fds)?;
 
         Ok(Self {
             service_generator,
             options,
+            pool,
             new_messages: NewMessages::default(),
             existing_messages: ExistingMessages::default(),
             modules: Vec::new(),
@@ -46,8 +59,7 @@ impl Generator {
         })
     }
 
-    fn decode_fds(bytes: &[u8]) -> Result<DynamicMessage, Box<dyn Error>> {
-        let pool = DescriptorPool::decode(bytes)?;
+    fn decode_fds(pool: &DescriptorPool, bytes: &[u8]) -> Result<DynamicMessage, Box<dyn Error>> {
         let fds_desc = pool
             .get_message_by_name("google.protobuf.FileDescriptorSet")
             .ok_or("Missing FileDescriptorSet schema")?;
@@ -57,7 +69,7 @@ impl Generator {
     fn generate_service(
         &mut self,
         service: &prost_build::Service,
-        buf: &mut String,
+        buf: &str,
     ) -> Result<(), Box<dyn Error>> {
         self.existing_messages.parse_source(buf)?;
 
@@ -80,26 +92,45 @@ impl Generator {
         let mut ws_handler_funcs = Vec::new();
         let mut ws_proto_routes = Vec::new();
         let mut ws_json_routes = Vec::new();
+        let mut ws_mux_arms = Vec::new();
         let mut has_client_streaming = false;
 
         for method in &service.methods {
-            if let Some((function, route)) =
+            if let Some((function, route, path_fields)) =
                 self.generate_func(&service.name, method, &service_type)?
             {
                 if method.client_streaming {
                     has_client_streaming = true;
                 }
 
-                // Generate WS handler for streaming RPCs when sockets are enabled
+                // HttpBody and raw bytes methods stream raw HTTP bodies, which have no WebSocket equivalent
+                let raw_body_method = method.input_proto_type == HTTP_BODY_PROTO_TYPE
+                    || method.output_proto_type == HTTP_BODY_PROTO_TYPE
+                    || self
+                        .method_options(&service.name, &method.proto_name)
+                        .raw_bytes_field
+                        .is_some();
+
+                // Generate WS handler for streaming (and optionally unary) RPCs when sockets are enabled
                 if self.config.generate_web_sockets
-                    && (method.client_streaming || method.server_streaming)
+                    && !raw_body_method
+                    && (method.client_streaming
+                        || method.server_streaming
+                        || self.config.unary_web_sockets)
+                    && let Some(path) = self.options.get_path(&service.name, &method.proto_name)
                 {
-                    if let Some(path) = self.options.get_path(&service.name, &method.proto_name) {
-                        let (ws_func, ws_proto_route, ws_json_route) =
-                            self.generate_ws_func(method, &service_type, &path);
-                        ws_handler_funcs.push(ws_func);
-                        ws_proto_routes.push(ws_proto_route);
-                        ws_json_routes.push(ws_json_route);
+                    let (ws_func, ws_proto_route, ws_json_route) = self.generate_ws_func(
+                        &service.name,
+                        method,
+                        &service_type,
+                        &path,
+                        &path_fields,
+                    );
+                    ws_handler_funcs.push(ws_func);
+                    ws_proto_routes.extend(ws_proto_route);
+                    ws_json_routes.push(ws_json_route);
+                    if self.config.generate_ws_mux {
+                        ws_mux_arms.push(self.generate_ws_mux_arm(&service.name, method));
                     }
                 }
 
@@ -113,7 +144,7 @@ impl Generator {
             service.name.to_snake_case(),
             self.config.service_mod_name_suffix
         );
-        let use_json_lines = if has_client_streaming {
+        if has_client_streaming {
             // This is due to the need to fill in the associated type in the trait object type. This effectively
             // no longer becomes automatic as this option was intended to be. It is now equivalent to using a custom
             // state type, so just do that (either with  the trait object type + associated type or the type itself).
@@ -124,12 +155,14 @@ impl Generator {
                 )
                 .into());
             }
-
-            Some(quote! { use axum_extra::json_lines::JsonLines; })
-        } else {
-            None
-        };
-        let has_ws = !ws_proto_routes.is_empty();
+        }
+        if !ws_mux_arms.is_empty() {
+            let (ws_mux_func, ws_mux_route) =
+                self.generate_ws_mux_func(service, &service_type, ws_mux_arms);
+            ws_handler_funcs.push(ws_mux_func);
+            ws_json_routes.push(ws_mux_route);
+        }
+        let has_ws = !ws_json_routes.is_empty();
         let use_ws = if has_ws {
             Some(quote! {
                 use axum::extract::WebSocketUpgrade;
@@ -167,7 +200,6 @@ impl Generator {
                 use axum::extract::{Path, Query, State};
                 use axum::routing::{any, get, post, put, delete, patch};
                 use axum::Router;
-                #use_json_lines
                 #use_ws
                 #use_openapi
 
@@ -186,6 +218,88 @@ impl Generator {
         Ok(())
     }
 
+    fn method_options(&self, service_name: &str, method_name: &str) -> MethodOptions {
+        // Builder settings take precedence over proto options
+        let configured = self
+            .config
+            .method_options
+            .get(service_name)
+            .and_then(|methods| methods.get(method_name))
+            .cloned()
+            .unwrap_or_default();
+        let mut options = match self.options.get_method_options(service_name, method_name) {
+            Some(proto_options) => configured.or(proto_options),
+            None => configured,
+        };
+
+        options.timeout = options.timeout.or(self.config.default_timeout);
+        options
+    }
+
+    /// Validates the raw bytes field of a streaming method, which must be a `bytes` field of the streamed messages
+    fn raw_bytes_field(
+        &self,
+        service_name: &str,
+        method: &prost_build::Method,
+        field_name: &str,
+    ) -> Result<syn::Ident, Box<dyn Error>> {
+        let error = |msg: &str| {
+            format!(
+                "{msg}: service: {service_name} method: {} field: {field_name}",
+                &method.name
+            )
+        };
+        if !method.client_streaming && !method.server_streaming {
+            return Err(error("Raw bytes fields are only supported for streaming methods").into());
+        }
+
+        let mut field_ident = None;
+        let streamed_types = [
+            (
+                method.client_streaming,
+                &method.input_type,
+                &method.input_proto_type,
+            ),
+            (
+                method.server_streaming,
+                &method.output_type,
+                &method.output_proto_type,
+            ),
+        ];
+        for (_, type_name, proto_type) in streamed_types.iter().filter(|(streamed, ..)| *streamed) {
+            let descriptor = self
+                .pool
+                .get_message_by_name(proto_type.trim_start_matches('.'))
+                .and_then(|message| message.get_field_by_name(field_name))
+                .ok_or_else(|| error("Raw bytes field not found"))?;
+            if descriptor.kind() != Kind::Bytes || descriptor.is_list() {
+                return Err(error("Raw bytes field must be a `bytes` field").into());
+            }
+
+            let message = self
+                .existing_messages
+                .get_message(type_name)
+                .ok_or_else(|| error("Raw bytes field not found"))?;
+            let field = message
+                .fields()
+                .iter()
+                .find(|field| field.name == field_name)
+                .ok_or_else(|| error("Raw bytes field not found"))?;
+            field_ident = Some(field.ident.clone());
+        }
+
+        field_ident.ok_or_else(|| error("Raw bytes field not found").into())
+    }
+
+    /// The content type documented for streaming requests and responses
+    fn streaming_content_type(&self) -> &'static str {
+        if self.config.json_array_streams {
+            JSON_CONTENT_TYPE
+        } else {
+            self.config.streaming_content_type
+        }
+    }
+
     fn generate_struct(&self, message: &Message, body: bool) -> TokenStream {
         let fields = message.fields().iter().map(|field| {
             let field_name = &field.ident;
@@ -237,9 +351,13 @@ impl Generator {
         service_name: &str,
         method_details: &MethodDetails,
         method: &prost_build::Method,
+        status_code: u16,
+        path: &str,
+        response_content_type: &str,
     ) -> TokenStream {
         let method_name = ident(&method_details.method);
-        let path = method_details.path.as_ref();
+        let method_options = self.method_options(service_name, &method.proto_name);
+        let raw_content_type = method_options.raw_content_type();
 
         let params = if method_details.path_fields.is_empty() && method_details.query_str.is_none()
         {
@@ -264,9 +382,19 @@ impl Generator {
 
         let request_body = if let Some(body) = &method_details.body {
             let input_type = &body.type_name;
+            // HttpBody and raw bytes bodies are sent as is
+            let raw_request_content_type = match &body.handling {
+                MessageHandling::HttpBodyRequest(_) | MessageHandling::HttpBodyField(..) => {
+                    Some(ANY_CONTENT_TYPE)
+                }
+                _ => raw_content_type.filter(|_| method.client_streaming),
+            };
 
-            if method.client_streaming {
-                let content_type = self.config.streaming_content_type;
+            if let Some(content_type) = raw_request_content_type {
+                // content = Vec<u8>, content_type = <content_type>
+                Some(quote! { , request_body(content = Vec<u8>, content_type = #content_type) })
+            } else if method.client_streaming {
+                let content_type = self.streaming_content_type();
 
                 // content = <type>, content_type = <content_type>
                 Some(
@@ -281,21 +409,39 @@ impl Generator {
         };
 
         let responses = if method.output_type == "()" {
-            None
+            // Only document the response when it is known to have no body
+            if matches!(status_code, 204 | 205 | 304) {
+                let status_code = Literal::u16_unsuffixed(status_code);
+                Some(quote! { , responses((status = #status_code, description = "Success")) })
+            } else {
+                None
+            }
         } else {
-            let output_type = ident(&method.output_type);
-
-            let content_type = if method.server_streaming {
-                let content_type = self.config.streaming_content_type;
-                Some(quote! { , content_type = #content_type })
+            let http_body_response = method.output_proto_type == HTTP_BODY_PROTO_TYPE;
+            let output_type =
+                if http_body_response || (raw_content_type.is_some() && method.server_streaming) {
+                    quote! { Vec<u8> }
+                } else {
+                    let output_type = ident(&method.output_type);
+                    quote! { super::#output_type }
+                };
+
+            let content_type = if method.server_streaming || http_body_response {
+                Some(quote! { , content_type = #response_content_type })
             } else {
                 None
             };
 
-            // (status = <code>, description = "description", body = <type>, (content_type = <content_type>))
-            Some(
-                quote! { , responses((status = 200, description = "Success", body = super::#output_type #content_type)) },
-            )
+            // These status codes never have a body
+            let body = if matches!(status_code, 204 | 205 | 304) {
+                None
+            } else {
+                Some(quote! { , body = #output_type #content_type })
+            };
+            let status_code = Literal::u16_unsuffixed(status_code);
+
+            // (status = <code>, description = "description", (body = <type>, (content_type = <content_type>)))
+            Some(quote! { , responses((status = #status_code, description = "Success" #body)) })
         };
 
         let security = if let Some(security) = &self.config.openapi_security {
@@ -327,23 +473,60 @@ impl Generator {
         service_name: &str,
         method: &prost_build::Method,
         service_type: &ServiceType,
-    ) -> Result<Option<(TokenStream, TokenStream)>, Box<dyn Error>> {
+    ) -> Result<Option<HandlerFunc>, Box<dyn Error>> {
         let input_type = &method.input_type;
+        // HttpBody is defined in another package, so it isn't one of the existing messages
+        let http_body_request = method.input_proto_type == HTTP_BODY_PROTO_TYPE;
 
         match self.existing_messages.get_message(input_type) {
-            Some(message) => {
-                let method_details = self.options.parse(
-                    service_name,
-                    &method.proto_name,
-                    message,
-                    &self.existing_messages,
-                    &mut self.new_messages,
-                    &self.config,
-                )?;
+            message if message.is_some() || http_body_request => {
+                let method_options = self.method_options(service_name, &method.proto_name);
+                let raw_field = match &method_options.raw_bytes_field {
+                    Some(field_name) => {
+                        Some(self.raw_bytes_field(service_name, method, field_name)?)
+                    }
+                    None => None,
+                };
+                // A raw request body is the bytes field itself
+                let raw_body = method_options
+                    .raw_bytes_field
+                    .as_deref()
+                    .filter(|_| method.client_streaming);
+
+                let http_option = self
+                    .options
+                    .get_http_options(service_name, &method.proto_name);
+                let method_details = http_option
+                    .map(|option| match message {
+                        Some(message) => option.parse(
+                            message,
+                            raw_body,
+                            &self.existing_messages,
+                            &mut self.new_messages,
+                            &self.config,
+                        ),
+                        None => {
+                            option
+                                .parse_http_body(syn::parse_str(input_type)?)
+                                .map_err(|err| {
+                                    format!(
+                                        "{err}: service: {service_name} method: {}",
+                                        &method.name
+                                    )
+                                    .into()
+                                })
+                        }
+                    })
+                    .transpose()?;
 
                 match method_details {
                     Some(method_details) => {
                         let (req, headers, extensions, state) = self.value_names.names();
+                        let uri = self.value_names.uri();
+                        let options = self.value_names.options();
+                        let timeout = self.value_names.timeout();
+                        let raw_content_type = method_options.raw_content_type();
+                        let http_body_response = method.output_proto_type == HTTP_BODY_PROTO_TYPE;
 
                         // Make the function parts from the method details
                         let func_parts = FunctionParts::new(
@@ -351,10 +534,16 @@ impl Generator {
                             &method_details,
                             input_type,
                             method.client_streaming,
-                            req,
+                            raw_body.is_some(),
+                            &self.value_names,
                         )?;
 
-                        let req_payload =
+                        // JSON request bodies are only read as a JSON array when enabled, which only the `_with` request
+                        // functions can be told, so they are also used (with an identity transform) when nothing is bound
+                        let json_stream_request =
+                            method.client_streaming && raw_field.is_none() && !http_body_request;
+                        let json_array = self.config.json_array_streams;
+                        let request_payload = |func_parts: &FunctionParts| {
                             if func_parts.verbatim_request() && !method.client_streaming {
                                 // Verbatim request so no need to build the request. There will only be a Json<T> tuple struct extractor.
                                 quote! { #req.0 }
@@ -366,17 +555,33 @@ impl Generator {
                                 // needs to be created as there won't be any extractors at all.
                                 let input_type = ident(input_type);
                                 quote! { super::#input_type {} }
+                            } else if json_stream_request
+                                && (func_parts.stream_transform.is_some() || json_array)
+                            {
+                                // Client stream with extracted params, which are merged into each message (otherwise
+                                // the messages are passed on as is)
+                                let identity = quote! { |#req| #req };
+                                let stream_transform =
+                                    func_parts.stream_transform.as_ref().unwrap_or(&identity);
+                                match &func_parts.proto_transform {
+                                    _ if !self.config.protobuf_streams => {
+                                        quote! { #req, #json_array, #stream_transform }
+                                    }
+                                    Some(proto_transform) => {
+                                        quote! { #req, #json_array, #stream_transform, #proto_transform }
+                                    }
+                                    None => {
+                                        quote! { #req, #json_array, #stream_transform, #identity }
+                                    }
+                                }
+                            } else if let Some(stream_transform) = &func_parts.stream_transform {
+                                // Raw or HttpBody client stream with extracted params, which are merged into each message
+                                quote! { #req, #stream_transform }
                             } else {
                                 // Normal case, just reference the request itself that was built from the extracted params.
                                 quote! { #req }
-                            };
-
-                        let FunctionParts {
-                            path_extractor,
-                            query_extractor,
-                            body_extractor,
-                            request_builder,
-                        } = func_parts;
+                            }
+                        };
 
                         let func_name = ident(&method.name);
                         let func_comments = method.comments.leading.join("\n");
@@ -389,54 +594,264 @@ impl Generator {
                         let state_type = &service_type.state_type_name;
                         let handler_generics = service_type.handler_generics();
 
-                        let request_func_name = if method.client_streaming {
-                            quote! { make_stream_request }
-                        } else {
-                            quote! { make_request }
+                        let protobuf_streams = self.config.protobuf_streams;
+                        let request_func_name = match method.client_streaming {
+                            true if raw_field.is_some() => quote! { make_raw_stream_request },
+                            true if http_body_request => quote! { make_http_body_stream_request },
+                            // Transformed messages are decoded into a body struct, which isn't a protobuf message, so protobuf
+                            // messages have their own transform
+                            true if (func_parts.stream_transform.is_some() || json_array)
+                                && protobuf_streams =>
+                            {
+                                quote! { make_negotiated_stream_request_with }
+                            }
+                            true if func_parts.stream_transform.is_some() || json_array => {
+                                quote! { make_stream_request_with }
+                            }
+                            true if protobuf_streams => quote! { make_negotiated_stream_request },
+                            true => quote! { make_stream_request },
+                            false => quote! { make_request },
                         };
-                        let response_func_name = if method.server_streaming {
-                            quote! { make_stream_response }
-                        } else {
-                            quote! { make_response }
+                        let response_func_name = match method.server_streaming {
+                            true if raw_field.is_some() => quote! { make_raw_stream_response },
+                            true if http_body_response => quote! { make_http_body_stream_response },
+                            true if protobuf_streams => quote! { make_negotiated_stream_response },
+                            true => quote! { make_stream_response_with },
+                            false if http_body_response => quote! { make_http_body_response },
+                            false => quote! { make_response_with },
                         };
-                        let path_attr = if self.config.generate_openapi {
-                            Some(self.generate_openapi_path_attr(
-                                service_name,
-                                &method_details,
-                                method,
-                            ))
+                        let timeout_func_name = if method.server_streaming {
+                            quote! { with_stream_timeout }
                         } else {
-                            None
+                            quote! { with_timeout }
                         };
-
-                        let func = quote! {
-                            #func_comments
-                            #path_attr
-                            pub async fn #func_name #handler_generics(
-                                State(#state): State<#state_type>,
-                                #path_extractor
-                                #query_extractor
-                                #headers: http::HeaderMap,
-                                #extensions: http::Extensions,
-                                #body_extractor
-                            ) -> http::Response<Body> {
-                                #request_builder
-                                let #req = tonic2axum::#request_func_name(#headers, #extensions, #req_payload);
-                                tonic2axum::#response_func_name(#state.#func_name(#req).await)
+                        let status_code = match method_options.status_code {
+                            Some(_) if method.server_streaming => {
+                                return Err(format!(
+                                    "Success status codes are only supported for unary responses: service: {} method: {}",
+                                    service_name, &method.name
+                                )
+                                .into());
                             }
+                            Some(status_code) => {
+                                validate_status_code(status_code).map_err(|err| {
+                                    format!(
+                                        "{err} for service: {service_name} method: {}",
+                                        &method.name
+                                    )
+                                })?
+                            }
+                            // Empty is mapped to unit by prost
+                            None if self.config.empty_no_content
+                                && method.output_type == "()"
+                                && !method.server_streaming =>
+                            {
+                                204
+                            }
+                            None => 200,
                         };
-
-                        // Build the route
-                        let turbofish = service_type.handler_route_turbofish();
-                        let route = if self.config.generate_openapi {
-                            quote! { .routes(routes!(#func_name)) }
-                        } else {
-                            let path = method_details.path.as_ref();
-                            let method = ident(&method_details.method);
-                            quote! { .route(#path, #method(#func_name #turbofish)) }
+                        let mut options_builder = TokenStream::new();
+                        if status_code != 200 {
+                            // Validated above (2xx or 3xx), so the conversion can't fail
+                            let status_code = Literal::u16_unsuffixed(status_code);
+                            options_builder.extend(quote! {
+                                .status_code(http::StatusCode::from_u16(#status_code).unwrap())
+                            });
+                        }
+                        // Raw and HttpBody responses have no envelope or stream format
+                        let json_stream_response =
+                            method.server_streaming && raw_field.is_none() && !http_body_response;
+                        if self.config.stream_envelope && json_stream_response {
+                            options_builder.extend(quote! { .stream_envelope(true) });
+                        }
+                        // The stream format is negotiated with the Accept header when other formats are enabled, otherwise it is
+                        // always JSON Lines
+                        if json_stream_response && self.config.json_array_streams {
+                            options_builder.extend(quote! {
+                                .stream_format(tonic2axum::StreamFormat::from_accept_or(
+                                    &#headers,
+                                    tonic2axum::StreamFormat::JsonArray,
+                                ))
+                            });
+                        } else if json_stream_response && protobuf_streams {
+                            options_builder.extend(quote! {
+                                .stream_format(tonic2axum::StreamFormat::from_accept(&#headers))
+                            });
+                        }
+                        // Raw responses write the bytes field of each message as is, and HttpBody responses also carry
+                        // their own content type
+                        let response_args = match (&raw_field, raw_content_type) {
+                            _ if http_body_response => {
+                                let output_type: syn::Type = syn::parse_str(&method.output_type)?;
+                                Some(quote! {
+                                    , |#req: super::#output_type| (#req.content_type, #req.data.into())
+                                })
+                            }
+                            (Some(raw_field), Some(content_type)) if method.server_streaming => {
+                                let output_type = ident(&method.output_type);
+                                Some(quote! {
+                                    , #content_type, |#req: super::#output_type| #req.#raw_field.into()
+                                })
+                            }
+                            _ => None,
+                        };
+                        // The content type of a streamed HttpBody comes from its first message
+                        let response_await = (http_body_response && method.server_streaming)
+                            .then(|| quote! { .await });
+                        let default_timeout = Self::default_timeout(&method_options);
+                        // Server-Sent Events only make sense for server streaming (as browsers can't stream requests). As
+                        // `EventSource` can only issue GET requests, everything not bound by the path comes from the query
+                        // string.
+                        let sse_request = match (http_option, message) {
+                            (Some(http_option), Some(message))
+                                if self.config.generate_sse
+                                    && json_stream_response
+                                    && !method.client_streaming =>
+                            {
+                                let sse_details = http_option.as_get().parse(
+                                    message,
+                                    None,
+                                    &self.existing_messages,
+                                    &mut self.new_messages,
+                                    &self.config,
+                                )?;
+                                let sse_parts = FunctionParts::new(
+                                    &method.name,
+                                    &sse_details,
+                                    input_type,
+                                    false,
+                                    false,
+                                    &self.value_names,
+                                )?;
+                                Some((sse_details, sse_parts))
+                            }
+                            _ => None,
                         };
+                        // Builds a handler (and its route) for the method that converts the response with the given function
+                        let make_handler =
+                            |handler_name: &syn::Ident,
+                             method_details: &MethodDetails,
+                             func_parts: &FunctionParts,
+                             response_func_name: TokenStream,
+                             path: &str,
+                             response_content_type: &str,
+                             collect: bool| {
+                                let req_payload = request_payload(func_parts);
+                                let FunctionParts {
+                                    path_extractor,
+                                    query_extractor,
+                                    body_extractor,
+                                    request_builder,
+                                    ..
+                                } = func_parts;
+                                let path_attr = if self.config.generate_openapi {
+                                    Some(self.generate_openapi_path_attr(
+                                        service_name,
+                                        method_details,
+                                        method,
+                                        status_code,
+                                        path,
+                                        response_content_type,
+                                    ))
+                                } else {
+                                    None
+                                };
+
+                                let response = quote! {
+                                    tonic2axum::#timeout_func_name(#timeout, #state.#func_name(#req)).await
+                                };
+                                // Collected streams are sent like unary responses when requested
+                                let (uri_extractor, collect_option, response) = if collect {
+                                    (
+                                        Some(quote! { #uri: http::Uri, }),
+                                        Some(quote! {
+                                            .collect_stream(tonic2axum::collect_requested(&#uri, &#headers, #json_array))
+                                        }),
+                                        quote! {
+                                            tonic2axum::collect_stream_response(
+                                                #response,
+                                                #options,
+                                                tonic2axum::#response_func_name,
+                                            )
+                                            .await
+                                        },
+                                    )
+                                } else {
+                                    (
+                                        None,
+                                        None,
+                                        quote! {
+                                            tonic2axum::#response_func_name(#response, #options #response_args)
+                                                #response_await
+                                        },
+                                    )
+                                };
+
+                                let func = quote! {
+                                    #func_comments
+                                    #path_attr
+                                    pub async fn #handler_name #handler_generics(
+                                        State(#state): State<#state_type>,
+                                        #path_extractor
+                                        #query_extractor
+                                        #uri_extractor
+                                        #headers: http::HeaderMap,
+                                        #extensions: http::Extensions,
+                                        #body_extractor
+                                    ) -> http::Response<Body> {
+                                        #request_builder
+                                        let #options = tonic2axum::ResponseOptions::from_extensions(&#extensions)
+                                            #options_builder
+                                            #collect_option;
+                                        let #timeout = tonic2axum::request_timeout(&#headers, #default_timeout);
+                                        let #req = tonic2axum::#request_func_name(#headers, #extensions, #req_payload);
+                                        #response
+                                    }
+                                };
+
+                                // Build the route
+                                let turbofish = service_type.handler_route_turbofish();
+                                let route = if self.config.generate_openapi {
+                                    quote! { .routes(routes!(#handler_name)) }
+                                } else {
+                                    let method = ident(&method_details.method);
+                                    quote! { .route(#path, #method(#handler_name #turbofish)) }
+                                };
+
+                                (func, route)
+                            };
 
-                        Ok(Some((func, route)))
+                        let (mut func, mut route) = make_handler(
+                            &func_name,
+                            &method_details,
+                            &func_parts,
+                            response_func_name,
+                            &method_details.path,
+                            if http_body_response {
+                                ANY_CONTENT_TYPE
+                            } else {
+                                raw_content_type.unwrap_or(self.streaming_content_type())
+                            },
+                            self.config.collect_streams && json_stream_response,
+                        );
+
+                        if let Some((sse_details, sse_parts)) = &sse_request {
+                            let sse_func_name = format_ident!("{}_sse", method.name);
+                            let sse_path = format!("{}/sse", method_details.path);
+                            let (sse_func, sse_route) = make_handler(
+                                &sse_func_name,
+                                sse_details,
+                                sse_parts,
+                                quote! { make_sse_response },
+                                &sse_path,
+                                SSE_CONTENT_TYPE,
+                                false,
+                            );
+                            func.extend(sse_func);
+                            route.extend(sse_route);
+                        }
+
+                        Ok(Some((func, route, method_details.path_fields)))
                     }
                     None => {
                         println!("No method details found");
@@ -444,7 +859,7 @@ impl Generator {
                     }
                 }
             }
-            None => Err(format!(
+            _ => Err(format!(
                 "Prost generated message not found: {} for service: {} method: {}",
                 method.input_type, service_name, &method.name
             )
@@ -454,13 +869,21 @@ impl Generator {
 
     fn generate_ws_func(
         &self,
+        service_name: &str,
         method: &prost_build::Method,
         service_type: &ServiceType,
         path: &str,
-    ) -> (TokenStream, TokenStream, TokenStream) {
+        path_fields: &[Field],
+    ) -> (TokenStream, Option<TokenStream>, TokenStream) {
         let (_, headers, extensions, state) = self.value_names.names();
+        let uri = self.value_names.uri();
         let protobuf = format_ident!("protobuf{}", self.config.value_suffix);
         let ws_upgrade = format_ident!("ws_upgrade{}", self.config.value_suffix);
+        let transform = format_ident!("transform{}", self.config.value_suffix);
+        let timeout = self.value_names.timeout();
+        let (extensions_param, ws_limits) = self.generate_ws_limits();
+        let default_timeout =
+            Self::default_timeout(&self.method_options(service_name, &method.proto_name));
 
         let func_name = ident(&method.name);
         let ws_func_name = format_ident!("{}_ws", method.name);
@@ -475,26 +898,52 @@ impl Generator {
             Some(quote! { #[doc = #func_comments] })
         };
 
+        // Path values are bound into each request message
+        let path_binding =
+            FunctionParts::ws_path_binding(path_fields, &method.input_type, &self.value_names);
+        let (path_extractor, transform_builder) = match &path_binding {
+            Some((path_extractor, transform_func)) => (
+                Some(path_extractor),
+                Some(quote! { let #transform = #transform_func; }),
+            ),
+            None => (None, None),
+        };
+        let make_stream_request = if path_binding.is_some() {
+            quote! { tonic2axum::make_ws_stream_request_with(#headers, #extensions, stream, #transform) }
+        } else {
+            quote! { tonic2axum::make_ws_stream_request(#headers, #extensions, stream) }
+        };
+        let (request, unary_request) = if path_binding.is_some() {
+            (
+                quote! { request.map(#transform) },
+                quote! { request.map(&#transform) },
+            )
+        } else {
+            (quote! { request }, quote! { request })
+        };
+
+        // Each call gets the method timeout, which the upgrade request can shorten
         let callback_body = if method.client_streaming && method.server_streaming {
             // Bidi streaming
             quote! {
-                let request = tonic2axum::make_ws_stream_request(#headers, #extensions, stream);
-                let response = #state.#func_name(request).await;
+                let request = #make_stream_request;
+                let response = tonic2axum::with_stream_timeout(#timeout, #state.#func_name(request)).await;
                 tonic2axum::process_ws_stream_response(response, sink, #protobuf).await;
             }
         } else if method.client_streaming {
             // Client streaming
             quote! {
-                let request = tonic2axum::make_ws_stream_request(#headers, #extensions, stream);
-                let response = #state.#func_name(request).await;
+                let request = #make_stream_request;
+                let response = tonic2axum::with_timeout(#timeout, #state.#func_name(request)).await;
                 tonic2axum::process_ws_response(response, sink, #protobuf).await;
             }
-        } else {
+        } else if method.server_streaming {
             // Server streaming
             quote! {
                 match tonic2axum::make_ws_request(#headers, #extensions, stream).await {
                     Some(request) => {
-                        let response = #state.#func_name(request).await;
+                        let response =
+                            tonic2axum::with_stream_timeout(#timeout, #state.#func_name(#request)).await;
                         tonic2axum::process_ws_stream_response(response, sink, #protobuf).await;
                     }
                     None => {
@@ -502,22 +951,41 @@ impl Generator {
                     }
                 }
             }
+        } else {
+            // Unary - each message is a separate call
+            quote! {
+                tonic2axum::process_ws_unary_calls(
+                    #headers,
+                    #extensions,
+                    stream,
+                    sink,
+                    #protobuf,
+                    |request| tonic2axum::with_timeout(#timeout, #state.#func_name(#unary_request)),
+                )
+                .await;
+            }
         };
 
         let func = quote! {
             #func_comments
             pub async fn #ws_func_name #handler_generics(
                 State((#state, #protobuf)): State<(#state_type, bool)>,
+                #path_extractor
                 #ws_upgrade: WebSocketUpgrade,
+                #uri: http::Uri,
                 #headers: http::HeaderMap,
-                #extensions: http::Extensions,
+                #extensions_param: http::Extensions,
             ) -> Response {
+                #ws_limits
+                #transform_builder
                 tonic2axum::upgrade_to_ws(
                     #ws_upgrade,
+                    #uri,
                     #headers,
                     #extensions,
                     #protobuf,
                     |#headers, #extensions, stream, sink, #protobuf| async move {
+                        let #timeout = tonic2axum::request_timeout(&#headers, #default_timeout);
                         #callback_body
                     },
                 )
@@ -525,15 +993,164 @@ impl Generator {
             }
         };
 
-        let ws_path_proto = format!("{}/ws/proto", path);
-        let ws_path_json = format!("{}/ws/json", path);
-
-        let ws_proto_route = quote! { .route(#ws_path_proto, any(#ws_func_name #turbofish)) };
-        let ws_json_route = quote! { .route(#ws_path_json, any(#ws_func_name #turbofish)) };
+        // The encoding is negotiated at the `/ws` route (defaulting to JSON), while the legacy routes set the default
+        let ws_path = format!("{}/ws", path);
+        let mut ws_json_route = quote! { .route(#ws_path, any(#ws_func_name #turbofish)) };
+        let ws_proto_route = if self.config.legacy_ws_routes {
+            let ws_path_json = format!("{}/ws/json", path);
+            let ws_path_proto = format!("{}/ws/proto", path);
+            ws_json_route.extend(quote! { .route(#ws_path_json, any(#ws_func_name #turbofish)) });
+            Some(quote! { .route(#ws_path_proto, any(#ws_func_name #turbofish)) })
+        } else {
+            None
+        };
 
         (func, ws_proto_route, ws_json_route)
     }
 
+    /// Generates the extensions parameter of a WebSocket handler and the statement installing the configured limits
+    /// (unless the router installed its own)
+    fn generate_ws_limits(&self) -> (TokenStream, Option<TokenStream>) {
+        let extensions = &self.value_names.names().2;
+        let mut limits = TokenStream::new();
+        if let Some(max_message_size) = self.config.ws_max_message_size {
+            let max_message_size = Literal::usize_unsuffixed(max_message_size);
+            limits.extend(quote! { .max_message_size(#max_message_size) });
+        }
+        if let Some(max_frame_size) = self.config.ws_max_frame_size {
+            let max_frame_size = Literal::usize_unsuffixed(max_frame_size);
+            limits.extend(quote! { .max_frame_size(#max_frame_size) });
+        }
+
+        if limits.is_empty() {
+            (quote! { #extensions }, None)
+        } else {
+            (
+                quote! { mut #extensions },
+                Some(quote! { #extensions.get_or_insert(tonic2axum::WsLimits::new() #limits); }),
+            )
+        }
+    }
+
+    /// Generates the match arm that dispatches a multiplexed WebSocket call to the method
+    fn generate_ws_mux_arm(&self, service_name: &str, method: &prost_build::Method) -> TokenStream {
+        let state = &self.value_names.names().3;
+        let timeout = self.value_names.timeout();
+        let call = format_ident!("call{}", self.config.value_suffix);
+        let func_name = ident(&method.name);
+        let method_name = &method.proto_name;
+        let default_timeout =
+            Self::default_timeout(&self.method_options(service_name, &method.proto_name));
+
+        let (process_response, timeout_func_name) = if method.server_streaming {
+            (
+                quote! { process_mux_stream_response },
+                quote! { with_stream_timeout },
+            )
+        } else {
+            (quote! { process_mux_response }, quote! { with_timeout })
+        };
+        let response = quote! {
+            tonic2axum::#timeout_func_name(#timeout, #state.#func_name(request)).await
+        };
+        let body = if method.client_streaming {
+            quote! {
+                let (request, sink) = tonic2axum::make_mux_stream_request(#call);
+                let response = #response;
+                tonic2axum::#process_response(response, sink).await;
+            }
+        } else {
+            quote! {
+                if let Some((request, sink)) = tonic2axum::make_mux_request(#call).await {
+                    let response = #response;
+                    tonic2axum::#process_response(response, sink).await;
+                }
+            }
+        };
+
+        quote! {
+            #method_name => {
+                let #timeout = tonic2axum::request_timeout(#call.headers(), #default_timeout);
+                #body
+            }
+        }
+    }
+
+    /// The configured timeout of a method, which clients can only shorten
+    fn default_timeout(method_options: &MethodOptions) -> TokenStream {
+        match method_options.timeout {
+            Some(duration) => {
+                let secs = Literal::u64_unsuffixed(duration.as_secs());
+                let nanos = Literal::u32_unsuffixed(duration.subsec_nanos());
+                quote! { Some(std::time::Duration::new(#secs, #nanos)) }
+            }
+            None => quote! { None },
+        }
+    }
+
+    fn generate_ws_mux_func(
+        &self,
+        service: &prost_build::Service,
+        service_type: &ServiceType,
+        arms: Vec<TokenStream>,
+    ) -> (TokenStream, TokenStream) {
+        let (_, headers, extensions, state) = self.value_names.names();
+        let uri = self.value_names.uri();
+        let ws_upgrade = format_ident!("ws_upgrade{}", self.config.value_suffix);
+        let call = format_ident!("call{}", self.config.value_suffix);
+        let (extensions_param, ws_limits) = self.generate_ws_limits();
+        let state_type = &service_type.state_type_name;
+        let handler_generics = service_type.handler_generics();
+        let turbofish = service_type.handler_route_turbofish();
+        let comment = format!(
+            " Multiplexes calls to the WebSocket enabled methods of the {} service over a single socket",
+            service.name
+        );
+
+        let func = quote! {
+            #[doc = #comment]
+            pub async fn ws_mux #handler_generics(
+                State((#state, _)): State<(#state_type, bool)>,
+                #ws_upgrade: WebSocketUpgrade,
+                #uri: http::Uri,
+                #headers: http::HeaderMap,
+                #extensions_param: http::Extensions,
+            ) -> Response {
+                #ws_limits
+                // Shared by the calls, as generic state types aren't required to be Clone
+                let #state = Arc::new(#state);
+                tonic2axum::upgrade_to_ws_mux(
+                    #ws_upgrade,
+                    #uri,
+                    #headers,
+                    #extensions,
+                    move |#call: tonic2axum::MuxCall| {
+                        let #state = Arc::clone(&#state);
+                        async move {
+                            match #call.method() {
+                                #(#arms)*
+                                method => {
+                                    let status = tonic::Status::unimplemented(format!("Unknown method: {method}"));
+                                    tonic2axum::close_mux_call(#call, status).await;
+                                }
+                            }
+                        }
+                    },
+                )
+                .await
+            }
+        };
+
+        let path = if service.package.is_empty() {
+            format!("/{}/ws", service.proto_name)
+        } else {
+            format!("/{}.{}/ws", service.package, service.proto_name)
+        };
+        let route = quote! { .route(#path, any(ws_mux #turbofish)) };
+
+        (func, route)
+    }
+
     fn generate_router(
         &self,
         service_name: &str,
@@ -553,7 +1170,7 @@ impl Generator {
             ident("Router")
         };
 
-        if ws_proto_routes.is_empty() {
+        if ws_json_routes.is_empty() {
             quote! {
                 #[doc = #comment]
                 pub fn #router_func_name #generics(state: #state_type_name) -> #router_type {
@@ -563,25 +1180,30 @@ impl Generator {
                 }
             }
         } else {
+            // Only the legacy `/ws/proto` routes default to protobuf
+            let mut ws_routers = Vec::with_capacity(2);
+            let ws_proto = if ws_proto_routes.is_empty() {
+                None
+            } else {
+                ws_routers.push(ident("ws_proto"));
+                Some(quote! {
+                    let ws_proto = Router::new()
+                        #(#ws_proto_routes)*
+                        .with_state((state.clone(), true));
+                })
+            };
+            ws_routers.push(ident("ws_json"));
             let merge_ws = if self.config.generate_openapi {
                 // OpenApiRouter::merge requires OpenApiRouter, so convert from Router
-                quote! {
-                    .merge(OpenApiRouter::from(ws_proto))
-                    .merge(OpenApiRouter::from(ws_json))
-                }
+                quote! { #(.merge(OpenApiRouter::from(#ws_routers)))* }
             } else {
-                quote! {
-                    .merge(ws_proto)
-                    .merge(ws_json)
-                }
+                quote! { #(.merge(#ws_routers))* }
             };
 
             quote! {
                 #[doc = #comment]
                 pub fn #router_func_name #generics(state: #state_type_name) -> #router_type {
-                    let ws_proto = Router::new()
-                        #(#ws_proto_routes)*
-                        .with_state((state.clone(), true));
+                    #ws_proto
                     let ws_json = Router::new()
                         #(#ws_json_routes)*
                         .with_state((state.clone(), false));
@@ -21,6 +21,9 @@ pub(crate) struct ValueNames {
     headers: syn::Ident,
     extensions: syn::Ident,
     state: syn::Ident,
+    options: syn::Ident,
+    timeout: syn::Ident,
+    uri: syn::Ident,
 }
 
 impl ValueNames {
@@ -30,12 +33,27 @@ impl ValueNames {
             headers: format_ident!("headers{}", value_suffix),
             extensions: format_ident!("extensions{}", value_suffix),
             state: format_ident!("state{}", value_suffix),
+            options: format_ident!("options{}", value_suffix),
+            timeout: format_ident!("timeout{}", value_suffix),
+            uri: format_ident!("uri{}", value_suffix),
         }
     }
 
     pub fn names(&self) -> (&syn::Ident, &syn::Ident, &syn::Ident, &syn::Ident) {
         (&self.req, &self.headers, &self.extensions, &self.state)
     }
+
+    pub fn options(&self) -> &syn::Ident {
+        &self.options
+    }
+
+    pub fn timeout(&self) -> &syn::Ident {
+        &self.timeout
+    }
+
+    pub fn uri(&self) -> &syn::Ident {
+        &self.uri
+    }
 }
 
 // *** ServiceTypeGenerics and ServiceType ***
@@ -138,6 +156,9 @@ pub(crate) struct FunctionParts {
     pub query_extractor: Option<TokenStream>,
     pub body_extractor: Option<TokenStream>,
     pub request_builder: Option<TokenStream>,
+    pub stream_transform: Option<TokenStream>,
+    // The transform of protobuf streamed messages, when they need one
+    pub proto_transform: Option<TokenStream>,
 }
 
 impl FunctionParts {
@@ -146,34 +167,132 @@ impl FunctionParts {
         method_details: &MethodDetails,
         input_type: &str,
         client_streaming: bool,
-        req_name: &syn::Ident,
+        raw_body: bool,
+        value_names: &ValueNames,
     ) -> Result<Self, Box<dyn Error>> {
+        let req_name = &value_names.req;
         let mut extracted_fields = Vec::new();
 
         let path_extractor =
             Self::make_path_extractor(&method_details.path_fields, &mut extracted_fields);
         let query_extractor =
             Self::make_query_extractor(&method_details.query_str, &mut extracted_fields, req_name);
-        if client_streaming && !extracted_fields.is_empty() {
-            return Err(format!(
-                "Client streaming methods are not supported with query or path parameters: (Method: {})",
-                method_name
-            )
-            .into());
+
+        if client_streaming {
+            // The streamed messages are the body, so path and query values are merged into each of them
+            let mut proto_transform = None;
+            let stream_transform = match (&method_details.body, raw_body) {
+                // Each chunk of a raw body becomes the bytes field of a message
+                (
+                    Some(MessageDetails {
+                        handling: MessageHandling::ExtractSingleField(raw_field),
+                        ..
+                    }),
+                    true,
+                ) => Some(Self::make_raw_stream_transform(
+                    &extracted_fields,
+                    raw_field,
+                    input_type,
+                    req_name,
+                )),
+                // Each chunk of the body becomes an HttpBody message
+                (
+                    Some(MessageDetails {
+                        handling: MessageHandling::HttpBodyRequest(http_body_type),
+                        ..
+                    }),
+                    false,
+                ) => Some(quote! {
+                    |content_type, data| super::#http_body_type {
+                        content_type,
+                        data: data.into(),
+                        extensions: Vec::new(),
+                    }
+                }),
+                (
+                    Some(MessageDetails {
+                        handling: MessageHandling::VerbatimRequest,
+                        ..
+                    }),
+                    false,
+                ) => None,
+                // Protobuf messages are the whole request message, so only the extracted fields are set
+                (
+                    Some(MessageDetails {
+                        type_name,
+                        handling: MessageHandling::ExtractFields(body_fields),
+                    }),
+                    false,
+                ) => {
+                    proto_transform = Some(Self::make_bind_transform(
+                        &extracted_fields,
+                        input_type,
+                        req_name,
+                    ));
+                    Some(Self::make_stream_transform(
+                        &extracted_fields,
+                        type_name,
+                        body_fields,
+                        input_type,
+                        req_name,
+                    ))
+                }
+                // Each streamed message is the (nested message) body field, in either format
+                (
+                    Some(MessageDetails {
+                        type_name,
+                        handling: MessageHandling::ExtractSingleField(body_field),
+                    }),
+                    false,
+                ) => {
+                    let transform = Self::make_field_stream_transform(
+                        &extracted_fields,
+                        type_name,
+                        body_field,
+                        input_type,
+                        req_name,
+                    );
+                    proto_transform = Some(transform.clone());
+                    Some(transform)
+                }
+                _ => {
+                    return Err(format!(
+                        "Client streaming methods require a body (`*` or a field) that isn't an HttpBody field: (Method: {})",
+                        method_name
+                    )
+                    .into());
+                }
+            };
+
+            return Ok(Self {
+                path_extractor,
+                query_extractor,
+                body_extractor: Some(quote! { #req_name: Body, }),
+                request_builder: None,
+                stream_transform,
+                proto_transform,
+            });
         }
-        let body_extractor = Self::make_body_extractor(
-            &method_details.body,
-            &mut extracted_fields,
-            client_streaming,
-            req_name,
-        );
+
+        let body_extractor =
+            Self::make_body_extractor(&method_details.body, &mut extracted_fields, req_name);
+        let http_body_builder = Self::make_http_body_builder(&method_details.body, value_names);
         let request_builder = Self::make_request_builder(&extracted_fields, input_type, req_name);
+        let request_builder = match (http_body_builder, request_builder) {
+            (Some(http_body_builder), Some(request_builder)) => Some(quote! {
+                #http_body_builder
+                #request_builder
+            }),
+            (http_body_builder, request_builder) => http_body_builder.or(request_builder),
+        };
 
         Ok(Self {
             path_extractor,
             query_extractor,
             body_extractor,
             request_builder,
+            stream_transform: None,
+            proto_transform: None,
         })
     }
 
@@ -216,7 +335,10 @@ impl FunctionParts {
                     })
                 }
                 MessageDetails {
-                    handling: MessageHandling::ExtractSingleField(_),
+                    handling:
+                        MessageHandling::ExtractSingleField(_)
+                        | MessageHandling::HttpBodyRequest(_)
+                        | MessageHandling::HttpBodyField(..),
                     ..
                 } => unreachable!(),
                 MessageDetails {
@@ -233,7 +355,6 @@ impl FunctionParts {
     fn make_body_extractor(
         body: &Option<MessageDetails>,
         extracted_fields: &mut Vec<syn::Ident>,
-        client_streaming: bool,
         req_name: &syn::Ident,
     ) -> Option<TokenStream> {
         match body {
@@ -259,20 +380,57 @@ impl FunctionParts {
                 MessageDetails {
                     type_name,
                     handling: MessageHandling::VerbatimRequest,
-                } if client_streaming => Some(quote! {
-                    #req_name: JsonLines<super::#type_name>,
+                } => Some(quote! {
+                    #req_name: Json<super::#type_name>,
                 }),
                 MessageDetails {
-                    type_name,
-                    handling: MessageHandling::VerbatimRequest,
+                    handling: MessageHandling::HttpBodyRequest(_),
+                    ..
                 } => Some(quote! {
-                    #req_name: Json<super::#type_name>,
+                    #req_name: axum::body::Bytes,
                 }),
+                MessageDetails {
+                    handling: MessageHandling::HttpBodyField(field, _),
+                    ..
+                } => {
+                    extracted_fields.push(field.clone());
+                    Some(quote! {
+                        #field: axum::body::Bytes,
+                    })
+                }
             },
             None => None,
         }
     }
 
+    /// Builds the HttpBody (request or field) from the raw body and its content type
+    fn make_http_body_builder(
+        body: &Option<MessageDetails>,
+        value_names: &ValueNames,
+    ) -> Option<TokenStream> {
+        let (name, http_body_type, optional) = match &body.as_ref()?.handling {
+            MessageHandling::HttpBodyRequest(http_body_type) => {
+                (&value_names.req, http_body_type, false)
+            }
+            MessageHandling::HttpBodyField(field, http_body_type) => (field, http_body_type, true),
+            _ => return None,
+        };
+
+        let headers = &value_names.headers;
+        let http_body = quote! {
+            super::#http_body_type {
+                content_type: tonic2axum::request_content_type(&#headers),
+                data: #name.into(),
+                extensions: Vec::new(),
+            }
+        };
+        Some(if optional {
+            quote! { let #name = Some(#http_body); }
+        } else {
+            quote! { let #name = #http_body; }
+        })
+    }
+
     fn make_request_builder(
         extracted_fields: &[syn::Ident],
         input_type: &str,
@@ -288,11 +446,90 @@ impl FunctionParts {
         }
     }
 
+    fn make_stream_transform(
+        extracted_fields: &[syn::Ident],
+        body_type: &syn::Ident,
+        body_fields: &[syn::Ident],
+        input_type: &str,
+        req_name: &syn::Ident,
+    ) -> TokenStream {
+        let type_name = ident(input_type);
+        quote! {
+            move |#req_name: super::#body_type| super::#type_name {
+                #(#extracted_fields: #extracted_fields.clone(),)*
+                #(#body_fields: #req_name.#body_fields,)*
+            }
+        }
+    }
+
+    fn make_field_stream_transform(
+        extracted_fields: &[syn::Ident],
+        body_type: &syn::Ident,
+        body_field: &syn::Ident,
+        input_type: &str,
+        req_name: &syn::Ident,
+    ) -> TokenStream {
+        let type_name = ident(input_type);
+        quote! {
+            move |#req_name: super::#body_type| super::#type_name {
+                #(#extracted_fields: #extracted_fields.clone(),)*
+                #body_field: Some(#req_name),
+            }
+        }
+    }
+
+    fn make_bind_transform(
+        extracted_fields: &[syn::Ident],
+        input_type: &str,
+        req_name: &syn::Ident,
+    ) -> TokenStream {
+        let type_name = ident(input_type);
+        quote! {
+            move |mut #req_name: super::#type_name| {
+                #(#req_name.#extracted_fields = #extracted_fields.clone();)*
+                #req_name
+            }
+        }
+    }
+
+    fn make_raw_stream_transform(
+        extracted_fields: &[syn::Ident],
+        raw_field: &syn::Ident,
+        input_type: &str,
+        req_name: &syn::Ident,
+    ) -> TokenStream {
+        let type_name = ident(input_type);
+        quote! {
+            move |#req_name| super::#type_name {
+                #(#extracted_fields: #extracted_fields.clone(),)*
+                #raw_field: #req_name.into(),
+            }
+        }
+    }
+
+    /// Builds the path extractor of a WebSocket handler and the transform that binds the path values into each request
+    /// message, if there are any path fields
+    pub fn ws_path_binding(
+        path_fields: &[Field],
+        input_type: &str,
+        value_names: &ValueNames,
+    ) -> Option<(TokenStream, TokenStream)> {
+        let mut extracted_fields = Vec::with_capacity(path_fields.len());
+        let path_extractor = Self::make_path_extractor(path_fields, &mut extracted_fields)?;
+
+        let transform = Self::make_bind_transform(&extracted_fields, input_type, &value_names.req);
+        Some((path_extractor, transform))
+    }
+
+    // The whole request comes from either the body or the query string
     pub fn verbatim_request(&self) -> bool {
-        self.body_extractor.is_some() && self.request_builder.is_none()
+        (self.body_extractor.is_some() || self.query_extractor.is_some())
+            && self.request_builder.is_none()
     }
 
     pub fn empty_request(&self) -> bool {
-        self.body_extractor.is_none() && self.request_builder.is_none()
+        self.body_extractor.is_none()
+            && self.query_extractor.is_none()
+            && self.request_builder.is_none()
     }
 }
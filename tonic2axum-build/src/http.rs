@@ -1,4 +1,4 @@
-use std::{borrow::Cow, collections::HashMap, error::Error};
+use std::{borrow::Cow, collections::HashMap, error::Error, time::Duration};
 
 use flexstr::{LocalStr, str::LocalStrRef};
 use proc_macro2::Span;
@@ -10,6 +10,9 @@ use crate::{
 };
 
 const HTTP_EXTENSION_TAG: u32 = 72295728;
+const METHOD_EXTENSION_TAG: u32 = 50001;
+const OCTET_STREAM_CONTENT_TYPE: &str = "application/octet-stream";
+pub(crate) const HTTP_BODY_PROTO_TYPE: &str = ".google.api.HttpBody";
 
 // *** Helper functions ***
 
@@ -32,12 +35,61 @@ fn get_list_field_by_name<'msg>(
     })
 }
 
+fn get_duration(msg: &DynamicMessage) -> Option<Duration> {
+    let seconds = msg.get_field_by_name("seconds")?.as_i64()?;
+    let nanos = msg.get_field_by_name("nanos")?.as_i32()?;
+    Some(Duration::new(
+        seconds.try_into().ok()?,
+        nanos.try_into().ok()?,
+    ))
+}
+
+/// Returns the `google.api.HttpBody` type held by an (optional) message field's type, if it holds one
+fn http_body_type(type_: &syn::Type) -> Option<syn::Type> {
+    let syn::Type::Path(type_path) = type_ else {
+        return None;
+    };
+    let last = type_path.path.segments.last()?;
+    match &last.arguments {
+        // Option<HttpBody>
+        syn::PathArguments::AngleBracketed(args) if last.ident == "Option" => {
+            match args.args.first()? {
+                syn::GenericArgument::Type(inner) => http_body_type(inner),
+                _ => None,
+            }
+        }
+        syn::PathArguments::None if last.ident == "HttpBody" => {
+            let mut segments = type_path.path.segments.iter().rev().skip(1);
+            let is_google_api = segments
+                .next()
+                .is_some_and(|segment| segment.ident == "api")
+                && segments
+                    .next()
+                    .is_some_and(|segment| segment.ident == "google");
+            is_google_api.then(|| type_.clone())
+        }
+        _ => None,
+    }
+}
+
+/// Validate a success status code, which must be a 2xx or 3xx code
+pub(crate) fn validate_status_code(status_code: u32) -> Result<u16, Box<dyn Error>> {
+    match status_code {
+        200..=399 => Ok(status_code as u16),
+        _ => Err(format!("Invalid success status code (must be 2xx or 3xx): {status_code}").into()),
+    }
+}
+
 // *** MessageHandling ***
 
 pub(crate) enum MessageHandling {
     VerbatimRequest,
     ExtractSingleField(syn::Ident),
     ExtractFields(Vec<syn::Ident>),
+    // The request is a `google.api.HttpBody` (of the given type) built from the raw body
+    HttpBodyRequest(syn::Type),
+    // The field is a `google.api.HttpBody` (of the given type) built from the raw body
+    HttpBodyField(syn::Ident, syn::Type),
 }
 
 // *** MessageDetails ***
@@ -67,6 +119,77 @@ pub(crate) struct MethodDetails {
     pub body: Option<MessageDetails>,
 }
 
+// *** MethodOptions ***
+
+/// The options from the `tonic2axum.method` extension (or the equivalent builder settings)
+#[derive(Debug, Clone, Default)]
+pub struct MethodOptions {
+    pub timeout: Option<Duration>,
+    pub status_code: Option<u32>,
+    pub raw_bytes_field: Option<LocalStr>,
+    pub raw_content_type: Option<LocalStr>,
+}
+
+impl MethodOptions {
+    fn parse(options_msg: &DynamicMessage) -> Self {
+        let timeout = if options_msg.has_field_by_name("timeout") {
+            options_msg
+                .get_field_by_name("timeout")
+                .and_then(|value| get_duration(value.as_message()?))
+        } else {
+            None
+        };
+        let status_code = if options_msg.has_field_by_name("status_code") {
+            options_msg
+                .get_field_by_name("status_code")
+                .and_then(|value| value.as_u32())
+        } else {
+            None
+        };
+
+        let get_str_option = |name| {
+            if options_msg.has_field_by_name(name) {
+                get_str_field_by_name(options_msg, name)
+                    .map(|value| LocalStrRef::from(value).into_owned())
+            } else {
+                None
+            }
+        };
+
+        Self {
+            timeout,
+            status_code,
+            raw_bytes_field: get_str_option("raw_bytes_field"),
+            raw_content_type: get_str_option("raw_content_type"),
+        }
+    }
+
+    /// The content type of the raw body, if the method has a raw bytes field
+    pub fn raw_content_type(&self) -> Option<&str> {
+        self.raw_bytes_field.as_ref().map(|_| {
+            self.raw_content_type
+                .as_deref()
+                .unwrap_or(OCTET_STREAM_CONTENT_TYPE)
+        })
+    }
+
+    /// Fill in any options not set in self from the other options
+    pub fn or(&self, other: &Self) -> Self {
+        Self {
+            timeout: self.timeout.or(other.timeout),
+            status_code: self.status_code.or(other.status_code),
+            raw_bytes_field: self
+                .raw_bytes_field
+                .clone()
+                .or_else(|| other.raw_bytes_field.clone()),
+            raw_content_type: self
+                .raw_content_type
+                .clone()
+                .or_else(|| other.raw_content_type.clone()),
+        }
+    }
+}
+
 // *** HttpOption ***
 
 #[derive(Debug, Clone)]
@@ -74,6 +197,7 @@ pub struct HttpOption {
     pub method: LocalStr,
     pub pattern: LocalStr,
     pub body: Option<LocalStr>,
+    pub options: MethodOptions,
 }
 
 impl HttpOption {
@@ -83,6 +207,15 @@ impl HttpOption {
         self.pattern.clone()
     }
 
+    /// The same rule as a GET request, where every field not bound by the path comes from the query string
+    pub fn as_get(&self) -> Self {
+        Self {
+            method: LocalStrRef::from_borrowed("get").into_owned(),
+            body: None,
+            ..self.clone()
+        }
+    }
+
     fn parse_pattern(&self, message: &mut Message) -> Result<Vec<Field>, Box<dyn Error>> {
         let mut path_fields = Vec::new();
 
@@ -104,10 +237,33 @@ impl HttpOption {
     fn parse_body(
         &self,
         message: &mut Message,
+        raw_body: Option<&str>,
         existing_messages: &ExistingMessages,
         new_messages: &mut NewMessages,
         config: &GeneratorConfig,
     ) -> Result<Option<MessageDetails>, Box<dyn Error>> {
+        // A raw body only holds the bytes field, so no body message is needed
+        if let Some(raw_field) = raw_body {
+            let is_raw_field = match &self.body {
+                Some(body) if body == "*" => {
+                    matches!(message.fields(), [field] if field.name == raw_field)
+                }
+                Some(body) => body == raw_field,
+                None => false,
+            };
+            if !is_raw_field {
+                return Err(format!("The body must be the raw bytes field: {raw_field}").into());
+            }
+
+            let field = message
+                .remove_field(raw_field)
+                .ok_or(format!("Field not found: {}", raw_field))?;
+            return Ok(Some(MessageDetails::new(
+                &message.name,
+                MessageHandling::ExtractSingleField(field.ident),
+            )));
+        }
+
         match &self.body {
             // Wildcard body
             Some(body) if body == "*" => {
@@ -145,6 +301,14 @@ impl HttpOption {
                     .remove_field(body.as_ref())
                     .ok_or(format!("Field not found: {}", body))?;
 
+                // Is the field an HttpBody? If so, it is built from the raw body
+                if let Some(http_body_type) = http_body_type(&field.type_) {
+                    return Ok(Some(MessageDetails::new(
+                        &message.name,
+                        MessageHandling::HttpBodyField(field.ident, http_body_type),
+                    )));
+                }
+
                 // Is the field a nested message?
                 let type_name = field.type_name.as_ref();
                 match existing_messages.get_message(type_name) {
@@ -188,8 +352,8 @@ impl HttpOption {
         if message.is_empty() {
             // No fields left, so no query struct is needed
             None
-        } else if message.is_intact() {
-            // Use the message itself
+        } else if message.is_intact() && !config.generate_openapi {
+            // Use the message itself (OpenAPI needs a query struct deriving `IntoParams`)
             Some(MessageDetails::new(
                 &message.name,
                 MessageHandling::VerbatimRequest,
@@ -214,13 +378,14 @@ impl HttpOption {
     pub fn parse(
         &self,
         message: &Message,
+        raw_body: Option<&str>,
         message_fields: &ExistingMessages,
         new_messages: &mut NewMessages,
         config: &GeneratorConfig,
     ) -> Result<MethodDetails, Box<dyn Error>> {
         let mut message = message.clone();
         let path_fields = self.parse_pattern(&mut message)?;
-        let body = self.parse_body(&mut message, message_fields, new_messages, config)?;
+        let body = self.parse_body(&mut message, raw_body, message_fields, new_messages, config)?;
         let query_str = self.parse_query_str(&mut message, new_messages, config);
 
         Ok(MethodDetails {
@@ -231,6 +396,30 @@ impl HttpOption {
             body,
         })
     }
+
+    /// Parse the rule of a method whose request is a `google.api.HttpBody` (of the given type), which can only be
+    /// populated from the whole body
+    pub fn parse_http_body(
+        &self,
+        http_body_type: syn::Type,
+    ) -> Result<MethodDetails, Box<dyn Error>> {
+        if self.pattern.contains('{') || self.body.as_deref() != Some("*") {
+            return Err(
+                "HttpBody requests require a wildcard (`*`) body and no path variables".into(),
+            );
+        }
+
+        Ok(MethodDetails {
+            method: self.method.clone(),
+            path: self.build_path(),
+            query_str: None,
+            path_fields: Vec::new(),
+            body: Some(MessageDetails::new(
+                "HttpBody",
+                MessageHandling::HttpBodyRequest(http_body_type),
+            )),
+        })
+    }
 }
 
 // *** HttpOptions ***
@@ -242,26 +431,6 @@ pub struct HttpOptions(
 );
 
 impl HttpOptions {
-    pub fn parse(
-        &mut self,
-        service_name: &str,
-        method_name: &str,
-        message: &Message,
-        existing_messages: &ExistingMessages,
-        new_messages: &mut NewMessages,
-        config: &GeneratorConfig,
-    ) -> Result<Option<MethodDetails>, Box<dyn Error>> {
-        match self.get_http_options(service_name, method_name) {
-            Some(option) => Ok(Some(option.parse(
-                message,
-                existing_messages,
-                new_messages,
-                config,
-            )?)),
-            None => Ok(None),
-        }
-    }
-
     pub fn parse_http_options(
         &mut self,
         fds_dynamic: &DynamicMessage,
@@ -309,39 +478,52 @@ impl HttpOptions {
     fn extract_options(method_msg: &DynamicMessage) -> Option<HttpOption> {
         let options_msg = method_msg.get_field_by_name("options")?;
         let options_msg = options_msg.as_message()?;
+        let mut http_option = None;
+        let mut method_options = MethodOptions::default();
 
         // Find specifically decoded extensions in prost-reflect 0.16
         for (ext_desc, ext_value) in options_msg.extensions() {
-            if ext_desc.number() == HTTP_EXTENSION_TAG {
-                let http_rule = ext_value.as_message()?;
-                let mut method = LocalStr::empty();
-                let mut pattern = LocalStr::empty();
-                let mut body = None;
-
-                for (field, value) in http_rule.fields() {
-                    match field.name() {
-                        "get" | "post" | "put" | "delete" | "patch" => {
-                            method = LocalStrRef::from_borrowed(field.name()).into_owned();
-                            pattern = LocalStrRef::from_borrowed(value.as_str()?).into_owned();
-                        }
-                        "body" => {
-                            body = Some(LocalStrRef::from_borrowed(value.as_str()?).into_owned())
-                        }
-                        _ => {}
-                    }
+            match ext_desc.number() {
+                HTTP_EXTENSION_TAG => http_option = Self::parse_http_rule(ext_value.as_message()?),
+                METHOD_EXTENSION_TAG => {
+                    method_options = MethodOptions::parse(ext_value.as_message()?)
                 }
+                _ => {}
+            }
+        }
+
+        http_option.map(|http_option| HttpOption {
+            options: method_options,
+            ..http_option
+        })
+    }
+
+    fn parse_http_rule(http_rule: &DynamicMessage) -> Option<HttpOption> {
+        let mut method = LocalStr::empty();
+        let mut pattern = LocalStr::empty();
+        let mut body = None;
 
-                if !method.is_empty() {
-                    return Some(HttpOption {
-                        method,
-                        pattern,
-                        body,
-                    });
+        for (field, value) in http_rule.fields() {
+            match field.name() {
+                "get" | "post" | "put" | "delete" | "patch" => {
+                    method = LocalStrRef::from_borrowed(field.name()).into_owned();
+                    pattern = LocalStrRef::from_borrowed(value.as_str()?).into_owned();
                 }
+                "body" => body = Some(LocalStrRef::from_borrowed(value.as_str()?).into_owned()),
+                _ => {}
             }
         }
 
-        None
+        if method.is_empty() {
+            None
+        } else {
+            Some(HttpOption {
+                method,
+                pattern,
+                body,
+                options: MethodOptions::default(),
+            })
+        }
     }
 
     pub fn get_path(&self, service_name: &str, method_name: &str) -> Option<LocalStr> {
@@ -349,7 +531,16 @@ impl HttpOptions {
             .map(|o| o.build_path())
     }
 
-    fn get_http_options(&self, service_name: &str, method_name: &str) -> Option<&HttpOption> {
+    pub fn get_method_options(
+        &self,
+        service_name: &str,
+        method_name: &str,
+    ) -> Option<&MethodOptions> {
+        self.get_http_options(service_name, method_name)
+            .map(|o| &o.options)
+    }
+
+    pub fn get_http_options(&self, service_name: &str, method_name: &str) -> Option<&HttpOption> {
         self.0.get(service_name)?.get(method_name)
     }
 }
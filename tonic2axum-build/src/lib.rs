@@ -6,3 +6,10 @@ mod message;
 pub use builder::{Builder, OpenApiSecurity};
 pub use prost_build::Config as ProstConfig;
 pub use tonic_prost_build::{Builder as TonicBuilder, configure as configure_tonic};
+
+/// The include directory holding `tonic2axum/options.proto`, which defines the `tonic2axum.method` option. Add it to
+/// the includes passed to [Builder::compile] to use the option in proto files.
+///
+/// > NOTE: The option uses extension number 50001 (from the private use range), so no other method option used by the
+/// > same protos may use that number.
+pub const PROTO_INCLUDE_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/proto");
@@ -322,10 +322,19 @@ impl NewMessages {
     }
 
     pub fn body_messages(&self) -> impl Iterator<Item = &Message> {
-        self.body_messages.values().flatten()
+        Self::sorted_messages(&self.body_messages)
     }
 
     pub fn query_messages(&self) -> impl Iterator<Item = &Message> {
-        self.query_messages.values().flatten()
+        Self::sorted_messages(&self.query_messages)
+    }
+
+    // Sorted by input message name so the generated code is deterministic
+    fn sorted_messages(
+        messages: &HashMap<LocalStr, Vec<Message>>,
+    ) -> impl Iterator<Item = &Message> {
+        let mut messages: Vec<_> = messages.iter().collect();
+        messages.sort_by_key(|(name, _)| *name);
+        messages.into_iter().flat_map(|(_, messages)| messages)
     }
 }
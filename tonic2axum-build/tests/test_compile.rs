@@ -1,7 +1,9 @@
 #[cfg(not(feature = "cleanup-markdown"))]
 mod test_compile {
+    use std::time::Duration;
     use tempfile::tempdir;
-    use tonic2axum_build::{Builder, OpenApiSecurity, ProstConfig};
+
+    use tonic2axum_build::{Builder, OpenApiSecurity, PROTO_INCLUDE_DIR, ProstConfig};
 
     #[test]
     fn test_compile_with_web_sockets() {
@@ -16,8 +18,16 @@ mod test_compile {
             .file_descriptor_set_path(dir.path().join("fds.bin"))
             .custom_state_type("StreamingTest", "crate::StreamingTest")
             .unwrap()
+            .method_timeout("StreamingTest", "UnaryCall", Duration::from_secs(5))
+            .unwrap()
+            .method_timeout("StreamingTest", "BidiStream", Duration::from_secs(60))
+            .unwrap()
             .generate_web_sockets(true)
-            .compile(&["tests/proto/test_ws/v1/test_ws.proto"], &["tests/proto"])
+            .unary_web_sockets(true)
+            .compile(
+                &["tests/proto/test_ws/v1/test_ws.proto"],
+                &["tests/proto", PROTO_INCLUDE_DIR],
+            )
             .unwrap();
 
         let actual = std::fs::read_to_string(dir.path().join("test_ws.v1.rs")).unwrap();
@@ -36,7 +46,14 @@ mod test_compile {
         Builder::new()
             .prost_config(config)
             .file_descriptor_set_path(dir.path().join("fds.bin"))
-            .compile(&["tests/proto/test/v1/test.proto"], &["tests/proto"])
+            .method_timeout("Test2", "DoTest", Duration::from_millis(1500))
+            .unwrap()
+            .method_status_code("Test2", "DoTest", 202)
+            .unwrap()
+            .compile(
+                &["tests/proto/test/v1/test.proto"],
+                &["tests/proto", PROTO_INCLUDE_DIR],
+            )
             .unwrap();
 
         let actual = std::fs::read_to_string(dir.path().join("test.v1.rs")).unwrap();
@@ -59,7 +76,22 @@ mod test_compile {
             .unwrap()
             .generate_openapi(true)
             .generate_web_sockets(true)
-            .compile(&["tests/proto/test_ws/v1/test_ws.proto"], &["tests/proto"])
+            .legacy_ws_routes(false)
+            .generate_ws_mux(true)
+            .ws_max_message_size(1 << 20)
+            .generate_sse(true)
+            .stream_envelope(true)
+            .protobuf_streams(true)
+            .method_raw_bytes("StreamingTest", "Download", "data")
+            .unwrap()
+            .method_raw_bytes("StreamingTest", "UploadFile", "data")
+            .unwrap()
+            .method_raw_content_type("StreamingTest", "Download", "application/pdf")
+            .unwrap()
+            .compile(
+                &["tests/proto/test_ws/v1/test_ws.proto"],
+                &["tests/proto", PROTO_INCLUDE_DIR],
+            )
             .unwrap();
 
         let actual = std::fs::read_to_string(dir.path().join("test_ws.v1.rs")).unwrap();
@@ -81,7 +113,14 @@ mod test_compile {
             .generic_state_type("StreamingTest")
             .unwrap()
             .generate_web_sockets(true)
-            .compile(&["tests/proto/test_ws/v1/test_ws.proto"], &["tests/proto"])
+            .generate_ws_mux(true)
+            .unary_web_sockets(true)
+            .json_array_streams(true)
+            .collect_streams(true)
+            .compile(
+                &["tests/proto/test_ws/v1/test_ws.proto"],
+                &["tests/proto", PROTO_INCLUDE_DIR],
+            )
             .unwrap();
 
         let actual = std::fs::read_to_string(dir.path().join("test_ws.v1.rs")).unwrap();
@@ -98,11 +137,15 @@ mod test_compile {
             .out_dir(dir.path())
             .type_attribute(".", "#[derive(serde::Serialize, serde::Deserialize)]");
         Builder::new()
+            .empty_no_content(true)
             .prost_config(config)
             .file_descriptor_set_path(dir.path().join("fds.bin"))
             .generate_openapi(true)
             .openapi_security(OpenApiSecurity::AllServices("Bearer"))
-            .compile(&["tests/proto/test/v1/test.proto"], &["tests/proto"])
+            .compile(
+                &["tests/proto/test/v1/test.proto"],
+                &["tests/proto", PROTO_INCLUDE_DIR],
+            )
             .unwrap();
 
         let actual = std::fs::read_to_string(dir.path().join("test.v1.rs")).unwrap();
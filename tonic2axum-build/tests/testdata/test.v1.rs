@@ -29,6 +29,28 @@ pub struct TestReply {
     #[prost(string, tag = "1")]
     pub message: ::prost::alloc::string::String,
 }
+/// The request message for getting an image.
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct GetImageRequest {
+    /// The name of the image
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+}
+/// The request message for a webhook.
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct WebhookRequest {
+    /// The source of the webhook
+    #[prost(string, tag = "1")]
+    pub source: ::prost::alloc::string::String,
+    /// The webhook payload
+    #[prost(message, optional, tag = "2")]
+    pub payload: ::core::option::Option<super::super::google::api::HttpBody>,
+    /// The webhook signature
+    #[prost(string, tag = "3")]
+    pub signature: ::prost::alloc::string::String,
+}
 #[derive(serde::Serialize, serde::Deserialize)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
@@ -159,6 +181,25 @@ pub mod test1_client {
             req.extensions_mut().insert(GrpcMethod::new("test.v1.Test1", "DoTest"));
             self.inner.unary(req, path, codec).await
         }
+        /// Deletes a test
+        pub async fn delete_test(
+            &mut self,
+            request: impl tonic::IntoRequest<super::TestRequest>,
+        ) -> std::result::Result<tonic::Response<()>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic_prost::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/test.v1.Test1/DeleteTest");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("test.v1.Test1", "DeleteTest"));
+            self.inner.unary(req, path, codec).await
+        }
     }
 }
 /// Generated server implementations.
@@ -179,6 +220,11 @@ pub mod test1_server {
             &self,
             request: tonic::Request<super::TestRequest>,
         ) -> std::result::Result<tonic::Response<super::TestReply>, tonic::Status>;
+        /// Deletes a test
+        async fn delete_test(
+            &self,
+            request: tonic::Request<super::TestRequest>,
+        ) -> std::result::Result<tonic::Response<()>, tonic::Status>;
     }
     /// The test service definition.
     #[derive(Debug)]
@@ -300,6 +346,49 @@ pub mod test1_server {
                     };
                     Box::pin(fut)
                 }
+                "/test.v1.Test1/DeleteTest" => {
+                    #[allow(non_camel_case_types)]
+                    struct DeleteTestSvc<T: Test1>(pub Arc<T>);
+                    impl<T: Test1> tonic::server::UnaryService<super::TestRequest>
+                    for DeleteTestSvc<T> {
+                        type Response = ();
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::TestRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Test1>::delete_test(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = DeleteTestSvc(inner);
+                        let codec = tonic_prost::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 _ => {
                     Box::pin(async move {
                         let mut response = http::Response::new(
@@ -617,12 +706,454 @@ pub mod test2_server {
         const NAME: &'static str = SERVICE_NAME;
     }
 }
+/// Generated client implementations.
+pub mod files_client {
+    #![allow(
+        unused_variables,
+        dead_code,
+        missing_docs,
+        clippy::wildcard_imports,
+        clippy::let_unit_value,
+    )]
+    use tonic::codegen::*;
+    use tonic::codegen::http::Uri;
+    /// The file service definition.
+    #[derive(Debug, Clone)]
+    pub struct FilesClient<T> {
+        inner: tonic::client::Grpc<T>,
+    }
+    impl<T> FilesClient<T>
+    where
+        T: tonic::client::GrpcService<tonic::body::Body>,
+        T::Error: Into<StdError>,
+        T::ResponseBody: Body<Data = Bytes> + std::marker::Send + 'static,
+        <T::ResponseBody as Body>::Error: Into<StdError> + std::marker::Send,
+    {
+        pub fn new(inner: T) -> Self {
+            let inner = tonic::client::Grpc::new(inner);
+            Self { inner }
+        }
+        pub fn with_origin(inner: T, origin: Uri) -> Self {
+            let inner = tonic::client::Grpc::with_origin(inner, origin);
+            Self { inner }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> FilesClient<InterceptedService<T, F>>
+        where
+            F: tonic::service::Interceptor,
+            T::ResponseBody: Default,
+            T: tonic::codegen::Service<
+                http::Request<tonic::body::Body>,
+                Response = http::Response<
+                    <T as tonic::client::GrpcService<tonic::body::Body>>::ResponseBody,
+                >,
+            >,
+            <T as tonic::codegen::Service<
+                http::Request<tonic::body::Body>,
+            >>::Error: Into<StdError> + std::marker::Send + std::marker::Sync,
+        {
+            FilesClient::new(InterceptedService::new(inner, interceptor))
+        }
+        /// Compress requests with the given encoding.
+        ///
+        /// This requires the server to support it otherwise it might respond with an
+        /// error.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.inner = self.inner.send_compressed(encoding);
+            self
+        }
+        /// Enable decompressing responses.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.inner = self.inner.accept_compressed(encoding);
+            self
+        }
+        /// Limits the maximum size of a decoded message.
+        ///
+        /// Default: `4MB`
+        #[must_use]
+        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+            self.inner = self.inner.max_decoding_message_size(limit);
+            self
+        }
+        /// Limits the maximum size of an encoded message.
+        ///
+        /// Default: `usize::MAX`
+        #[must_use]
+        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+            self.inner = self.inner.max_encoding_message_size(limit);
+            self
+        }
+        /// Gets an image
+        pub async fn get_image(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetImageRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::super::google::api::HttpBody>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic_prost::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/test.v1.Files/GetImage");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("test.v1.Files", "GetImage"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Uploads an image
+        pub async fn upload_image(
+            &mut self,
+            request: impl tonic::IntoRequest<super::super::super::google::api::HttpBody>,
+        ) -> std::result::Result<tonic::Response<super::TestReply>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic_prost::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/test.v1.Files/UploadImage",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("test.v1.Files", "UploadImage"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Receives a webhook
+        pub async fn webhook(
+            &mut self,
+            request: impl tonic::IntoRequest<super::WebhookRequest>,
+        ) -> std::result::Result<tonic::Response<()>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic_prost::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/test.v1.Files/Webhook");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("test.v1.Files", "Webhook"));
+            self.inner.unary(req, path, codec).await
+        }
+    }
+}
+/// Generated server implementations.
+pub mod files_server {
+    #![allow(
+        unused_variables,
+        dead_code,
+        missing_docs,
+        clippy::wildcard_imports,
+        clippy::let_unit_value,
+    )]
+    use tonic::codegen::*;
+    /// Generated trait containing gRPC methods that should be implemented for use with FilesServer.
+    #[async_trait]
+    pub trait Files: std::marker::Send + std::marker::Sync + 'static {
+        /// Gets an image
+        async fn get_image(
+            &self,
+            request: tonic::Request<super::GetImageRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::super::google::api::HttpBody>,
+            tonic::Status,
+        >;
+        /// Uploads an image
+        async fn upload_image(
+            &self,
+            request: tonic::Request<super::super::super::google::api::HttpBody>,
+        ) -> std::result::Result<tonic::Response<super::TestReply>, tonic::Status>;
+        /// Receives a webhook
+        async fn webhook(
+            &self,
+            request: tonic::Request<super::WebhookRequest>,
+        ) -> std::result::Result<tonic::Response<()>, tonic::Status>;
+    }
+    /// The file service definition.
+    #[derive(Debug)]
+    pub struct FilesServer<T> {
+        inner: Arc<T>,
+        accept_compression_encodings: EnabledCompressionEncodings,
+        send_compression_encodings: EnabledCompressionEncodings,
+        max_decoding_message_size: Option<usize>,
+        max_encoding_message_size: Option<usize>,
+    }
+    impl<T> FilesServer<T> {
+        pub fn new(inner: T) -> Self {
+            Self::from_arc(Arc::new(inner))
+        }
+        pub fn from_arc(inner: Arc<T>) -> Self {
+            Self {
+                inner,
+                accept_compression_encodings: Default::default(),
+                send_compression_encodings: Default::default(),
+                max_decoding_message_size: None,
+                max_encoding_message_size: None,
+            }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> InterceptedService<Self, F>
+        where
+            F: tonic::service::Interceptor,
+        {
+            InterceptedService::new(Self::new(inner), interceptor)
+        }
+        /// Enable decompressing requests with the given encoding.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.accept_compression_encodings.enable(encoding);
+            self
+        }
+        /// Compress responses with the given encoding, if the client supports it.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.send_compression_encodings.enable(encoding);
+            self
+        }
+        /// Limits the maximum size of a decoded message.
+        ///
+        /// Default: `4MB`
+        #[must_use]
+        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+            self.max_decoding_message_size = Some(limit);
+            self
+        }
+        /// Limits the maximum size of an encoded message.
+        ///
+        /// Default: `usize::MAX`
+        #[must_use]
+        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+            self.max_encoding_message_size = Some(limit);
+            self
+        }
+    }
+    impl<T, B> tonic::codegen::Service<http::Request<B>> for FilesServer<T>
+    where
+        T: Files,
+        B: Body + std::marker::Send + 'static,
+        B::Error: Into<StdError> + std::marker::Send + 'static,
+    {
+        type Response = http::Response<tonic::body::Body>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+        fn poll_ready(
+            &mut self,
+            _cx: &mut Context<'_>,
+        ) -> Poll<std::result::Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            match req.uri().path() {
+                "/test.v1.Files/GetImage" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetImageSvc<T: Files>(pub Arc<T>);
+                    impl<T: Files> tonic::server::UnaryService<super::GetImageRequest>
+                    for GetImageSvc<T> {
+                        type Response = super::super::super::google::api::HttpBody;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetImageRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Files>::get_image(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = GetImageSvc(inner);
+                        let codec = tonic_prost::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/test.v1.Files/UploadImage" => {
+                    #[allow(non_camel_case_types)]
+                    struct UploadImageSvc<T: Files>(pub Arc<T>);
+                    impl<
+                        T: Files,
+                    > tonic::server::UnaryService<
+                        super::super::super::google::api::HttpBody,
+                    > for UploadImageSvc<T> {
+                        type Response = super::TestReply;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::super::google::api::HttpBody,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Files>::upload_image(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = UploadImageSvc(inner);
+                        let codec = tonic_prost::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/test.v1.Files/Webhook" => {
+                    #[allow(non_camel_case_types)]
+                    struct WebhookSvc<T: Files>(pub Arc<T>);
+                    impl<T: Files> tonic::server::UnaryService<super::WebhookRequest>
+                    for WebhookSvc<T> {
+                        type Response = ();
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::WebhookRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Files>::webhook(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = WebhookSvc(inner);
+                        let codec = tonic_prost::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                _ => {
+                    Box::pin(async move {
+                        let mut response = http::Response::new(
+                            tonic::body::Body::default(),
+                        );
+                        let headers = response.headers_mut();
+                        headers
+                            .insert(
+                                tonic::Status::GRPC_STATUS,
+                                (tonic::Code::Unimplemented as i32).into(),
+                            );
+                        headers
+                            .insert(
+                                http::header::CONTENT_TYPE,
+                                tonic::metadata::GRPC_CONTENT_TYPE,
+                            );
+                        Ok(response)
+                    })
+                }
+            }
+        }
+    }
+    impl<T> Clone for FilesServer<T> {
+        fn clone(&self) -> Self {
+            let inner = self.inner.clone();
+            Self {
+                inner,
+                accept_compression_encodings: self.accept_compression_encodings,
+                send_compression_encodings: self.send_compression_encodings,
+                max_decoding_message_size: self.max_decoding_message_size,
+                max_encoding_message_size: self.max_encoding_message_size,
+            }
+        }
+    }
+    /// Generated gRPC service name
+    pub const SERVICE_NAME: &str = "test.v1.Files";
+    impl<T> tonic::server::NamedService for FilesServer<T> {
+        const NAME: &'static str = SERVICE_NAME;
+    }
+}
 /// The request message containing the test data.
 #[derive(serde::Deserialize)]
 pub struct TestRequestBody__ {
     /// tester
     pub tester: ::core::option::Option<Nested>,
 }
+/// The request message containing the test data.
+#[derive(serde::Deserialize)]
+pub struct TestRequestQuery__ {
+    /// test type
+    pub test_type: i32,
+    /// tester
+    pub tester: ::core::option::Option<Nested>,
+}
+/// The request message for a webhook.
+#[derive(serde::Deserialize)]
+pub struct WebhookRequestQuery__ {
+    /// The webhook signature
+    pub signature: ::prost::alloc::string::String,
+}
 /// Generated axum handlers and router.
 pub mod test1_axum {
     #![allow(unused_imports)]
@@ -646,13 +1177,46 @@ pub mod test1_axum {
             test_type,
             tester,
         };
+        let options__ = tonic2axum::ResponseOptions::from_extensions(&extensions__)
+            .status_code(http::StatusCode::from_u16(201).unwrap());
+        let timeout__ = tonic2axum::request_timeout(
+            &headers__,
+            Some(std::time::Duration::new(30, 0)),
+        );
+        let req__ = tonic2axum::make_request(headers__, extensions__, req__);
+        tonic2axum::make_response_with(
+            tonic2axum::with_timeout(timeout__, state__.do_test(req__)).await,
+            options__,
+        )
+    }
+    /// Deletes a test
+    pub async fn delete_test(
+        State(state__): State<Arc<dyn super::test1_server::Test1>>,
+        Path(data): Path<::prost::alloc::string::String>,
+        Query(
+            super::TestRequestQuery__ { test_type, tester },
+        ): Query<super::TestRequestQuery__>,
+        headers__: http::HeaderMap,
+        extensions__: http::Extensions,
+    ) -> http::Response<Body> {
+        let req__ = super::TestRequest {
+            data,
+            test_type,
+            tester,
+        };
+        let options__ = tonic2axum::ResponseOptions::from_extensions(&extensions__);
+        let timeout__ = tonic2axum::request_timeout(&headers__, None);
         let req__ = tonic2axum::make_request(headers__, extensions__, req__);
-        tonic2axum::make_response(state__.do_test(req__).await)
+        tonic2axum::make_response_with(
+            tonic2axum::with_timeout(timeout__, state__.delete_test(req__)).await,
+            options__,
+        )
     }
     /// Axum router for the Test1 service
     pub fn make_router(state: Arc<dyn super::test1_server::Test1>) -> Router {
         Router::new()
             .route("/test/{data}/testing/{test_type}", post(do_test))
+            .route("/test/{data}", delete(delete_test))
             .with_state(state)
     }
 }
@@ -678,8 +1242,17 @@ pub mod test2_axum {
             test_type,
             tester,
         };
+        let options__ = tonic2axum::ResponseOptions::from_extensions(&extensions__)
+            .status_code(http::StatusCode::from_u16(202).unwrap());
+        let timeout__ = tonic2axum::request_timeout(
+            &headers__,
+            Some(std::time::Duration::new(1, 500000000)),
+        );
         let req__ = tonic2axum::make_request(headers__, extensions__, req__);
-        tonic2axum::make_response(state__.do_test(req__).await)
+        tonic2axum::make_response_with(
+            tonic2axum::with_timeout(timeout__, state__.do_test(req__)).await,
+            options__,
+        )
     }
     /// Axum router for the Test2 service
     pub fn make_router(state: Arc<dyn super::test2_server::Test2>) -> Router {
@@ -688,3 +1261,90 @@ pub mod test2_axum {
             .with_state(state)
     }
 }
+/// Generated axum handlers and router.
+pub mod files_axum {
+    #![allow(unused_imports)]
+    use std::sync::Arc;
+    use axum::Json;
+    use axum::body::Body;
+    use axum::extract::{Path, Query, State};
+    use axum::routing::{any, get, post, put, delete, patch};
+    use axum::Router;
+    /// Gets an image
+    pub async fn get_image(
+        State(state__): State<Arc<dyn super::files_server::Files>>,
+        Path(name): Path<::prost::alloc::string::String>,
+        headers__: http::HeaderMap,
+        extensions__: http::Extensions,
+    ) -> http::Response<Body> {
+        let req__ = super::GetImageRequest { name };
+        let options__ = tonic2axum::ResponseOptions::from_extensions(&extensions__);
+        let timeout__ = tonic2axum::request_timeout(&headers__, None);
+        let req__ = tonic2axum::make_request(headers__, extensions__, req__);
+        tonic2axum::make_http_body_response(
+            tonic2axum::with_timeout(timeout__, state__.get_image(req__)).await,
+            options__,
+            |req__: super::super::super::google::api::HttpBody| (
+                req__.content_type,
+                req__.data.into(),
+            ),
+        )
+    }
+    /// Uploads an image
+    pub async fn upload_image(
+        State(state__): State<Arc<dyn super::files_server::Files>>,
+        headers__: http::HeaderMap,
+        extensions__: http::Extensions,
+        req__: axum::body::Bytes,
+    ) -> http::Response<Body> {
+        let req__ = super::super::super::google::api::HttpBody {
+            content_type: tonic2axum::request_content_type(&headers__),
+            data: req__.into(),
+            extensions: Vec::new(),
+        };
+        let options__ = tonic2axum::ResponseOptions::from_extensions(&extensions__);
+        let timeout__ = tonic2axum::request_timeout(&headers__, None);
+        let req__ = tonic2axum::make_request(headers__, extensions__, req__);
+        tonic2axum::make_response_with(
+            tonic2axum::with_timeout(timeout__, state__.upload_image(req__)).await,
+            options__,
+        )
+    }
+    /// Receives a webhook
+    pub async fn webhook(
+        State(state__): State<Arc<dyn super::files_server::Files>>,
+        Path(source): Path<::prost::alloc::string::String>,
+        Query(
+            super::WebhookRequestQuery__ { signature },
+        ): Query<super::WebhookRequestQuery__>,
+        headers__: http::HeaderMap,
+        extensions__: http::Extensions,
+        payload: axum::body::Bytes,
+    ) -> http::Response<Body> {
+        let payload = Some(super::super::super::google::api::HttpBody {
+            content_type: tonic2axum::request_content_type(&headers__),
+            data: payload.into(),
+            extensions: Vec::new(),
+        });
+        let req__ = super::WebhookRequest {
+            source,
+            signature,
+            payload,
+        };
+        let options__ = tonic2axum::ResponseOptions::from_extensions(&extensions__);
+        let timeout__ = tonic2axum::request_timeout(&headers__, None);
+        let req__ = tonic2axum::make_request(headers__, extensions__, req__);
+        tonic2axum::make_response_with(
+            tonic2axum::with_timeout(timeout__, state__.webhook(req__)).await,
+            options__,
+        )
+    }
+    /// Axum router for the Files service
+    pub fn make_router(state: Arc<dyn super::files_server::Files>) -> Router {
+        Router::new()
+            .route("/images/{name}", get(get_image))
+            .route("/images", post(upload_image))
+            .route("/webhooks/{source}", post(webhook))
+            .with_state(state)
+    }
+}
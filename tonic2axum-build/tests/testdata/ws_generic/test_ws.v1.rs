@@ -7,6 +7,50 @@ pub struct StreamRequest {
     #[prost(string, tag = "1")]
     pub data: ::prost::alloc::string::String,
 }
+/// The upload request message.
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct UploadRequest {
+    /// The bucket to upload to
+    #[prost(string, tag = "1")]
+    pub bucket: ::prost::alloc::string::String,
+    /// The data to upload
+    #[prost(string, tag = "2")]
+    pub data: ::prost::alloc::string::String,
+}
+/// The append request message.
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct AppendRequest {
+    /// The log to append to
+    #[prost(string, tag = "1")]
+    pub log: ::prost::alloc::string::String,
+    /// The tag of the entries
+    #[prost(string, tag = "2")]
+    pub tag: ::prost::alloc::string::String,
+    /// The entry to append
+    #[prost(message, optional, tag = "3")]
+    pub entry: ::core::option::Option<StreamRequest>,
+}
+/// The download request message.
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct DownloadRequest {
+    /// The name of the file
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+}
+/// A chunk of a file.
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct FileChunk {
+    /// The name of the file
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    /// The chunk data
+    #[prost(bytes = "vec", tag = "2")]
+    pub data: ::prost::alloc::vec::Vec<u8>,
+}
 /// The response message.
 #[derive(serde::Serialize, serde::Deserialize)]
 #[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
@@ -168,6 +212,148 @@ pub mod streaming_test_client {
                 .insert(GrpcMethod::new("test_ws.v1.StreamingTest", "BidiStream"));
             self.inner.streaming(req, path, codec).await
         }
+        /// Client streaming RPC with a path parameter
+        pub async fn upload(
+            &mut self,
+            request: impl tonic::IntoStreamingRequest<Message = super::UploadRequest>,
+        ) -> std::result::Result<tonic::Response<super::StreamReply>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic_prost::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/test_ws.v1.StreamingTest/Upload",
+            );
+            let mut req = request.into_streaming_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("test_ws.v1.StreamingTest", "Upload"));
+            self.inner.client_streaming(req, path, codec).await
+        }
+        /// Client streaming RPC with a field body, the other fields bound from the path and query string
+        pub async fn append(
+            &mut self,
+            request: impl tonic::IntoStreamingRequest<Message = super::AppendRequest>,
+        ) -> std::result::Result<tonic::Response<super::StreamReply>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic_prost::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/test_ws.v1.StreamingTest/Append",
+            );
+            let mut req = request.into_streaming_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("test_ws.v1.StreamingTest", "Append"));
+            self.inner.client_streaming(req, path, codec).await
+        }
+        /// Server streaming RPC of file chunks
+        pub async fn download(
+            &mut self,
+            request: impl tonic::IntoRequest<super::DownloadRequest>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::FileChunk>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic_prost::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/test_ws.v1.StreamingTest/Download",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("test_ws.v1.StreamingTest", "Download"));
+            self.inner.server_streaming(req, path, codec).await
+        }
+        /// Client streaming RPC of file chunks
+        pub async fn upload_file(
+            &mut self,
+            request: impl tonic::IntoStreamingRequest<Message = super::FileChunk>,
+        ) -> std::result::Result<tonic::Response<super::StreamReply>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic_prost::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/test_ws.v1.StreamingTest/UploadFile",
+            );
+            let mut req = request.into_streaming_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("test_ws.v1.StreamingTest", "UploadFile"));
+            self.inner.client_streaming(req, path, codec).await
+        }
+        /// Server streaming RPC of an HttpBody
+        pub async fn stream_image(
+            &mut self,
+            request: impl tonic::IntoRequest<super::DownloadRequest>,
+        ) -> std::result::Result<
+            tonic::Response<
+                tonic::codec::Streaming<super::super::super::google::api::HttpBody>,
+            >,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic_prost::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/test_ws.v1.StreamingTest/StreamImage",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("test_ws.v1.StreamingTest", "StreamImage"));
+            self.inner.server_streaming(req, path, codec).await
+        }
+        /// Client streaming RPC of an HttpBody
+        pub async fn upload_image(
+            &mut self,
+            request: impl tonic::IntoStreamingRequest<
+                Message = super::super::super::google::api::HttpBody,
+            >,
+        ) -> std::result::Result<tonic::Response<super::StreamReply>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic_prost::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/test_ws.v1.StreamingTest/UploadImage",
+            );
+            let mut req = request.into_streaming_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("test_ws.v1.StreamingTest", "UploadImage"));
+            self.inner.client_streaming(req, path, codec).await
+        }
         /// Unary RPC - should NOT generate WS handler
         pub async fn unary_call(
             &mut self,
@@ -235,6 +421,56 @@ pub mod streaming_test_server {
             &self,
             request: tonic::Request<tonic::Streaming<super::StreamRequest>>,
         ) -> std::result::Result<tonic::Response<Self::BidiStreamStream>, tonic::Status>;
+        /// Client streaming RPC with a path parameter
+        async fn upload(
+            &self,
+            request: tonic::Request<tonic::Streaming<super::UploadRequest>>,
+        ) -> std::result::Result<tonic::Response<super::StreamReply>, tonic::Status>;
+        /// Client streaming RPC with a field body, the other fields bound from the path and query string
+        async fn append(
+            &self,
+            request: tonic::Request<tonic::Streaming<super::AppendRequest>>,
+        ) -> std::result::Result<tonic::Response<super::StreamReply>, tonic::Status>;
+        /// Server streaming response type for the Download method.
+        type DownloadStream: tonic::codegen::tokio_stream::Stream<
+                Item = std::result::Result<super::FileChunk, tonic::Status>,
+            >
+            + std::marker::Send
+            + 'static;
+        /// Server streaming RPC of file chunks
+        async fn download(
+            &self,
+            request: tonic::Request<super::DownloadRequest>,
+        ) -> std::result::Result<tonic::Response<Self::DownloadStream>, tonic::Status>;
+        /// Client streaming RPC of file chunks
+        async fn upload_file(
+            &self,
+            request: tonic::Request<tonic::Streaming<super::FileChunk>>,
+        ) -> std::result::Result<tonic::Response<super::StreamReply>, tonic::Status>;
+        /// Server streaming response type for the StreamImage method.
+        type StreamImageStream: tonic::codegen::tokio_stream::Stream<
+                Item = std::result::Result<
+                    super::super::super::google::api::HttpBody,
+                    tonic::Status,
+                >,
+            >
+            + std::marker::Send
+            + 'static;
+        /// Server streaming RPC of an HttpBody
+        async fn stream_image(
+            &self,
+            request: tonic::Request<super::DownloadRequest>,
+        ) -> std::result::Result<
+            tonic::Response<Self::StreamImageStream>,
+            tonic::Status,
+        >;
+        /// Client streaming RPC of an HttpBody
+        async fn upload_image(
+            &self,
+            request: tonic::Request<
+                tonic::Streaming<super::super::super::google::api::HttpBody>,
+            >,
+        ) -> std::result::Result<tonic::Response<super::StreamReply>, tonic::Status>;
         /// Unary RPC - should NOT generate WS handler
         async fn unary_call(
             &self,
@@ -459,13 +695,13 @@ pub mod streaming_test_server {
                     };
                     Box::pin(fut)
                 }
-                "/test_ws.v1.StreamingTest/UnaryCall" => {
+                "/test_ws.v1.StreamingTest/Upload" => {
                     #[allow(non_camel_case_types)]
-                    struct UnaryCallSvc<T: StreamingTest>(pub Arc<T>);
+                    struct UploadSvc<T: StreamingTest>(pub Arc<T>);
                     impl<
                         T: StreamingTest,
-                    > tonic::server::UnaryService<super::StreamRequest>
-                    for UnaryCallSvc<T> {
+                    > tonic::server::ClientStreamingService<super::UploadRequest>
+                    for UploadSvc<T> {
                         type Response = super::StreamReply;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
@@ -473,11 +709,13 @@ pub mod streaming_test_server {
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::StreamRequest>,
+                            request: tonic::Request<
+                                tonic::Streaming<super::UploadRequest>,
+                            >,
                         ) -> Self::Future {
                             let inner = Arc::clone(&self.0);
                             let fut = async move {
-                                <T as StreamingTest>::unary_call(&inner, request).await
+                                <T as StreamingTest>::upload(&inner, request).await
                             };
                             Box::pin(fut)
                         }
@@ -488,7 +726,7 @@ pub mod streaming_test_server {
                     let max_encoding_message_size = self.max_encoding_message_size;
                     let inner = self.inner.clone();
                     let fut = async move {
-                        let method = UnaryCallSvc(inner);
+                        let method = UploadSvc(inner);
                         let codec = tonic_prost::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -499,39 +737,316 @@ pub mod streaming_test_server {
                                 max_decoding_message_size,
                                 max_encoding_message_size,
                             );
-                        let res = grpc.unary(method, req).await;
+                        let res = grpc.client_streaming(method, req).await;
                         Ok(res)
                     };
                     Box::pin(fut)
                 }
-                _ => {
-                    Box::pin(async move {
-                        let mut response = http::Response::new(
-                            tonic::body::Body::default(),
-                        );
-                        let headers = response.headers_mut();
-                        headers
-                            .insert(
-                                tonic::Status::GRPC_STATUS,
-                                (tonic::Code::Unimplemented as i32).into(),
+                "/test_ws.v1.StreamingTest/Append" => {
+                    #[allow(non_camel_case_types)]
+                    struct AppendSvc<T: StreamingTest>(pub Arc<T>);
+                    impl<
+                        T: StreamingTest,
+                    > tonic::server::ClientStreamingService<super::AppendRequest>
+                    for AppendSvc<T> {
+                        type Response = super::StreamReply;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                tonic::Streaming<super::AppendRequest>,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as StreamingTest>::append(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = AppendSvc(inner);
+                        let codec = tonic_prost::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
                             );
-                        headers
-                            .insert(
-                                http::header::CONTENT_TYPE,
-                                tonic::metadata::GRPC_CONTENT_TYPE,
+                        let res = grpc.client_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/test_ws.v1.StreamingTest/Download" => {
+                    #[allow(non_camel_case_types)]
+                    struct DownloadSvc<T: StreamingTest>(pub Arc<T>);
+                    impl<
+                        T: StreamingTest,
+                    > tonic::server::ServerStreamingService<super::DownloadRequest>
+                    for DownloadSvc<T> {
+                        type Response = super::FileChunk;
+                        type ResponseStream = T::DownloadStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::DownloadRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as StreamingTest>::download(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = DownloadSvc(inner);
+                        let codec = tonic_prost::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
                             );
-                        Ok(response)
-                    })
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
                 }
-            }
-        }
-    }
-    impl<T> Clone for StreamingTestServer<T> {
-        fn clone(&self) -> Self {
-            let inner = self.inner.clone();
-            Self {
-                inner,
-                accept_compression_encodings: self.accept_compression_encodings,
+                "/test_ws.v1.StreamingTest/UploadFile" => {
+                    #[allow(non_camel_case_types)]
+                    struct UploadFileSvc<T: StreamingTest>(pub Arc<T>);
+                    impl<
+                        T: StreamingTest,
+                    > tonic::server::ClientStreamingService<super::FileChunk>
+                    for UploadFileSvc<T> {
+                        type Response = super::StreamReply;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<tonic::Streaming<super::FileChunk>>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as StreamingTest>::upload_file(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = UploadFileSvc(inner);
+                        let codec = tonic_prost::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.client_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/test_ws.v1.StreamingTest/StreamImage" => {
+                    #[allow(non_camel_case_types)]
+                    struct StreamImageSvc<T: StreamingTest>(pub Arc<T>);
+                    impl<
+                        T: StreamingTest,
+                    > tonic::server::ServerStreamingService<super::DownloadRequest>
+                    for StreamImageSvc<T> {
+                        type Response = super::super::super::google::api::HttpBody;
+                        type ResponseStream = T::StreamImageStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::DownloadRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as StreamingTest>::stream_image(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = StreamImageSvc(inner);
+                        let codec = tonic_prost::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/test_ws.v1.StreamingTest/UploadImage" => {
+                    #[allow(non_camel_case_types)]
+                    struct UploadImageSvc<T: StreamingTest>(pub Arc<T>);
+                    impl<
+                        T: StreamingTest,
+                    > tonic::server::ClientStreamingService<
+                        super::super::super::google::api::HttpBody,
+                    > for UploadImageSvc<T> {
+                        type Response = super::StreamReply;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                tonic::Streaming<super::super::super::google::api::HttpBody>,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as StreamingTest>::upload_image(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = UploadImageSvc(inner);
+                        let codec = tonic_prost::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.client_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/test_ws.v1.StreamingTest/UnaryCall" => {
+                    #[allow(non_camel_case_types)]
+                    struct UnaryCallSvc<T: StreamingTest>(pub Arc<T>);
+                    impl<
+                        T: StreamingTest,
+                    > tonic::server::UnaryService<super::StreamRequest>
+                    for UnaryCallSvc<T> {
+                        type Response = super::StreamReply;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::StreamRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as StreamingTest>::unary_call(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = UnaryCallSvc(inner);
+                        let codec = tonic_prost::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                _ => {
+                    Box::pin(async move {
+                        let mut response = http::Response::new(
+                            tonic::body::Body::default(),
+                        );
+                        let headers = response.headers_mut();
+                        headers
+                            .insert(
+                                tonic::Status::GRPC_STATUS,
+                                (tonic::Code::Unimplemented as i32).into(),
+                            );
+                        headers
+                            .insert(
+                                http::header::CONTENT_TYPE,
+                                tonic::metadata::GRPC_CONTENT_TYPE,
+                            );
+                        Ok(response)
+                    })
+                }
+            }
+        }
+    }
+    impl<T> Clone for StreamingTestServer<T> {
+        fn clone(&self) -> Self {
+            let inner = self.inner.clone();
+            Self {
+                inner,
+                accept_compression_encodings: self.accept_compression_encodings,
                 send_compression_encodings: self.send_compression_encodings,
                 max_decoding_message_size: self.max_decoding_message_size,
                 max_encoding_message_size: self.max_encoding_message_size,
@@ -544,6 +1059,30 @@ pub mod streaming_test_server {
         const NAME: &'static str = SERVICE_NAME;
     }
 }
+/// The append request message.
+#[derive(serde::Deserialize)]
+pub struct AppendRequestBody__ {
+    /// The entry to append
+    pub entry: ::core::option::Option<StreamRequest>,
+}
+/// A chunk of a file.
+#[derive(serde::Deserialize)]
+pub struct FileChunkBody__ {
+    /// The chunk data
+    pub data: ::prost::alloc::vec::Vec<u8>,
+}
+/// The upload request message.
+#[derive(serde::Deserialize)]
+pub struct UploadRequestBody__ {
+    /// The data to upload
+    pub data: ::prost::alloc::string::String,
+}
+/// The append request message.
+#[derive(serde::Deserialize)]
+pub struct AppendRequestQuery__ {
+    /// The tag of the entries
+    pub tag: ::prost::alloc::string::String,
+}
 /// Generated axum handlers and router.
 pub mod streaming_test_axum {
     #![allow(unused_imports)]
@@ -553,38 +1092,236 @@ pub mod streaming_test_axum {
     use axum::extract::{Path, Query, State};
     use axum::routing::{any, get, post, put, delete, patch};
     use axum::Router;
-    use axum_extra::json_lines::JsonLines;
     use axum::extract::WebSocketUpgrade;
     use axum::response::Response;
     /// Server streaming RPC
     pub async fn server_stream<S: super::streaming_test_server::StreamingTest>(
         State(state__): State<S>,
+        uri__: http::Uri,
         headers__: http::HeaderMap,
         extensions__: http::Extensions,
         req__: Json<super::StreamRequest>,
     ) -> http::Response<Body> {
+        let options__ = tonic2axum::ResponseOptions::from_extensions(&extensions__)
+            .stream_format(
+                tonic2axum::StreamFormat::from_accept_or(
+                    &headers__,
+                    tonic2axum::StreamFormat::JsonArray,
+                ),
+            )
+            .collect_stream(tonic2axum::collect_requested(&uri__, &headers__, true));
+        let timeout__ = tonic2axum::request_timeout(&headers__, None);
         let req__ = tonic2axum::make_request(headers__, extensions__, req__.0);
-        tonic2axum::make_stream_response(state__.server_stream(req__).await)
+        tonic2axum::collect_stream_response(
+                tonic2axum::with_stream_timeout(timeout__, state__.server_stream(req__))
+                    .await,
+                options__,
+                tonic2axum::make_stream_response_with,
+            )
+            .await
     }
     /// Client streaming RPC
     pub async fn client_stream<S: super::streaming_test_server::StreamingTest>(
         State(state__): State<S>,
         headers__: http::HeaderMap,
         extensions__: http::Extensions,
-        req__: JsonLines<super::StreamRequest>,
+        req__: Body,
     ) -> http::Response<Body> {
-        let req__ = tonic2axum::make_stream_request(headers__, extensions__, req__);
-        tonic2axum::make_response(state__.client_stream(req__).await)
+        let options__ = tonic2axum::ResponseOptions::from_extensions(&extensions__);
+        let timeout__ = tonic2axum::request_timeout(&headers__, None);
+        let req__ = tonic2axum::make_stream_request_with(
+            headers__,
+            extensions__,
+            req__,
+            true,
+            |req__| req__,
+        );
+        tonic2axum::make_response_with(
+            tonic2axum::with_timeout(timeout__, state__.client_stream(req__)).await,
+            options__,
+        )
     }
     /// Bidirectional streaming RPC
     pub async fn bidi_stream<S: super::streaming_test_server::StreamingTest>(
+        State(state__): State<S>,
+        uri__: http::Uri,
+        headers__: http::HeaderMap,
+        extensions__: http::Extensions,
+        req__: Body,
+    ) -> http::Response<Body> {
+        let options__ = tonic2axum::ResponseOptions::from_extensions(&extensions__)
+            .stream_format(
+                tonic2axum::StreamFormat::from_accept_or(
+                    &headers__,
+                    tonic2axum::StreamFormat::JsonArray,
+                ),
+            )
+            .collect_stream(tonic2axum::collect_requested(&uri__, &headers__, true));
+        let timeout__ = tonic2axum::request_timeout(&headers__, None);
+        let req__ = tonic2axum::make_stream_request_with(
+            headers__,
+            extensions__,
+            req__,
+            true,
+            |req__| req__,
+        );
+        tonic2axum::collect_stream_response(
+                tonic2axum::with_stream_timeout(timeout__, state__.bidi_stream(req__))
+                    .await,
+                options__,
+                tonic2axum::make_stream_response_with,
+            )
+            .await
+    }
+    /// Client streaming RPC with a path parameter
+    pub async fn upload<S: super::streaming_test_server::StreamingTest>(
+        State(state__): State<S>,
+        Path(bucket): Path<::prost::alloc::string::String>,
+        headers__: http::HeaderMap,
+        extensions__: http::Extensions,
+        req__: Body,
+    ) -> http::Response<Body> {
+        let options__ = tonic2axum::ResponseOptions::from_extensions(&extensions__);
+        let timeout__ = tonic2axum::request_timeout(&headers__, None);
+        let req__ = tonic2axum::make_stream_request_with(
+            headers__,
+            extensions__,
+            req__,
+            true,
+            move |req__: super::UploadRequestBody__| super::UploadRequest {
+                bucket: bucket.clone(),
+                data: req__.data,
+            },
+        );
+        tonic2axum::make_response_with(
+            tonic2axum::with_timeout(timeout__, state__.upload(req__)).await,
+            options__,
+        )
+    }
+    /// Client streaming RPC with a field body, the other fields bound from the path and query string
+    pub async fn append<S: super::streaming_test_server::StreamingTest>(
+        State(state__): State<S>,
+        Path(log): Path<::prost::alloc::string::String>,
+        Query(super::AppendRequestQuery__ { tag }): Query<super::AppendRequestQuery__>,
+        headers__: http::HeaderMap,
+        extensions__: http::Extensions,
+        req__: Body,
+    ) -> http::Response<Body> {
+        let options__ = tonic2axum::ResponseOptions::from_extensions(&extensions__);
+        let timeout__ = tonic2axum::request_timeout(&headers__, None);
+        let req__ = tonic2axum::make_stream_request_with(
+            headers__,
+            extensions__,
+            req__,
+            true,
+            move |req__: super::AppendRequestBody__| super::AppendRequest {
+                log: log.clone(),
+                tag: tag.clone(),
+                entry: req__.entry,
+            },
+        );
+        tonic2axum::make_response_with(
+            tonic2axum::with_timeout(timeout__, state__.append(req__)).await,
+            options__,
+        )
+    }
+    /// Server streaming RPC of file chunks
+    pub async fn download<S: super::streaming_test_server::StreamingTest>(
+        State(state__): State<S>,
+        Path(name): Path<::prost::alloc::string::String>,
+        uri__: http::Uri,
+        headers__: http::HeaderMap,
+        extensions__: http::Extensions,
+    ) -> http::Response<Body> {
+        let req__ = super::DownloadRequest { name };
+        let options__ = tonic2axum::ResponseOptions::from_extensions(&extensions__)
+            .stream_format(
+                tonic2axum::StreamFormat::from_accept_or(
+                    &headers__,
+                    tonic2axum::StreamFormat::JsonArray,
+                ),
+            )
+            .collect_stream(tonic2axum::collect_requested(&uri__, &headers__, true));
+        let timeout__ = tonic2axum::request_timeout(&headers__, None);
+        let req__ = tonic2axum::make_request(headers__, extensions__, req__);
+        tonic2axum::collect_stream_response(
+                tonic2axum::with_stream_timeout(timeout__, state__.download(req__))
+                    .await,
+                options__,
+                tonic2axum::make_stream_response_with,
+            )
+            .await
+    }
+    /// Client streaming RPC of file chunks
+    pub async fn upload_file<S: super::streaming_test_server::StreamingTest>(
+        State(state__): State<S>,
+        Path(name): Path<::prost::alloc::string::String>,
+        headers__: http::HeaderMap,
+        extensions__: http::Extensions,
+        req__: Body,
+    ) -> http::Response<Body> {
+        let options__ = tonic2axum::ResponseOptions::from_extensions(&extensions__);
+        let timeout__ = tonic2axum::request_timeout(&headers__, None);
+        let req__ = tonic2axum::make_stream_request_with(
+            headers__,
+            extensions__,
+            req__,
+            true,
+            move |req__: super::FileChunkBody__| super::FileChunk {
+                name: name.clone(),
+                data: req__.data,
+            },
+        );
+        tonic2axum::make_response_with(
+            tonic2axum::with_timeout(timeout__, state__.upload_file(req__)).await,
+            options__,
+        )
+    }
+    /// Server streaming RPC of an HttpBody
+    pub async fn stream_image<S: super::streaming_test_server::StreamingTest>(
+        State(state__): State<S>,
+        Path(name): Path<::prost::alloc::string::String>,
+        headers__: http::HeaderMap,
+        extensions__: http::Extensions,
+    ) -> http::Response<Body> {
+        let req__ = super::DownloadRequest { name };
+        let options__ = tonic2axum::ResponseOptions::from_extensions(&extensions__);
+        let timeout__ = tonic2axum::request_timeout(&headers__, None);
+        let req__ = tonic2axum::make_request(headers__, extensions__, req__);
+        tonic2axum::make_http_body_stream_response(
+                tonic2axum::with_stream_timeout(timeout__, state__.stream_image(req__))
+                    .await,
+                options__,
+                |req__: super::super::super::google::api::HttpBody| (
+                    req__.content_type,
+                    req__.data.into(),
+                ),
+            )
+            .await
+    }
+    /// Client streaming RPC of an HttpBody
+    pub async fn upload_image<S: super::streaming_test_server::StreamingTest>(
         State(state__): State<S>,
         headers__: http::HeaderMap,
         extensions__: http::Extensions,
-        req__: JsonLines<super::StreamRequest>,
+        req__: Body,
     ) -> http::Response<Body> {
-        let req__ = tonic2axum::make_stream_request(headers__, extensions__, req__);
-        tonic2axum::make_stream_response(state__.bidi_stream(req__).await)
+        let options__ = tonic2axum::ResponseOptions::from_extensions(&extensions__);
+        let timeout__ = tonic2axum::request_timeout(&headers__, None);
+        let req__ = tonic2axum::make_http_body_stream_request(
+            headers__,
+            extensions__,
+            req__,
+            |content_type, data| super::super::super::google::api::HttpBody {
+                content_type,
+                data: data.into(),
+                extensions: Vec::new(),
+            },
+        );
+        tonic2axum::make_response_with(
+            tonic2axum::with_timeout(timeout__, state__.upload_image(req__)).await,
+            options__,
+        )
     }
     /// Unary RPC - should NOT generate WS handler
     pub async fn unary_call<S: super::streaming_test_server::StreamingTest>(
@@ -593,27 +1330,39 @@ pub mod streaming_test_axum {
         extensions__: http::Extensions,
         req__: Json<super::StreamRequest>,
     ) -> http::Response<Body> {
+        let options__ = tonic2axum::ResponseOptions::from_extensions(&extensions__);
+        let timeout__ = tonic2axum::request_timeout(&headers__, None);
         let req__ = tonic2axum::make_request(headers__, extensions__, req__.0);
-        tonic2axum::make_response(state__.unary_call(req__).await)
+        tonic2axum::make_response_with(
+            tonic2axum::with_timeout(timeout__, state__.unary_call(req__)).await,
+            options__,
+        )
     }
     /// Server streaming RPC
     pub async fn server_stream_ws<S: super::streaming_test_server::StreamingTest>(
         State((state__, protobuf__)): State<(S, bool)>,
         ws_upgrade__: WebSocketUpgrade,
+        uri__: http::Uri,
         headers__: http::HeaderMap,
         extensions__: http::Extensions,
     ) -> Response {
         tonic2axum::upgrade_to_ws(
                 ws_upgrade__,
+                uri__,
                 headers__,
                 extensions__,
                 protobuf__,
                 |headers__, extensions__, stream, sink, protobuf__| async move {
+                    let timeout__ = tonic2axum::request_timeout(&headers__, None);
                     match tonic2axum::make_ws_request(headers__, extensions__, stream)
                         .await
                     {
                         Some(request) => {
-                            let response = state__.server_stream(request).await;
+                            let response = tonic2axum::with_stream_timeout(
+                                    timeout__,
+                                    state__.server_stream(request),
+                                )
+                                .await;
                             tonic2axum::process_ws_stream_response(
                                     response,
                                     sink,
@@ -637,21 +1386,28 @@ pub mod streaming_test_axum {
     pub async fn client_stream_ws<S: super::streaming_test_server::StreamingTest>(
         State((state__, protobuf__)): State<(S, bool)>,
         ws_upgrade__: WebSocketUpgrade,
+        uri__: http::Uri,
         headers__: http::HeaderMap,
         extensions__: http::Extensions,
     ) -> Response {
         tonic2axum::upgrade_to_ws(
                 ws_upgrade__,
+                uri__,
                 headers__,
                 extensions__,
                 protobuf__,
                 |headers__, extensions__, stream, sink, protobuf__| async move {
+                    let timeout__ = tonic2axum::request_timeout(&headers__, None);
                     let request = tonic2axum::make_ws_stream_request(
                         headers__,
                         extensions__,
                         stream,
                     );
-                    let response = state__.client_stream(request).await;
+                    let response = tonic2axum::with_timeout(
+                            timeout__,
+                            state__.client_stream(request),
+                        )
+                        .await;
                     tonic2axum::process_ws_response(response, sink, protobuf__).await;
                 },
             )
@@ -661,27 +1417,388 @@ pub mod streaming_test_axum {
     pub async fn bidi_stream_ws<S: super::streaming_test_server::StreamingTest>(
         State((state__, protobuf__)): State<(S, bool)>,
         ws_upgrade__: WebSocketUpgrade,
+        uri__: http::Uri,
         headers__: http::HeaderMap,
         extensions__: http::Extensions,
     ) -> Response {
         tonic2axum::upgrade_to_ws(
                 ws_upgrade__,
+                uri__,
                 headers__,
                 extensions__,
                 protobuf__,
                 |headers__, extensions__, stream, sink, protobuf__| async move {
+                    let timeout__ = tonic2axum::request_timeout(&headers__, None);
                     let request = tonic2axum::make_ws_stream_request(
                         headers__,
                         extensions__,
                         stream,
                     );
-                    let response = state__.bidi_stream(request).await;
+                    let response = tonic2axum::with_stream_timeout(
+                            timeout__,
+                            state__.bidi_stream(request),
+                        )
+                        .await;
                     tonic2axum::process_ws_stream_response(response, sink, protobuf__)
                         .await;
                 },
             )
             .await
     }
+    /// Client streaming RPC with a path parameter
+    pub async fn upload_ws<S: super::streaming_test_server::StreamingTest>(
+        State((state__, protobuf__)): State<(S, bool)>,
+        Path(bucket): Path<::prost::alloc::string::String>,
+        ws_upgrade__: WebSocketUpgrade,
+        uri__: http::Uri,
+        headers__: http::HeaderMap,
+        extensions__: http::Extensions,
+    ) -> Response {
+        let transform__ = move |mut req__: super::UploadRequest| {
+            req__.bucket = bucket.clone();
+            req__
+        };
+        tonic2axum::upgrade_to_ws(
+                ws_upgrade__,
+                uri__,
+                headers__,
+                extensions__,
+                protobuf__,
+                |headers__, extensions__, stream, sink, protobuf__| async move {
+                    let timeout__ = tonic2axum::request_timeout(&headers__, None);
+                    let request = tonic2axum::make_ws_stream_request_with(
+                        headers__,
+                        extensions__,
+                        stream,
+                        transform__,
+                    );
+                    let response = tonic2axum::with_timeout(
+                            timeout__,
+                            state__.upload(request),
+                        )
+                        .await;
+                    tonic2axum::process_ws_response(response, sink, protobuf__).await;
+                },
+            )
+            .await
+    }
+    /// Client streaming RPC with a field body, the other fields bound from the path and query string
+    pub async fn append_ws<S: super::streaming_test_server::StreamingTest>(
+        State((state__, protobuf__)): State<(S, bool)>,
+        Path(log): Path<::prost::alloc::string::String>,
+        ws_upgrade__: WebSocketUpgrade,
+        uri__: http::Uri,
+        headers__: http::HeaderMap,
+        extensions__: http::Extensions,
+    ) -> Response {
+        let transform__ = move |mut req__: super::AppendRequest| {
+            req__.log = log.clone();
+            req__
+        };
+        tonic2axum::upgrade_to_ws(
+                ws_upgrade__,
+                uri__,
+                headers__,
+                extensions__,
+                protobuf__,
+                |headers__, extensions__, stream, sink, protobuf__| async move {
+                    let timeout__ = tonic2axum::request_timeout(&headers__, None);
+                    let request = tonic2axum::make_ws_stream_request_with(
+                        headers__,
+                        extensions__,
+                        stream,
+                        transform__,
+                    );
+                    let response = tonic2axum::with_timeout(
+                            timeout__,
+                            state__.append(request),
+                        )
+                        .await;
+                    tonic2axum::process_ws_response(response, sink, protobuf__).await;
+                },
+            )
+            .await
+    }
+    /// Server streaming RPC of file chunks
+    pub async fn download_ws<S: super::streaming_test_server::StreamingTest>(
+        State((state__, protobuf__)): State<(S, bool)>,
+        Path(name): Path<::prost::alloc::string::String>,
+        ws_upgrade__: WebSocketUpgrade,
+        uri__: http::Uri,
+        headers__: http::HeaderMap,
+        extensions__: http::Extensions,
+    ) -> Response {
+        let transform__ = move |mut req__: super::DownloadRequest| {
+            req__.name = name.clone();
+            req__
+        };
+        tonic2axum::upgrade_to_ws(
+                ws_upgrade__,
+                uri__,
+                headers__,
+                extensions__,
+                protobuf__,
+                |headers__, extensions__, stream, sink, protobuf__| async move {
+                    let timeout__ = tonic2axum::request_timeout(&headers__, None);
+                    match tonic2axum::make_ws_request(headers__, extensions__, stream)
+                        .await
+                    {
+                        Some(request) => {
+                            let response = tonic2axum::with_stream_timeout(
+                                    timeout__,
+                                    state__.download(request.map(transform__)),
+                                )
+                                .await;
+                            tonic2axum::process_ws_stream_response(
+                                    response,
+                                    sink,
+                                    protobuf__,
+                                )
+                                .await;
+                        }
+                        None => {
+                            tonic2axum::close_ws(
+                                    sink,
+                                    tonic::Status::aborted("No request received"),
+                                )
+                                .await;
+                        }
+                    }
+                },
+            )
+            .await
+    }
+    /// Client streaming RPC of file chunks
+    pub async fn upload_file_ws<S: super::streaming_test_server::StreamingTest>(
+        State((state__, protobuf__)): State<(S, bool)>,
+        Path(name): Path<::prost::alloc::string::String>,
+        ws_upgrade__: WebSocketUpgrade,
+        uri__: http::Uri,
+        headers__: http::HeaderMap,
+        extensions__: http::Extensions,
+    ) -> Response {
+        let transform__ = move |mut req__: super::FileChunk| {
+            req__.name = name.clone();
+            req__
+        };
+        tonic2axum::upgrade_to_ws(
+                ws_upgrade__,
+                uri__,
+                headers__,
+                extensions__,
+                protobuf__,
+                |headers__, extensions__, stream, sink, protobuf__| async move {
+                    let timeout__ = tonic2axum::request_timeout(&headers__, None);
+                    let request = tonic2axum::make_ws_stream_request_with(
+                        headers__,
+                        extensions__,
+                        stream,
+                        transform__,
+                    );
+                    let response = tonic2axum::with_timeout(
+                            timeout__,
+                            state__.upload_file(request),
+                        )
+                        .await;
+                    tonic2axum::process_ws_response(response, sink, protobuf__).await;
+                },
+            )
+            .await
+    }
+    /// Unary RPC - should NOT generate WS handler
+    pub async fn unary_call_ws<S: super::streaming_test_server::StreamingTest>(
+        State((state__, protobuf__)): State<(S, bool)>,
+        ws_upgrade__: WebSocketUpgrade,
+        uri__: http::Uri,
+        headers__: http::HeaderMap,
+        extensions__: http::Extensions,
+    ) -> Response {
+        tonic2axum::upgrade_to_ws(
+                ws_upgrade__,
+                uri__,
+                headers__,
+                extensions__,
+                protobuf__,
+                |headers__, extensions__, stream, sink, protobuf__| async move {
+                    let timeout__ = tonic2axum::request_timeout(&headers__, None);
+                    tonic2axum::process_ws_unary_calls(
+                            headers__,
+                            extensions__,
+                            stream,
+                            sink,
+                            protobuf__,
+                            |request| tonic2axum::with_timeout(
+                                timeout__,
+                                state__.unary_call(request),
+                            ),
+                        )
+                        .await;
+                },
+            )
+            .await
+    }
+    /// Multiplexes calls to the WebSocket enabled methods of the StreamingTest service over a single socket
+    pub async fn ws_mux<S: super::streaming_test_server::StreamingTest>(
+        State((state__, _)): State<(S, bool)>,
+        ws_upgrade__: WebSocketUpgrade,
+        uri__: http::Uri,
+        headers__: http::HeaderMap,
+        extensions__: http::Extensions,
+    ) -> Response {
+        let state__ = Arc::new(state__);
+        tonic2axum::upgrade_to_ws_mux(
+                ws_upgrade__,
+                uri__,
+                headers__,
+                extensions__,
+                move |call__: tonic2axum::MuxCall| {
+                    let state__ = Arc::clone(&state__);
+                    async move {
+                        match call__.method() {
+                            "ServerStream" => {
+                                let timeout__ = tonic2axum::request_timeout(
+                                    call__.headers(),
+                                    None,
+                                );
+                                if let Some((request, sink)) = tonic2axum::make_mux_request(
+                                        call__,
+                                    )
+                                    .await
+                                {
+                                    let response = tonic2axum::with_stream_timeout(
+                                            timeout__,
+                                            state__.server_stream(request),
+                                        )
+                                        .await;
+                                    tonic2axum::process_mux_stream_response(response, sink)
+                                        .await;
+                                }
+                            }
+                            "ClientStream" => {
+                                let timeout__ = tonic2axum::request_timeout(
+                                    call__.headers(),
+                                    None,
+                                );
+                                let (request, sink) = tonic2axum::make_mux_stream_request(
+                                    call__,
+                                );
+                                let response = tonic2axum::with_timeout(
+                                        timeout__,
+                                        state__.client_stream(request),
+                                    )
+                                    .await;
+                                tonic2axum::process_mux_response(response, sink).await;
+                            }
+                            "BidiStream" => {
+                                let timeout__ = tonic2axum::request_timeout(
+                                    call__.headers(),
+                                    None,
+                                );
+                                let (request, sink) = tonic2axum::make_mux_stream_request(
+                                    call__,
+                                );
+                                let response = tonic2axum::with_stream_timeout(
+                                        timeout__,
+                                        state__.bidi_stream(request),
+                                    )
+                                    .await;
+                                tonic2axum::process_mux_stream_response(response, sink)
+                                    .await;
+                            }
+                            "Upload" => {
+                                let timeout__ = tonic2axum::request_timeout(
+                                    call__.headers(),
+                                    None,
+                                );
+                                let (request, sink) = tonic2axum::make_mux_stream_request(
+                                    call__,
+                                );
+                                let response = tonic2axum::with_timeout(
+                                        timeout__,
+                                        state__.upload(request),
+                                    )
+                                    .await;
+                                tonic2axum::process_mux_response(response, sink).await;
+                            }
+                            "Append" => {
+                                let timeout__ = tonic2axum::request_timeout(
+                                    call__.headers(),
+                                    None,
+                                );
+                                let (request, sink) = tonic2axum::make_mux_stream_request(
+                                    call__,
+                                );
+                                let response = tonic2axum::with_timeout(
+                                        timeout__,
+                                        state__.append(request),
+                                    )
+                                    .await;
+                                tonic2axum::process_mux_response(response, sink).await;
+                            }
+                            "Download" => {
+                                let timeout__ = tonic2axum::request_timeout(
+                                    call__.headers(),
+                                    None,
+                                );
+                                if let Some((request, sink)) = tonic2axum::make_mux_request(
+                                        call__,
+                                    )
+                                    .await
+                                {
+                                    let response = tonic2axum::with_stream_timeout(
+                                            timeout__,
+                                            state__.download(request),
+                                        )
+                                        .await;
+                                    tonic2axum::process_mux_stream_response(response, sink)
+                                        .await;
+                                }
+                            }
+                            "UploadFile" => {
+                                let timeout__ = tonic2axum::request_timeout(
+                                    call__.headers(),
+                                    None,
+                                );
+                                let (request, sink) = tonic2axum::make_mux_stream_request(
+                                    call__,
+                                );
+                                let response = tonic2axum::with_timeout(
+                                        timeout__,
+                                        state__.upload_file(request),
+                                    )
+                                    .await;
+                                tonic2axum::process_mux_response(response, sink).await;
+                            }
+                            "UnaryCall" => {
+                                let timeout__ = tonic2axum::request_timeout(
+                                    call__.headers(),
+                                    None,
+                                );
+                                if let Some((request, sink)) = tonic2axum::make_mux_request(
+                                        call__,
+                                    )
+                                    .await
+                                {
+                                    let response = tonic2axum::with_timeout(
+                                            timeout__,
+                                            state__.unary_call(request),
+                                        )
+                                        .await;
+                                    tonic2axum::process_mux_response(response, sink).await;
+                                }
+                            }
+                            method => {
+                                let status = tonic::Status::unimplemented(
+                                    format!("Unknown method: {method}"),
+                                );
+                                tonic2axum::close_mux_call(call__, status).await;
+                            }
+                        }
+                    }
+                },
+            )
+            .await
+    }
     /// Axum router for the StreamingTest service
     pub fn make_router<S: super::streaming_test_server::StreamingTest + Clone>(
         state: S,
@@ -690,16 +1807,47 @@ pub mod streaming_test_axum {
             .route("/stream/server/ws/proto", any(server_stream_ws::<S>))
             .route("/stream/client/ws/proto", any(client_stream_ws::<S>))
             .route("/stream/bidi/ws/proto", any(bidi_stream_ws::<S>))
+            .route(
+                "/stream/buckets/{bucket}/objects:upload/ws/proto",
+                any(upload_ws::<S>),
+            )
+            .route("/stream/logs/{log}/entries:append/ws/proto", any(append_ws::<S>))
+            .route("/stream/files/{name}/ws/proto", any(download_ws::<S>))
+            .route("/stream/files/{name}/ws/proto", any(upload_file_ws::<S>))
+            .route("/stream/unary/ws/proto", any(unary_call_ws::<S>))
             .with_state((state.clone(), true));
         let ws_json = Router::new()
+            .route("/stream/server/ws", any(server_stream_ws::<S>))
             .route("/stream/server/ws/json", any(server_stream_ws::<S>))
+            .route("/stream/client/ws", any(client_stream_ws::<S>))
             .route("/stream/client/ws/json", any(client_stream_ws::<S>))
+            .route("/stream/bidi/ws", any(bidi_stream_ws::<S>))
             .route("/stream/bidi/ws/json", any(bidi_stream_ws::<S>))
+            .route("/stream/buckets/{bucket}/objects:upload/ws", any(upload_ws::<S>))
+            .route(
+                "/stream/buckets/{bucket}/objects:upload/ws/json",
+                any(upload_ws::<S>),
+            )
+            .route("/stream/logs/{log}/entries:append/ws", any(append_ws::<S>))
+            .route("/stream/logs/{log}/entries:append/ws/json", any(append_ws::<S>))
+            .route("/stream/files/{name}/ws", any(download_ws::<S>))
+            .route("/stream/files/{name}/ws/json", any(download_ws::<S>))
+            .route("/stream/files/{name}/ws", any(upload_file_ws::<S>))
+            .route("/stream/files/{name}/ws/json", any(upload_file_ws::<S>))
+            .route("/stream/unary/ws", any(unary_call_ws::<S>))
+            .route("/stream/unary/ws/json", any(unary_call_ws::<S>))
+            .route("/test_ws.v1.StreamingTest/ws", any(ws_mux::<S>))
             .with_state((state.clone(), false));
         Router::new()
             .route("/stream/server", post(server_stream::<S>))
             .route("/stream/client", post(client_stream::<S>))
             .route("/stream/bidi", post(bidi_stream::<S>))
+            .route("/stream/buckets/{bucket}/objects:upload", post(upload::<S>))
+            .route("/stream/logs/{log}/entries:append", post(append::<S>))
+            .route("/stream/files/{name}", get(download::<S>))
+            .route("/stream/files/{name}", post(upload_file::<S>))
+            .route("/stream/images/{name}", get(stream_image::<S>))
+            .route("/stream/images", post(upload_image::<S>))
             .route("/stream/unary", post(unary_call::<S>))
             .with_state(state)
             .merge(ws_proto)
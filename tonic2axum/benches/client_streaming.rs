@@ -0,0 +1,181 @@
+//! Compares client streaming request decoding against the previous design, which handed items from `JsonLines` to the
+//! decoder through a mutex, faking empty gRPC frames in the body.
+
+use std::{
+    convert::Infallible,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use axum::extract::FromRequest as _;
+use axum_extra::extract::JsonLines;
+use bytes::Bytes;
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use futures_core::Stream;
+use futures_util::StreamExt as _;
+use http_body::Frame;
+use serde::{Deserialize, Serialize};
+use tonic::codec::{DecodeBuf, Decoder};
+
+#[derive(Serialize, Deserialize)]
+struct Item {
+    id: u64,
+    name: String,
+    tags: Vec<String>,
+}
+
+fn make_body(count: usize) -> Bytes {
+    let mut body = Vec::new();
+    for id in 0..count {
+        let item = Item {
+            id: id as u64,
+            name: format!("item-{id}"),
+            tags: vec!["a".into(), "b".into(), "c".into()],
+        };
+        serde_json::to_writer(&mut body, &item).unwrap();
+        body.push(b'\n');
+    }
+    body.into()
+}
+
+// *** Previous design ***
+
+struct FakeGrpcFrameStreamingHelperInner<S, T> {
+    lines: Pin<Box<S>>,
+    next: Option<Result<T, axum::Error>>,
+}
+
+#[derive(Clone, Default)]
+struct EmptyGrpcFrame([u8; 5]);
+
+impl bytes::Buf for EmptyGrpcFrame {
+    fn remaining(&self) -> usize {
+        (&self.0[..]).remaining()
+    }
+
+    fn chunk(&self) -> &[u8] {
+        &self.0[..]
+    }
+
+    fn advance(&mut self, cnt: usize) {
+        (&self.0[..]).advance(cnt);
+    }
+}
+
+struct FakeGrpcFrameStreamingHelper<S, T> {
+    inner: Arc<Mutex<FakeGrpcFrameStreamingHelperInner<S, T>>>,
+    empty_frame: EmptyGrpcFrame,
+}
+
+impl<S, T> Clone for FakeGrpcFrameStreamingHelper<S, T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            empty_frame: self.empty_frame.clone(),
+        }
+    }
+}
+
+impl<S, T> http_body::Body for FakeGrpcFrameStreamingHelper<S, T>
+where
+    S: Stream<Item = Result<T, axum::Error>>,
+{
+    type Data = EmptyGrpcFrame;
+    type Error = Infallible;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let mut inner = self.inner.lock().expect("poisoned lock");
+        if inner.next.is_some() {
+            return Poll::Ready(Some(Ok(Frame::data(self.empty_frame.clone()))));
+        }
+
+        match inner.lines.as_mut().poll_next(cx) {
+            Poll::Ready(Some(item)) => {
+                inner.next = Some(item);
+                Poll::Ready(Some(Ok(Frame::data(self.empty_frame.clone()))))
+            }
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl<S, T> Decoder for FakeGrpcFrameStreamingHelper<S, T>
+where
+    S: Stream<Item = Result<T, axum::Error>> + Send + 'static,
+    T: Send + 'static,
+{
+    type Item = T;
+    type Error = tonic::Status;
+
+    fn decode(&mut self, _src: &mut DecodeBuf<'_>) -> Result<Option<Self::Item>, Self::Error> {
+        let mut inner = self.inner.lock().expect("poisoned lock");
+        inner
+            .next
+            .take()
+            .transpose()
+            .map_err(|e| tonic::Status::internal(e.to_string()))
+    }
+}
+
+async fn previous_design(body: Bytes) -> usize {
+    let request = http::Request::new(axum::body::Body::from(body));
+    let lines: JsonLines<Item> = JsonLines::from_request(request, &()).await.unwrap();
+    let helper = FakeGrpcFrameStreamingHelper {
+        inner: Arc::new(Mutex::new(FakeGrpcFrameStreamingHelperInner {
+            lines: Box::pin(lines),
+            next: None,
+        })),
+        empty_frame: EmptyGrpcFrame::default(),
+    };
+    let streaming = tonic::Streaming::new_request(helper.clone(), helper, None, None);
+    streaming
+        .map(|item| item.unwrap().id as usize)
+        .count()
+        .await
+}
+
+// *** Current design ***
+
+async fn current_design(body: Bytes) -> usize {
+    let request: tonic::Request<tonic::Streaming<Item>> = tonic2axum::make_stream_request(
+        http::HeaderMap::new(),
+        http::Extensions::new(),
+        axum::body::Body::from(body),
+    );
+    request
+        .into_inner()
+        .map(|item| item.unwrap().id as usize)
+        .count()
+        .await
+}
+
+fn client_streaming(c: &mut Criterion) {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    let mut group = c.benchmark_group("client_streaming");
+
+    for count in [10, 1_000, 10_000] {
+        let body = make_body(count);
+        group.throughput(Throughput::Elements(count as u64));
+
+        group.bench_with_input(BenchmarkId::new("fake_frames", count), &body, |b, body| {
+            b.iter(|| runtime.block_on(previous_design(body.clone())))
+        });
+        group.bench_with_input(
+            BenchmarkId::new("native_frames", count),
+            &body,
+            |b, body| b.iter(|| runtime.block_on(current_design(body.clone()))),
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, client_streaming);
+criterion_main!(benches);
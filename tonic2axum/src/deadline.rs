@@ -0,0 +1,284 @@
+use std::time::Duration;
+
+#[cfg(feature = "_streaming")]
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+#[cfg(feature = "_streaming")]
+use futures_core::Stream;
+#[cfg(feature = "_streaming")]
+use tokio::time::{Instant, Sleep};
+
+/// The gRPC timeout header (ie. `100m` for 100 milliseconds)
+pub const GRPC_TIMEOUT_HEADER: &str = "grpc-timeout";
+
+/// A REST friendly timeout header holding the timeout in (possibly fractional) seconds (ie. `2.5`)
+pub const REQUEST_TIMEOUT_HEADER: &str = "x-request-timeout";
+
+// The gRPC spec limits the timeout value to 8 digits
+const MAX_GRPC_TIMEOUT_DIGITS: usize = 8;
+
+/// Determine the timeout for a call. The client timeout is taken from a valid `grpc-timeout` header, or else a valid
+/// `x-request-timeout` header. Clients can only shorten the given default (typically the configured timeout for the
+/// method), so the shorter of the two is used when both are set.
+pub fn request_timeout(headers: &http::HeaderMap, default: Option<Duration>) -> Option<Duration> {
+    let client = headers
+        .get(GRPC_TIMEOUT_HEADER)
+        .and_then(|value| parse_grpc_timeout(value.to_str().ok()?))
+        .or_else(|| {
+            headers
+                .get(REQUEST_TIMEOUT_HEADER)
+                .and_then(|value| parse_seconds(value.to_str().ok()?))
+        });
+
+    match (client, default) {
+        (Some(client), Some(default)) => Some(client.min(default)),
+        (client, default) => client.or(default),
+    }
+}
+
+fn parse_grpc_timeout(value: &str) -> Option<Duration> {
+    // The value is ASCII (from to_str), so splitting at any byte is safe
+    let (value, unit) = value.split_at_checked(value.len().checked_sub(1)?)?;
+    if value.is_empty() || value.len() > MAX_GRPC_TIMEOUT_DIGITS {
+        return None;
+    }
+
+    let value: u64 = value.parse().ok()?;
+    match unit {
+        "H" => Some(Duration::from_secs(value * 60 * 60)),
+        "M" => Some(Duration::from_secs(value * 60)),
+        "S" => Some(Duration::from_secs(value)),
+        "m" => Some(Duration::from_millis(value)),
+        "u" => Some(Duration::from_micros(value)),
+        "n" => Some(Duration::from_nanos(value)),
+        _ => None,
+    }
+}
+
+fn parse_seconds(value: &str) -> Option<Duration> {
+    let secs: f64 = value.trim().parse().ok()?;
+    Duration::try_from_secs_f64(secs).ok()
+}
+
+fn deadline_exceeded() -> tonic::Status {
+    tonic::Status::deadline_exceeded("Deadline exceeded")
+}
+
+/// Runs a service call, failing with `DeadlineExceeded` if it doesn't complete within the timeout (if any).
+pub async fn with_timeout<F, T>(
+    timeout: Option<Duration>,
+    call: F,
+) -> Result<tonic::Response<T>, tonic::Status>
+where
+    F: Future<Output = Result<tonic::Response<T>, tonic::Status>>,
+{
+    match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, call)
+            .await
+            .unwrap_or_else(|_| Err(deadline_exceeded())),
+        None => call.await,
+    }
+}
+
+/// Runs a streaming service call, failing with `DeadlineExceeded` if the call doesn't complete within the timeout
+/// (if any). The timeout covers the entire RPC, so if the response stream is still open when it expires, the stream
+/// ends with a `DeadlineExceeded` error.
+#[cfg(feature = "_streaming")]
+pub async fn with_stream_timeout<F, S, T>(
+    timeout: Option<Duration>,
+    call: F,
+) -> Result<tonic::Response<DeadlineStream<S>>, tonic::Status>
+where
+    F: Future<Output = Result<tonic::Response<S>, tonic::Status>>,
+    S: Stream<Item = Result<T, tonic::Status>>,
+{
+    // A timeout too far off to be represented (which a client can ask for) is no deadline at all
+    let deadline = timeout.and_then(|timeout| Instant::now().checked_add(timeout));
+    let response = match deadline {
+        Some(deadline) => tokio::time::timeout_at(deadline, call)
+            .await
+            .unwrap_or_else(|_| Err(deadline_exceeded()))?,
+        None => call.await?,
+    };
+    Ok(response.map(|stream| DeadlineStream::new(stream, deadline)))
+}
+
+// *** DeadlineStream ***
+
+/// A response stream that ends with a `DeadlineExceeded` error once its deadline passes
+#[cfg(feature = "_streaming")]
+pub struct DeadlineStream<S> {
+    stream: Pin<Box<S>>,
+    sleep: Option<Pin<Box<Sleep>>>,
+    done: bool,
+}
+
+#[cfg(feature = "_streaming")]
+impl<S> DeadlineStream<S> {
+    fn new(stream: S, deadline: Option<Instant>) -> Self {
+        Self {
+            stream: Box::pin(stream),
+            sleep: deadline.map(|deadline| Box::pin(tokio::time::sleep_until(deadline))),
+            done: false,
+        }
+    }
+}
+
+#[cfg(feature = "_streaming")]
+impl<S, T> Stream for DeadlineStream<S>
+where
+    S: Stream<Item = Result<T, tonic::Status>>,
+{
+    type Item = Result<T, tonic::Status>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.done {
+            return Poll::Ready(None);
+        }
+
+        // Items that are already available win over an expired deadline
+        match self.stream.as_mut().poll_next(cx) {
+            Poll::Ready(None) => {
+                self.done = true;
+                return Poll::Ready(None);
+            }
+            Poll::Ready(Some(item)) => return Poll::Ready(Some(item)),
+            Poll::Pending => {}
+        }
+
+        match self.sleep.as_mut().map(|sleep| sleep.as_mut().poll(cx)) {
+            Some(Poll::Ready(())) => {
+                self.done = true;
+                Poll::Ready(Some(Err(deadline_exceeded())))
+            }
+            _ => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use http::{HeaderMap, HeaderValue};
+
+    use super::{request_timeout, with_timeout};
+
+    #[test]
+    fn test_request_timeout() {
+        let default = Some(Duration::from_secs(30));
+        let mut headers = HeaderMap::new();
+        assert_eq!(request_timeout(&headers, default), default);
+
+        headers.insert("x-request-timeout", HeaderValue::from_static("2.5"));
+        assert_eq!(
+            request_timeout(&headers, default),
+            Some(Duration::from_millis(2500))
+        );
+
+        headers.insert("grpc-timeout", HeaderValue::from_static("100m"));
+        assert_eq!(
+            request_timeout(&headers, default),
+            Some(Duration::from_millis(100))
+        );
+
+        // The client can't lift the default
+        headers.insert("grpc-timeout", HeaderValue::from_static("99999999H"));
+        assert_eq!(request_timeout(&headers, default), default);
+        assert_eq!(
+            request_timeout(&headers, None),
+            Some(Duration::from_secs(99999999 * 60 * 60))
+        );
+
+        // Invalid values are ignored
+        headers.insert("grpc-timeout", HeaderValue::from_static("123456789S"));
+        headers.insert("x-request-timeout", HeaderValue::from_static("-1"));
+        assert_eq!(request_timeout(&headers, None), None);
+    }
+
+    #[tokio::test]
+    async fn test_with_timeout() {
+        let response = with_timeout(Some(Duration::from_millis(10)), async {
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            Ok(tonic::Response::new(()))
+        })
+        .await;
+        assert_eq!(response.unwrap_err().code(), tonic::Code::DeadlineExceeded);
+
+        let response = with_timeout(None, async { Ok(tonic::Response::new(())) }).await;
+        assert!(response.is_ok());
+    }
+
+    #[cfg(feature = "_streaming")]
+    #[tokio::test(start_paused = true)]
+    async fn test_with_stream_timeout() {
+        use futures_util::StreamExt as _;
+
+        use super::with_stream_timeout;
+
+        // The call itself times out
+        let response = with_stream_timeout(Some(Duration::from_millis(10)), async {
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            Ok(tonic::Response::new(futures_util::stream::empty::<
+                Result<(), tonic::Status>,
+            >()))
+        })
+        .await;
+        assert_eq!(
+            response.err().unwrap().code(),
+            tonic::Code::DeadlineExceeded
+        );
+
+        // The stream yields what is ready, then ends with an error at the deadline
+        let stream = async_stream::stream! {
+            yield Ok(1);
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            yield Ok(2);
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            yield Ok(3);
+        };
+        let response = with_stream_timeout(Some(Duration::from_millis(10)), async {
+            Ok(tonic::Response::new(stream))
+        })
+        .await;
+        let items: Vec<_> = response.unwrap().into_inner().collect().await;
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[0].as_ref().unwrap(), &1);
+        assert_eq!(items[1].as_ref().unwrap(), &2);
+        assert_eq!(
+            items[2].as_ref().unwrap_err().code(),
+            tonic::Code::DeadlineExceeded
+        );
+
+        // Without a timeout the stream is untouched
+        let response = with_stream_timeout(None, async {
+            Ok(tonic::Response::new(futures_util::stream::iter([
+                Ok::<_, tonic::Status>(1),
+                Ok(2),
+            ])))
+        })
+        .await;
+        let items: Vec<_> = response.unwrap().into_inner().collect().await;
+        assert_eq!(items.len(), 2);
+
+        // A client can ask for a timeout too large to add to the current time
+        let mut headers = http::HeaderMap::new();
+        headers.insert("x-request-timeout", HeaderValue::from_static("1e19"));
+        let timeout = super::request_timeout(&headers, None);
+        assert!(timeout.is_some());
+        let response = with_stream_timeout(timeout, async {
+            Ok(tonic::Response::new(futures_util::stream::iter([Ok::<
+                _,
+                tonic::Status,
+            >(
+                1
+            )])))
+        })
+        .await;
+        let items: Vec<_> = response.unwrap().into_inner().collect().await;
+        assert_eq!(items.len(), 1);
+    }
+}
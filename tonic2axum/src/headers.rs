@@ -0,0 +1,551 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, LazyLock},
+};
+
+use base64::Engine as _;
+use http::{HeaderMap, HeaderName, HeaderValue};
+use tonic::metadata::{KeyAndValueRef, MetadataMap};
+
+/// The prefix grpc-gateway uses to mark HTTP headers that should be forwarded as gRPC metadata
+pub const GRPC_METADATA_PREFIX: &str = "grpc-metadata-";
+
+/// The prefix grpc-gateway uses for HTTP trailers that carry gRPC trailing metadata
+pub const GRPC_TRAILER_PREFIX: &str = "grpc-trailer-";
+
+// Metadata keys reserved by the gRPC protocol (ie. `grpc-timeout`), which clients can't set with the metadata prefix
+const RESERVED_METADATA_PREFIX: &str = "grpc-";
+
+/// Headers forwarded as gRPC metadata by the default [RequestHeaderPolicy] (with or without the
+/// [GRPC_METADATA_PREFIX]). These only carry tracing and request context, never credentials.
+pub const DEFAULT_ALLOWED_HEADERS: &[&str] = &[
+    "x-request-id",
+    "x-correlation-id",
+    "traceparent",
+    "tracestate",
+    "baggage",
+    "accept-language",
+    "user-agent",
+];
+
+/// Headers that are never forwarded as gRPC metadata by the default [RequestHeaderPolicy]. This includes hop-by-hop
+/// headers, headers that only describe the HTTP transport, and credentials.
+pub const DEFAULT_DENIED_HEADERS: &[&str] = &[
+    "connection",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "proxy-connection",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+    "host",
+    "content-length",
+    "expect",
+    "cookie",
+    "authorization",
+    "sec-websocket-key",
+    "sec-websocket-version",
+    "sec-websocket-extensions",
+];
+
+/// Metadata keys that are never sent to the HTTP client by the default [ResponseHeaderPolicy]. These are either
+/// internal to the gRPC protocol or describe the HTTP transport.
+pub const DEFAULT_DENIED_METADATA: &[&str] = &[
+    "grpc-status",
+    "grpc-message",
+    "grpc-status-details-bin",
+    "grpc-encoding",
+    "grpc-accept-encoding",
+    "grpc-timeout",
+    crate::HTTP_CODE_METADATA,
+    "content-type",
+    "content-length",
+    "connection",
+    "keep-alive",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+/// Metadata keys that the default [ResponseHeaderPolicy] sends as is (without a prefix), as they are meaningful to
+/// HTTP clients (ie. `location` for `201 Created` responses).
+pub const DEFAULT_VERBATIM_METADATA: &[&str] = &["location"];
+
+static DEFAULT_REQUEST_HEADER_POLICY: LazyLock<RequestHeaderPolicy> =
+    LazyLock::new(RequestHeaderPolicy::new);
+
+static DEFAULT_RESPONSE_HEADER_POLICY: LazyLock<ResponseHeaderPolicy> =
+    LazyLock::new(ResponseHeaderPolicy::new);
+
+fn make_header_set(names: &[&'static str]) -> HashSet<HeaderName> {
+    names
+        .iter()
+        .map(|name| HeaderName::from_static(name))
+        .collect()
+}
+
+// *** RequestHeaderPolicy ***
+
+#[derive(Clone, Debug)]
+struct RequestHeaderPolicyInner {
+    allowed: Option<HashSet<HeaderName>>,
+    denied: HashSet<HeaderName>,
+    prefix: Option<&'static str>,
+    renames: HashMap<HeaderName, HeaderName>,
+}
+
+/// The policy used to decide which HTTP request headers are forwarded to the gRPC service as request metadata.
+///
+/// The policy is looked up in the request extensions by every request helper, so it is typically installed on the
+/// router with an [axum::Extension] layer. When none is found, [RequestHeaderPolicy::default] is used.
+///
+/// The default policy is deliberately restrictive: only [DEFAULT_ALLOWED_HEADERS] are forwarded, with or without the
+/// metadata prefix. Services often trust their metadata (ie. headers set by an authenticating proxy in front of
+/// the gRPC server), so forwarding arbitrary client headers would let a REST client spoof them. Widening the policy
+/// with [RequestHeaderPolicy::allow_all] or [RequestHeaderPolicy::forward_all] should only be done when the service
+/// treats all metadata as untrusted client input.
+///
+/// Headers are processed as follows:
+/// 1. If the header starts with the metadata prefix (default: `grpc-metadata-`), the prefix is stripped. Reserved
+///    `grpc-` keys (ie. `grpc-timeout`) are then dropped
+/// 2. Denied headers are dropped
+/// 3. If an allowlist is set, headers not on it are dropped
+/// 4. Renames are applied
+#[derive(Clone, Debug)]
+pub struct RequestHeaderPolicy(Arc<RequestHeaderPolicyInner>);
+
+impl RequestHeaderPolicy {
+    /// Create a new policy that only forwards [DEFAULT_ALLOWED_HEADERS], denies [DEFAULT_DENIED_HEADERS] and strips the
+    /// [GRPC_METADATA_PREFIX].
+    pub fn new() -> Self {
+        Self(Arc::new(RequestHeaderPolicyInner {
+            allowed: Some(make_header_set(DEFAULT_ALLOWED_HEADERS)),
+            denied: make_header_set(DEFAULT_DENIED_HEADERS),
+            prefix: Some(GRPC_METADATA_PREFIX),
+            renames: HashMap::new(),
+        }))
+    }
+
+    /// Create a new policy that forwards every header verbatim.
+    ///
+    /// > NOTE: This forwards credentials and hop-by-hop headers to the service.
+    pub fn forward_all() -> Self {
+        Self(Arc::new(RequestHeaderPolicyInner {
+            allowed: None,
+            denied: HashSet::new(),
+            prefix: None,
+            renames: HashMap::new(),
+        }))
+    }
+
+    fn inner_mut(&mut self) -> &mut RequestHeaderPolicyInner {
+        Arc::make_mut(&mut self.0)
+    }
+
+    /// Only forward the given headers, replacing the current allowlist. The names are matched after the metadata
+    /// prefix is stripped, so `x-tenant` also allows `grpc-metadata-x-tenant`.
+    pub fn allow_only(mut self, names: impl IntoIterator<Item = HeaderName>) -> Self {
+        self.inner_mut().allowed = Some(names.into_iter().collect());
+        self
+    }
+
+    /// Forward every header that isn't denied (the allowlist is disabled).
+    ///
+    /// > NOTE: This lets clients set any metadata key the service may trust. See [RequestHeaderPolicy].
+    pub fn allow_all(mut self) -> Self {
+        self.inner_mut().allowed = None;
+        self
+    }
+
+    /// Forward the given header: it is added to the allowlist (if any) and removed from the denylist (ie.
+    /// [http::header::AUTHORIZATION]).
+    pub fn allow(mut self, name: HeaderName) -> Self {
+        let inner = self.inner_mut();
+        inner.denied.remove(&name);
+        if let Some(allowed) = &mut inner.allowed {
+            allowed.insert(name);
+        }
+        self
+    }
+
+    /// Never forward the given header.
+    pub fn deny(mut self, name: HeaderName) -> Self {
+        self.inner_mut().denied.insert(name);
+        self
+    }
+
+    /// Set the prefix that marks headers to be forwarded with the prefix stripped (default: `grpc-metadata-`).
+    /// `None` disables prefix handling. The prefix is matched case-insensitively.
+    pub fn metadata_prefix(mut self, prefix: Option<&'static str>) -> Self {
+        self.inner_mut().prefix = prefix;
+        self
+    }
+
+    /// Forward the header `from` as the metadata key `to`. Renames are applied after filtering.
+    pub fn rename(mut self, from: HeaderName, to: HeaderName) -> Self {
+        self.inner_mut().renames.insert(from, to);
+        self
+    }
+
+    /// Apply the policy to the given headers, producing the gRPC request metadata.
+    pub fn apply(&self, headers: HeaderMap) -> MetadataMap {
+        let inner = &*self.0;
+        let mut forwarded = HeaderMap::with_capacity(headers.len());
+        let mut current = None;
+
+        // Only the first value of a multi-valued header has a name, the rest reuse the previous one
+        for (name, value) in headers {
+            if let Some(name) = name {
+                current = inner.forwarded_name(name);
+            }
+            if let Some(name) = &current {
+                forwarded.append(name.clone(), value);
+            }
+        }
+
+        MetadataMap::from_headers(forwarded)
+    }
+}
+
+impl Default for RequestHeaderPolicy {
+    fn default() -> Self {
+        DEFAULT_REQUEST_HEADER_POLICY.clone()
+    }
+}
+
+impl RequestHeaderPolicyInner {
+    fn forwarded_name(&self, name: HeaderName) -> Option<HeaderName> {
+        let (name, prefixed) = match self.prefix {
+            Some(prefix) if name.as_str().len() > prefix.len() => {
+                // Header names are always lowercase, so only the prefix needs normalizing
+                let (head, tail) = name.as_str().split_at(prefix.len());
+                if head.eq_ignore_ascii_case(prefix) {
+                    (HeaderName::from_bytes(tail.as_bytes()).ok()?, true)
+                } else {
+                    (name, false)
+                }
+            }
+            _ => (name, false),
+        };
+
+        if prefixed && name.as_str().starts_with(RESERVED_METADATA_PREFIX) {
+            return None;
+        }
+        if self.denied.contains(&name) {
+            return None;
+        }
+        if let Some(allowed) = &self.allowed
+            && !allowed.contains(&name)
+        {
+            return None;
+        }
+
+        match self.renames.get(&name) {
+            Some(renamed) => Some(renamed.clone()),
+            None => Some(name),
+        }
+    }
+}
+
+// *** ResponseHeaderPolicy ***
+
+#[derive(Clone, Debug)]
+struct ResponseHeaderPolicyInner {
+    denied: HashSet<HeaderName>,
+    verbatim: HashSet<HeaderName>,
+    header_prefix: Option<&'static str>,
+    trailer_prefix: Option<&'static str>,
+}
+
+/// The policy used to decide how gRPC response metadata is sent to the HTTP client.
+///
+/// The policy is looked up in the request extensions when building [crate::ResponseOptions], so it is typically
+/// installed on the router with an [axum::Extension] layer. When none is found, [ResponseHeaderPolicy::default] is
+/// used.
+///
+/// Metadata is processed as follows:
+/// 1. Denied keys are dropped
+/// 2. Keys marked as verbatim are sent as is, all others get the header prefix (default: `grpc-metadata-`) or trailer
+///    prefix (default: `grpc-trailer-`)
+/// 3. Binary (`-bin`) values are sent as standard padded base64
+#[derive(Clone, Debug)]
+pub struct ResponseHeaderPolicy(Arc<ResponseHeaderPolicyInner>);
+
+impl ResponseHeaderPolicy {
+    /// Create a new policy that denies [DEFAULT_DENIED_METADATA], sends [DEFAULT_VERBATIM_METADATA] as is and
+    /// prefixes all other metadata with [GRPC_METADATA_PREFIX] (headers) or [GRPC_TRAILER_PREFIX] (trailers).
+    pub fn new() -> Self {
+        Self(Arc::new(ResponseHeaderPolicyInner {
+            denied: make_header_set(DEFAULT_DENIED_METADATA),
+            verbatim: make_header_set(DEFAULT_VERBATIM_METADATA),
+            header_prefix: Some(GRPC_METADATA_PREFIX),
+            trailer_prefix: Some(GRPC_TRAILER_PREFIX),
+        }))
+    }
+
+    /// Create a new policy that sends all metadata verbatim (other than binary values which are still sent as
+    /// standard base64).
+    pub fn forward_all() -> Self {
+        Self(Arc::new(ResponseHeaderPolicyInner {
+            denied: HashSet::new(),
+            verbatim: HashSet::new(),
+            header_prefix: None,
+            trailer_prefix: None,
+        }))
+    }
+
+    fn inner_mut(&mut self) -> &mut ResponseHeaderPolicyInner {
+        Arc::make_mut(&mut self.0)
+    }
+
+    /// Remove the given key from the denylist so that it is sent to the client.
+    pub fn allow(mut self, name: HeaderName) -> Self {
+        self.inner_mut().denied.remove(&name);
+        self
+    }
+
+    /// Never send the given metadata key to the client.
+    pub fn deny(mut self, name: HeaderName) -> Self {
+        self.inner_mut().denied.insert(name);
+        self
+    }
+
+    /// Send the given metadata key without a prefix (ie. `cache-control` or `x-request-id`).
+    pub fn verbatim(mut self, name: HeaderName) -> Self {
+        self.inner_mut().verbatim.insert(name);
+        self
+    }
+
+    /// Set the prefix added to metadata sent as HTTP headers (default: `grpc-metadata-`). `None` disables it.
+    pub fn header_prefix(mut self, prefix: Option<&'static str>) -> Self {
+        self.inner_mut().header_prefix = prefix;
+        self
+    }
+
+    /// Set the prefix added to trailing metadata sent as HTTP trailers (default: `grpc-trailer-`). `None` disables
+    /// it.
+    pub fn trailer_prefix(mut self, prefix: Option<&'static str>) -> Self {
+        self.inner_mut().trailer_prefix = prefix;
+        self
+    }
+
+    /// Apply the policy to (initial) response metadata, producing HTTP headers.
+    pub fn apply_headers(&self, metadata: &MetadataMap, headers: &mut HeaderMap) {
+        self.0.apply(metadata, self.0.header_prefix, headers);
+    }
+
+    /// Apply the policy to trailing metadata, producing HTTP trailers.
+    pub fn apply_trailers(&self, metadata: &MetadataMap, trailers: &mut HeaderMap) {
+        self.0.apply(metadata, self.0.trailer_prefix, trailers);
+    }
+
+    /// Apply the policy to metadata sent in its own frame, where no prefix is needed.
+    #[cfg(feature = "ws-streaming")]
+    pub(crate) fn apply_metadata(&self, metadata: &MetadataMap, headers: &mut HeaderMap) {
+        self.0.apply(metadata, None, headers);
+    }
+}
+
+impl Default for ResponseHeaderPolicy {
+    fn default() -> Self {
+        DEFAULT_RESPONSE_HEADER_POLICY.clone()
+    }
+}
+
+impl ResponseHeaderPolicyInner {
+    fn apply(&self, metadata: &MetadataMap, prefix: Option<&str>, headers: &mut HeaderMap) {
+        for entry in metadata.iter() {
+            let (key, value) = match entry {
+                KeyAndValueRef::Ascii(key, value) => (
+                    key.as_str(),
+                    HeaderValue::from_bytes(value.as_encoded_bytes()),
+                ),
+                KeyAndValueRef::Binary(key, value) => {
+                    // Tonic stores binary values as unpadded base64, so normalize to what REST clients expect
+                    let value = match value.to_bytes() {
+                        Ok(bytes) => base64::engine::general_purpose::STANDARD.encode(bytes),
+                        Err(_) => continue,
+                    };
+                    (key.as_str(), HeaderValue::try_from(value))
+                }
+            };
+
+            let Ok(value) = value else {
+                continue;
+            };
+            if let Some(name) = self.header_name(key, prefix) {
+                headers.append(name, value);
+            }
+        }
+    }
+
+    fn header_name(&self, key: &str, prefix: Option<&str>) -> Option<HeaderName> {
+        let name = HeaderName::from_bytes(key.as_bytes()).ok()?;
+        if self.denied.contains(&name) {
+            return None;
+        }
+
+        match prefix {
+            Some(prefix) if !self.verbatim.contains(&name) => {
+                HeaderName::try_from(format!("{prefix}{key}")).ok()
+            }
+            _ => Some(name),
+        }
+    }
+}
+
+/// Metadata bound from something other than the request headers (ie. the query string of a WebSocket upgrade). It is
+/// added to the request metadata as is, as the header policy doesn't apply to it.
+#[cfg(feature = "ws-streaming")]
+#[derive(Clone, Debug)]
+pub(crate) struct BoundMetadata(pub(crate) HeaderMap);
+
+/// Converts request headers into gRPC metadata using the policy found in the extensions (or the default). The policy
+/// is removed from the extensions so it isn't passed on to the service.
+pub(crate) fn request_metadata(
+    headers: HeaderMap,
+    extensions: &mut http::Extensions,
+) -> MetadataMap {
+    let metadata = match extensions.remove::<RequestHeaderPolicy>() {
+        Some(policy) => policy.apply(headers),
+        None => DEFAULT_REQUEST_HEADER_POLICY.apply(headers),
+    };
+
+    // Bound values replace any forwarded headers of the same name
+    #[cfg(feature = "ws-streaming")]
+    if let Some(BoundMetadata(bound)) = extensions.remove::<BoundMetadata>() {
+        let mut headers = metadata.into_headers();
+        headers.extend(bound);
+        return MetadataMap::from_headers(headers);
+    }
+
+    metadata
+}
+
+#[cfg(test)]
+mod tests {
+    use http::{HeaderMap, HeaderName, HeaderValue, header};
+    use tonic::metadata::{MetadataMap, MetadataValue};
+
+    use super::{RequestHeaderPolicy, ResponseHeaderPolicy};
+
+    fn make_headers() -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::HOST, HeaderValue::from_static("example.com"));
+        headers.insert(header::AUTHORIZATION, HeaderValue::from_static("Bearer x"));
+        headers.insert(header::COOKIE, HeaderValue::from_static("a=b"));
+        headers.insert("x-request-id", HeaderValue::from_static("123"));
+        headers.insert("grpc-metadata-tenant", HeaderValue::from_static("acme"));
+        headers.append("x-tag", HeaderValue::from_static("a"));
+        headers.append("x-tag", HeaderValue::from_static("b"));
+        headers
+    }
+
+    #[test]
+    fn test_default_policy() {
+        let metadata = RequestHeaderPolicy::default().apply(make_headers());
+        let headers = metadata.into_headers();
+
+        assert!(!headers.contains_key(header::HOST));
+        assert!(!headers.contains_key(header::AUTHORIZATION));
+        assert!(!headers.contains_key(header::COOKIE));
+        assert!(!headers.contains_key("grpc-metadata-tenant"));
+        assert!(!headers.contains_key("tenant"));
+        assert_eq!(headers.get("x-request-id").unwrap(), "123");
+        assert!(!headers.contains_key("x-tag"));
+    }
+
+    #[test]
+    fn test_metadata_prefix_spoofing() {
+        let mut headers = HeaderMap::new();
+        headers.insert("grpc-metadata-x-user-id", HeaderValue::from_static("admin"));
+        headers.insert("grpc-metadata-grpc-timeout", HeaderValue::from_static("1S"));
+        headers.insert(
+            "grpc-metadata-x-request-id",
+            HeaderValue::from_static("123"),
+        );
+
+        // The prefix doesn't get around the allowlist
+        let forwarded = RequestHeaderPolicy::default()
+            .apply(headers.clone())
+            .into_headers();
+        assert_eq!(forwarded.len(), 1);
+        assert_eq!(forwarded.get("x-request-id").unwrap(), "123");
+
+        // Reserved keys are dropped even without an allowlist
+        let forwarded = RequestHeaderPolicy::new()
+            .allow_all()
+            .apply(headers)
+            .into_headers();
+        assert_eq!(forwarded.len(), 2);
+        assert_eq!(forwarded.get("x-user-id").unwrap(), "admin");
+        assert!(!forwarded.contains_key("grpc-timeout"));
+    }
+
+    #[test]
+    fn test_allow_all() {
+        let policy = RequestHeaderPolicy::new()
+            .allow_all()
+            .allow(header::AUTHORIZATION);
+        let headers = policy.apply(make_headers()).into_headers();
+
+        assert!(!headers.contains_key(header::HOST));
+        assert_eq!(headers.get(header::AUTHORIZATION).unwrap(), "Bearer x");
+        assert_eq!(headers.get_all("x-tag").iter().count(), 2);
+    }
+
+    #[test]
+    fn test_allowlist_and_renames() {
+        let policy = RequestHeaderPolicy::new()
+            .allow_only([HeaderName::from_static("tenant")])
+            .allow(header::AUTHORIZATION)
+            .rename(
+                HeaderName::from_static("tenant"),
+                HeaderName::from_static("x-tenant"),
+            );
+        let headers = policy.apply(make_headers()).into_headers();
+
+        assert_eq!(headers.len(), 2);
+        assert_eq!(headers.get(header::AUTHORIZATION).unwrap(), "Bearer x");
+        assert_eq!(headers.get("x-tenant").unwrap(), "acme");
+    }
+
+    #[test]
+    fn test_forward_all() {
+        let headers = make_headers();
+        let count = headers.len();
+        let metadata = RequestHeaderPolicy::forward_all().apply(headers);
+
+        assert_eq!(metadata.len(), count);
+    }
+
+    fn make_metadata() -> MetadataMap {
+        let mut metadata = MetadataMap::new();
+        metadata.insert("x-request-id", MetadataValue::from_static("123"));
+        metadata.insert("grpc-status", MetadataValue::from_static("0"));
+        metadata.insert_bin("trace-bin", MetadataValue::from_bytes(b"ab"));
+        metadata
+    }
+
+    #[test]
+    fn test_default_response_policy() {
+        let policy = ResponseHeaderPolicy::default();
+        let mut headers = HeaderMap::new();
+        policy.apply_headers(&make_metadata(), &mut headers);
+
+        assert_eq!(headers.len(), 2);
+        assert_eq!(headers.get("grpc-metadata-x-request-id").unwrap(), "123");
+        assert_eq!(headers.get("grpc-metadata-trace-bin").unwrap(), "YWI=");
+
+        let mut trailers = HeaderMap::new();
+        policy
+            .verbatim(HeaderName::from_static("x-request-id"))
+            .apply_trailers(&make_metadata(), &mut trailers);
+
+        assert_eq!(trailers.get("x-request-id").unwrap(), "123");
+        assert_eq!(trailers.get("grpc-trailer-trace-bin").unwrap(), "YWI=");
+    }
+}
@@ -0,0 +1,91 @@
+use axum::{body::Bytes, response::IntoResponse as _};
+
+use crate::ResponseOptions;
+
+/// Returns the content type of a request body (or an empty string when there is none), which is what an `HttpBody`
+/// request message carries along with the raw body
+pub fn request_content_type(headers: &http::HeaderMap) -> String {
+    headers
+        .get(http::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Converts a Tonic response with a `google.api.HttpBody` message into an HTTP response. The body is sent as is, with
+/// the content type and data taken from the message by the given function. An empty content type falls back to
+/// `application/octet-stream`. Response metadata and the status code are handled as for
+/// [make_response_with](crate::make_response_with).
+pub fn make_http_body_response<T, F>(
+    response: Result<tonic::Response<T>, tonic::Status>,
+    options: ResponseOptions,
+    parts: F,
+) -> http::Response<axum::body::Body>
+where
+    F: FnOnce(T) -> (String, Bytes),
+{
+    let (mut meta, message, ext) = match response {
+        Ok(response) => response.into_parts(),
+        Err(status) => return crate::make_err_response(status, &options),
+    };
+    let status_code = options.success_status(&mut meta);
+    let mut headers = http::HeaderMap::new();
+    options.header_policy.apply_headers(&meta, &mut headers);
+
+    let (content_type, data) = parts(message);
+    if !content_type.is_empty()
+        && let Ok(value) = http::HeaderValue::from_str(&content_type)
+    {
+        headers.insert(http::header::CONTENT_TYPE, value);
+    }
+
+    match status_code {
+        http::StatusCode::NO_CONTENT
+        | http::StatusCode::RESET_CONTENT
+        | http::StatusCode::NOT_MODIFIED => (status_code, headers, ext).into_response(),
+        _ => (status_code, headers, ext, data).into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_make_http_body_response() {
+        let parts = |(content_type, data): (&str, &str)| {
+            (
+                content_type.to_string(),
+                Bytes::copy_from_slice(data.as_bytes()),
+            )
+        };
+
+        let response = make_http_body_response(
+            Ok(tonic::Response::new(("image/png", "png"))),
+            ResponseOptions::default(),
+            parts,
+        );
+        assert_eq!(response.status(), http::StatusCode::OK);
+        assert_eq!(response.headers()["content-type"], "image/png");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await;
+        assert_eq!(body.unwrap(), "png");
+
+        // No content type in the message
+        let response = make_http_body_response(
+            Ok(tonic::Response::new(("", "data"))),
+            ResponseOptions::default(),
+            parts,
+        );
+        assert_eq!(
+            response.headers()["content-type"],
+            "application/octet-stream"
+        );
+
+        let response = make_http_body_response(
+            Err(tonic::Status::not_found("missing")),
+            ResponseOptions::default(),
+            parts,
+        );
+        assert_eq!(response.status(), http::StatusCode::NOT_FOUND);
+    }
+}
@@ -1,52 +1,102 @@
-use std::mem;
-
 use axum::Json;
 use axum::response::IntoResponse as _;
 use serde::Serialize;
-use tonic::metadata::MetadataMap;
 
+mod deadline;
+mod headers;
+mod http_body;
+mod options;
 #[cfg(feature = "_streaming")]
 mod streaming;
 
+#[cfg(feature = "_streaming")]
+pub use deadline::{DeadlineStream, with_stream_timeout};
+pub use deadline::{GRPC_TIMEOUT_HEADER, REQUEST_TIMEOUT_HEADER, request_timeout, with_timeout};
+pub use headers::{
+    DEFAULT_ALLOWED_HEADERS, DEFAULT_DENIED_HEADERS, DEFAULT_DENIED_METADATA,
+    DEFAULT_VERBATIM_METADATA, GRPC_METADATA_PREFIX, GRPC_TRAILER_PREFIX, RequestHeaderPolicy,
+    ResponseHeaderPolicy,
+};
+pub use http_body::{make_http_body_response, request_content_type};
+pub use options::{
+    COLLECT_QUERY_PARAM, CollectLimits, HTTP_CODE_METADATA, Heartbeat,
+    PROTOBUF_STREAM_CONTENT_TYPE, ResponseOptions, StreamFormat, collect_requested,
+};
+#[cfg(feature = "_streaming")]
+pub use streaming::StreamLimits;
+
 #[cfg(feature = "http-streaming")]
-pub use streaming::{make_stream_request, make_stream_response};
+pub use streaming::{
+    OCTET_STREAM_CONTENT_TYPE, collect_stream_response, make_http_body_stream_request,
+    make_http_body_stream_response, make_negotiated_stream_request,
+    make_negotiated_stream_request_with, make_negotiated_stream_response,
+    make_proto_stream_request, make_proto_stream_response, make_raw_stream_request,
+    make_raw_stream_response, make_sse_response, make_stream_request, make_stream_request_with,
+    make_stream_response, make_stream_response_with,
+};
 
 #[cfg(feature = "ws-streaming")]
 pub use streaming::{
-    close_ws, make_ws_request, make_ws_stream_request, process_ws_response,
-    process_ws_stream_response, upgrade_to_ws,
+    MuxCall, MuxSink, WS_END_OF_INPUT_BINARY, WS_END_OF_INPUT_TEXT, WS_JSON_PROTOCOL,
+    WS_PROTOBUF_PROTOCOL, WsKeepalive, WsLimits, WsMetadataBinding, WsOptions, WsSink, WsStream,
+    close_mux_call, close_ws, make_mux_request, make_mux_stream_request, make_ws_request,
+    make_ws_stream_request, make_ws_stream_request_with, process_mux_response,
+    process_mux_stream_response, process_ws_response, process_ws_stream_response,
+    process_ws_unary_calls, upgrade_to_ws, upgrade_to_ws_mux,
 };
 
-/// Converts the parts of an HTTP request into a Tonic request
+/// Converts the parts of an HTTP request into a Tonic request. Headers are forwarded as metadata according to the
+/// [RequestHeaderPolicy] found in the extensions (or the default policy).
 pub fn make_request<T>(
     headers: http::HeaderMap,
-    extensions: http::Extensions,
+    mut extensions: http::Extensions,
     message: T,
 ) -> tonic::Request<T> {
-    let metadata = MetadataMap::from_headers(headers);
+    let metadata = headers::request_metadata(headers, &mut extensions);
     tonic::Request::from_parts(metadata, extensions, message)
 }
 
-/// Converts a Tonic response into an HTTP response
+/// Converts a Tonic response into an HTTP response using the default [ResponseOptions] (see [make_response_with]).
 pub fn make_response<T: Serialize>(
     response: Result<tonic::Response<T>, tonic::Status>,
+) -> http::Response<axum::body::Body> {
+    make_response_with(response, ResponseOptions::default())
+}
+
+/// Converts a Tonic response into an HTTP response. Response metadata is mapped onto HTTP headers according to the
+/// [ResponseHeaderPolicy] in the options. Successful calls use the status code from the options, unless the service
+/// overrides it via the [HTTP_CODE_METADATA] metadata key.
+pub fn make_response_with<T: Serialize>(
+    response: Result<tonic::Response<T>, tonic::Status>,
+    options: ResponseOptions,
 ) -> http::Response<axum::body::Body> {
     match response {
-        Ok(response) => make_ok_response(response),
-        Err(status) => make_err_response(status),
+        Ok(response) => make_ok_response(response, &options),
+        Err(status) => make_err_response(status, &options),
     }
 }
 
 fn make_ok_response<T: Serialize>(
     response: tonic::Response<T>,
+    options: &ResponseOptions,
 ) -> http::Response<axum::body::Body> {
-    let (meta, message, ext) = response.into_parts();
-    let headers = meta.into_headers();
+    let (mut meta, message, ext) = response.into_parts();
+    let status_code = options.success_status(&mut meta);
+    let mut headers = http::HeaderMap::new();
+    options.header_policy.apply_headers(&meta, &mut headers);
 
-    (http::StatusCode::OK, headers, ext, Json(message)).into_response()
+    match status_code {
+        http::StatusCode::NO_CONTENT
+        | http::StatusCode::RESET_CONTENT
+        | http::StatusCode::NOT_MODIFIED => (status_code, headers, ext).into_response(),
+        _ => (status_code, headers, ext, Json(message)).into_response(),
+    }
 }
 
-fn make_err_response(mut status: tonic::Status) -> http::Response<axum::body::Body> {
+fn make_err_response(
+    status: tonic::Status,
+    options: &ResponseOptions,
+) -> http::Response<axum::body::Body> {
     let status_code = match status.code() {
         tonic::Code::Ok => http::StatusCode::OK,
         tonic::Code::Cancelled => http::StatusCode::REQUEST_TIMEOUT,
@@ -66,8 +116,10 @@ fn make_err_response(mut status: tonic::Status) -> http::Response<axum::body::Bo
         tonic::Code::DataLoss => http::StatusCode::INTERNAL_SERVER_ERROR,
         tonic::Code::Unauthenticated => http::StatusCode::UNAUTHORIZED,
     };
-    let metadata = mem::replace(status.metadata_mut(), MetadataMap::new());
-    let headers = metadata.into_headers();
+    let mut headers = http::HeaderMap::new();
+    options
+        .header_policy
+        .apply_headers(status.metadata(), &mut headers);
 
     let mut msg = status.message();
     if msg.is_empty() {
@@ -0,0 +1,362 @@
+use std::time::Duration;
+
+use crate::ResponseHeaderPolicy;
+
+/// Reserved metadata key a service can set on a unary response to override the HTTP status code of a successful call
+/// (ie. `201`). Only 2xx and 3xx codes are honored. The key is always removed before the response is sent.
+pub const HTTP_CODE_METADATA: &str = "x-http-code";
+
+/// Content type of a length-prefixed protobuf stream, where each message is preceded by its length as a 4-byte big
+/// endian integer
+pub const PROTOBUF_STREAM_CONTENT_TYPE: &str = "application/x-protobuf-stream";
+
+/// Query parameter a client can set to `false` to have a stream response collected into a single JSON response (see
+/// [collect_requested]). The `$` prefix keeps it from colliding with a request field named `stream` bound from the
+/// query string.
+pub const COLLECT_QUERY_PARAM: &str = "$stream";
+
+pub(crate) const NDJSON_CONTENT_TYPE: &str = "application/x-ndjson";
+pub(crate) const JSON_CONTENT_TYPE: &str = "application/json";
+
+/// The wire format of an HTTP streaming request or response body
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StreamFormat {
+    /// JSON Lines (one JSON message per line), sent as `application/x-ndjson`
+    #[default]
+    JsonLines,
+    /// A single JSON array holding the messages, sent as `application/json`
+    JsonArray,
+    /// Length-prefixed protobuf messages (see [PROTOBUF_STREAM_CONTENT_TYPE])
+    Protobuf,
+}
+
+impl StreamFormat {
+    fn from_media_type(media_type: &str) -> Option<Self> {
+        let media_type = media_type.split(';').next()?.trim();
+        [
+            (NDJSON_CONTENT_TYPE, Self::JsonLines),
+            (JSON_CONTENT_TYPE, Self::JsonArray),
+            (PROTOBUF_STREAM_CONTENT_TYPE, Self::Protobuf),
+        ]
+        .into_iter()
+        .find_map(|(content_type, format)| {
+            media_type
+                .eq_ignore_ascii_case(content_type)
+                .then_some(format)
+        })
+    }
+
+    /// The content type of a body in this format
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::JsonLines => NDJSON_CONTENT_TYPE,
+            Self::JsonArray => JSON_CONTENT_TYPE,
+            Self::Protobuf => PROTOBUF_STREAM_CONTENT_TYPE,
+        }
+    }
+
+    /// The format of a request body, based on its `Content-Type` header (default: JSON Lines). `application/json` only
+    /// selects a JSON array when `json_array` is set, as clients commonly send it with JSON Lines bodies.
+    pub fn from_content_type(headers: &http::HeaderMap, json_array: bool) -> Self {
+        match headers
+            .get(http::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(Self::from_media_type)
+        {
+            Some(Self::JsonArray) if !json_array => Self::JsonLines,
+            format => format.unwrap_or_default(),
+        }
+    }
+
+    /// The stream format requested for a response body, based on the `Accept` header: JSON Lines or length-prefixed
+    /// protobuf (default: JSON Lines). `application/json` isn't treated as a stream format here, as clients commonly
+    /// send it when expecting JSON Lines. See [StreamFormat::from_accept_or] to also negotiate JSON arrays.
+    pub fn from_accept(headers: &http::HeaderMap) -> Self {
+        Self::negotiate(headers, false).unwrap_or(Self::JsonLines)
+    }
+
+    /// The format requested for a response body, based on the `Accept` header, where `application/json` selects a JSON
+    /// array. The listed format with the highest quality value is used (ties go to the first listed, and formats with
+    /// `q=0` are never used), or the given default if none are listed.
+    pub fn from_accept_or(headers: &http::HeaderMap, default: Self) -> Self {
+        Self::negotiate(headers, true).unwrap_or(default)
+    }
+
+    fn negotiate(headers: &http::HeaderMap, json_array: bool) -> Option<Self> {
+        let mut best: Option<(Self, f32)> = None;
+        let media_ranges = headers
+            .get_all(http::header::ACCEPT)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','));
+
+        for media_range in media_ranges {
+            let format = match Self::from_media_type(media_range) {
+                Some(Self::JsonArray) if !json_array => continue,
+                Some(format) => format,
+                None => continue,
+            };
+            let quality = accept_quality(media_range);
+            if quality > 0.0 && best.is_none_or(|(_, best)| quality > best) {
+                best = Some((format, quality));
+            }
+        }
+
+        best.map(|(format, _)| format)
+    }
+}
+
+/// The quality value (`q` parameter) of an `Accept` media range (default: 1)
+fn accept_quality(media_range: &str) -> f32 {
+    media_range
+        .split(';')
+        .skip(1)
+        .find_map(|param| {
+            let (name, value) = param.split_once('=')?;
+            name.trim().eq_ignore_ascii_case("q").then_some(value)
+        })
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(1.0)
+}
+
+/// Returns true if the client asked for a stream response to be collected into a single JSON response. This decides
+/// between the collected and streamed formats (see `Builder::collect_streams` and `Builder::json_array_streams` in
+/// tonic2axum-build), based on the format preferred in the `Accept` header:
+/// 1. A stream format (JSON Lines or length-prefixed protobuf) is streamed
+/// 2. `application/json` is collected, unless JSON array streams are enabled (`json_array`), where it is streamed as a
+///    JSON array unless the `$stream=false` query parameter (see [COLLECT_QUERY_PARAM]) is passed
+/// 3. Otherwise (ie. the header is missing or `*/*`), the response is only collected when the `$stream=false` query
+///    parameter is passed, for clients that can't set headers
+pub fn collect_requested(uri: &http::Uri, headers: &http::HeaderMap, json_array: bool) -> bool {
+    match StreamFormat::negotiate(headers, true) {
+        Some(StreamFormat::JsonArray) if !json_array => true,
+        Some(StreamFormat::JsonArray) | None => uri.query().is_some_and(|query| {
+            query.split('&').any(|param| {
+                matches!(param.split_once('='), Some((name, "false"))
+                    if name == COLLECT_QUERY_PARAM || name.eq_ignore_ascii_case("%24stream"))
+            })
+        }),
+        Some(_) => false,
+    }
+}
+
+/// Limits on a stream response that is collected into a single JSON response (see
+/// [ResponseOptions::collect_stream]). Limits can be installed on the router with an [axum::Extension] layer. When none
+/// are found, [CollectLimits::default] is used. A stream exceeding them fails with `RESOURCE_EXHAUSTED`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CollectLimits {
+    pub(crate) max_items: usize,
+    pub(crate) max_bytes: usize,
+}
+
+impl CollectLimits {
+    /// Create limits of 10,000 items and 4 MiB of JSON
+    pub const fn new() -> Self {
+        Self {
+            max_items: 10_000,
+            max_bytes: 4 * 1024 * 1024,
+        }
+    }
+
+    /// Set the maximum number of items collected
+    pub const fn max_items(mut self, max_items: usize) -> Self {
+        self.max_items = max_items;
+        self
+    }
+
+    /// Set the maximum size of the collected JSON array in bytes
+    pub const fn max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+}
+
+impl Default for CollectLimits {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Heartbeats sent on a stream response while the service stream is idle, so proxies and load balancers don't close
+/// the connection. Heartbeats can be installed with an [axum::Extension] layer, on the router or on individual routes.
+/// JSON Lines streams send a blank line (or the keepalive object, if set), JSON array streams send whitespace and SSE
+/// streams send a comment. Length-prefixed protobuf and collected streams have no no-op data, so they are sent as is.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Heartbeat {
+    pub(crate) interval: Duration,
+    pub(crate) keepalive: Option<String>,
+}
+
+impl Heartbeat {
+    /// Create heartbeats sent after the stream has been idle for the given interval
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            keepalive: None,
+        }
+    }
+
+    /// Send the given JSON object as the heartbeat line of JSON Lines streams instead of a blank line (ie.
+    /// `{"keepalive":true}`). It must be a single line and clients are expected to skip it.
+    pub fn keepalive_object(mut self, json: impl Into<String>) -> Self {
+        self.keepalive = Some(json.into());
+        self
+    }
+}
+
+/// Options that control how a Tonic response is converted into an HTTP response. Generated handlers build these from
+/// the request before it is handed to the service.
+#[derive(Clone, Debug, Default)]
+pub struct ResponseOptions {
+    pub(crate) header_policy: ResponseHeaderPolicy,
+    pub(crate) status_code: http::StatusCode,
+    pub(crate) stream_envelope: bool,
+    pub(crate) stream_format: StreamFormat,
+    pub(crate) collect_stream: bool,
+    pub(crate) collect_limits: CollectLimits,
+    pub(crate) heartbeat: Option<Heartbeat>,
+}
+
+impl ResponseOptions {
+    /// Create response options from the request extensions, picking up a [ResponseHeaderPolicy], [CollectLimits] and
+    /// [Heartbeat] if they were installed.
+    pub fn from_extensions(extensions: &http::Extensions) -> Self {
+        Self {
+            header_policy: extensions
+                .get::<ResponseHeaderPolicy>()
+                .cloned()
+                .unwrap_or_default(),
+            status_code: http::StatusCode::OK,
+            stream_envelope: false,
+            stream_format: StreamFormat::JsonLines,
+            collect_stream: false,
+            collect_limits: extensions
+                .get::<CollectLimits>()
+                .copied()
+                .unwrap_or_default(),
+            heartbeat: extensions.get::<Heartbeat>().cloned(),
+        }
+    }
+
+    /// Set the policy used to map response metadata onto HTTP headers and trailers.
+    pub fn header_policy(mut self, policy: ResponseHeaderPolicy) -> Self {
+        self.header_policy = policy;
+        self
+    }
+
+    /// Set the HTTP status code returned for successful unary calls (default: `200 OK`). A `204 No Content`,
+    /// `205 Reset Content` or `304 Not Modified` response is sent without a body.
+    pub fn status_code(mut self, status_code: http::StatusCode) -> Self {
+        self.status_code = status_code;
+        self
+    }
+
+    /// Wrap each line of a JSON Lines stream response in a grpc-gateway style envelope (default: `false`). Each item
+    /// is sent as `{"result": ...}` and a failed stream ends with a final `{"error": {"code", "message", "details"}}`
+    /// line, so clients that can't read HTTP trailers still see the error.
+    pub fn stream_envelope(mut self, enabled: bool) -> Self {
+        self.stream_envelope = enabled;
+        self
+    }
+
+    /// Set the wire format of stream responses sent by the negotiating stream helpers (default: JSON Lines). This is
+    /// normally based on the request's `Accept` header (see [StreamFormat::from_accept]).
+    pub fn stream_format(mut self, format: StreamFormat) -> Self {
+        self.stream_format = format;
+        self
+    }
+
+    /// Collect stream responses into a single JSON array sent as a regular response (default: `false`). This is
+    /// normally based on the request (see [collect_requested]).
+    pub fn collect_stream(mut self, enabled: bool) -> Self {
+        self.collect_stream = enabled;
+        self
+    }
+
+    /// Set the limits on collected stream responses.
+    pub fn collect_limits(mut self, limits: CollectLimits) -> Self {
+        self.collect_limits = limits;
+        self
+    }
+
+    /// Send heartbeats on stream responses while the service stream is idle (default: none).
+    pub fn heartbeat(mut self, heartbeat: Heartbeat) -> Self {
+        self.heartbeat = Some(heartbeat);
+        self
+    }
+
+    /// Determine the status code for a successful response, consuming any [HTTP_CODE_METADATA] override
+    pub(crate) fn success_status(
+        &self,
+        metadata: &mut tonic::metadata::MetadataMap,
+    ) -> http::StatusCode {
+        metadata
+            .remove(HTTP_CODE_METADATA)
+            .and_then(|value| value.to_str().ok()?.trim().parse::<u16>().ok())
+            .and_then(|code| http::StatusCode::from_u16(code).ok())
+            .filter(|code| code.is_success() || code.is_redirection())
+            .unwrap_or(self.status_code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HTTP_CODE_METADATA, ResponseOptions, StreamFormat};
+
+    #[test]
+    fn test_from_accept_quality() {
+        let format = |accept: &'static str| {
+            let mut headers = http::HeaderMap::new();
+            headers.insert(http::header::ACCEPT, accept.parse().unwrap());
+            StreamFormat::from_accept(&headers)
+        };
+
+        assert_eq!(format("text/plain"), StreamFormat::JsonLines);
+        assert_eq!(
+            format("application/x-ndjson, application/x-protobuf-stream"),
+            StreamFormat::JsonLines
+        );
+        assert_eq!(
+            format("application/x-ndjson;q=0.5, application/x-protobuf-stream;q=0.9"),
+            StreamFormat::Protobuf
+        );
+        // q=0 means "not acceptable"
+        assert_eq!(
+            format("application/x-protobuf-stream, application/x-ndjson;q=0"),
+            StreamFormat::Protobuf
+        );
+        assert_eq!(
+            format("application/x-protobuf-stream;q=0"),
+            StreamFormat::JsonLines
+        );
+        // JSON arrays are only negotiated on request
+        assert_eq!(format("application/json"), StreamFormat::JsonLines);
+    }
+
+    #[tokio::test]
+    async fn test_success_status_code() {
+        let mut response = tonic::Response::new("created");
+        let metadata = response.metadata_mut();
+        metadata.insert(HTTP_CODE_METADATA, "201".parse().unwrap());
+        metadata.insert("location", "/items/1".parse().unwrap());
+
+        let response = crate::make_response(Ok(response));
+        assert_eq!(response.status(), http::StatusCode::CREATED);
+        assert_eq!(response.headers().get("location").unwrap(), "/items/1");
+        assert!(!response.headers().contains_key("grpc-metadata-x-http-code"));
+
+        // Configured 204 has no body, and invalid overrides are ignored
+        let mut response = tonic::Response::new(());
+        response
+            .metadata_mut()
+            .insert(HTTP_CODE_METADATA, "500".parse().unwrap());
+        let options = ResponseOptions::default().status_code(http::StatusCode::NO_CONTENT);
+
+        let response = crate::make_response_with(Ok(response), options);
+        assert_eq!(response.status(), http::StatusCode::NO_CONTENT);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(body.is_empty());
+    }
+}
@@ -0,0 +1,182 @@
+use http::{HeaderMap, HeaderName, HeaderValue};
+
+use crate::headers::BoundMetadata;
+
+// Where the value of a binding comes from
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Source {
+    // A query parameter with this name
+    QueryParam(String),
+    // A `Sec-WebSocket-Protocol` entry with this prefix
+    ProtocolToken(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Binding {
+    source: Source,
+    key: HeaderName,
+    // Prepended to the value (ie. `Bearer `)
+    value_prefix: &'static str,
+}
+
+/// Binds values from a WebSocket upgrade request to request metadata. Browsers can't set headers on WebSocket upgrade
+/// requests, so credentials and other metadata have to be sent in the query string or as a `Sec-WebSocket-Protocol`
+/// entry instead. The binding can be installed with an [axum::Extension] layer, on the router or on individual routes.
+///
+/// Bound values are added to the metadata of every call on the socket, replacing any header forwarded under the same
+/// key. They bypass the [crate::RequestHeaderPolicy], so denied keys such as `authorization` can be bound. Protocol
+/// entries are never selected as the subprotocol of the socket, so clients still need to list one of
+/// [crate::WS_JSON_PROTOCOL] or [crate::WS_PROTOBUF_PROTOCOL] for browsers to accept the upgrade.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WsMetadataBinding {
+    bindings: Vec<Binding>,
+}
+
+impl WsMetadataBinding {
+    /// Create a binding that doesn't bind anything
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn bind(mut self, source: Source, key: HeaderName, value_prefix: &'static str) -> Self {
+        self.bindings.push(Binding {
+            source,
+            key,
+            value_prefix,
+        });
+        self
+    }
+
+    /// Bind the value of the query parameter `name` to the metadata key (ie. `?tenant=a` as `x-tenant: a`)
+    pub fn query_param(self, name: impl Into<String>, key: HeaderName) -> Self {
+        self.bind(Source::QueryParam(name.into()), key, "")
+    }
+
+    /// Bind the value of the query parameter `name` as a bearer token (ie. `?access_token=x` as
+    /// `authorization: Bearer x`)
+    pub fn bearer_query_param(self, name: impl Into<String>) -> Self {
+        self.bind(
+            Source::QueryParam(name.into()),
+            http::header::AUTHORIZATION,
+            "Bearer ",
+        )
+    }
+
+    /// Bind the rest of the first `Sec-WebSocket-Protocol` entry starting with the prefix to the metadata key (ie.
+    /// `tenant.a` as `x-tenant: a` for the prefix `tenant.`)
+    pub fn protocol_token(self, prefix: impl Into<String>, key: HeaderName) -> Self {
+        self.bind(Source::ProtocolToken(prefix.into()), key, "")
+    }
+
+    /// Bind the rest of the first `Sec-WebSocket-Protocol` entry starting with the prefix as a bearer token (ie.
+    /// `bearer.x` as `authorization: Bearer x` for the prefix `bearer.`). Protocol entries can only hold token
+    /// characters, so the token may need to be encoded (ie. base64url without padding).
+    pub fn bearer_protocol_token(self, prefix: impl Into<String>) -> Self {
+        self.bind(
+            Source::ProtocolToken(prefix.into()),
+            http::header::AUTHORIZATION,
+            "Bearer ",
+        )
+    }
+
+    /// Returns the metadata bound from the request. Missing values and values that aren't valid metadata are skipped.
+    fn apply(&self, uri: &http::Uri, headers: &HeaderMap) -> HeaderMap {
+        let mut bound = HeaderMap::new();
+
+        for binding in &self.bindings {
+            let value = match &binding.source {
+                Source::QueryParam(name) => uri.query().and_then(|query| {
+                    form_urlencoded::parse(query.as_bytes())
+                        .find(|(param, _)| param == name)
+                        .map(|(_, value)| value.into_owned())
+                }),
+                Source::ProtocolToken(prefix) => headers
+                    .get_all(http::header::SEC_WEBSOCKET_PROTOCOL)
+                    .iter()
+                    .filter_map(|value| value.to_str().ok())
+                    .flat_map(|value| value.split(','))
+                    .find_map(|protocol| protocol.trim().strip_prefix(prefix.as_str()))
+                    .map(str::to_string),
+            };
+
+            let value = value.and_then(|value| {
+                HeaderValue::try_from(format!("{}{value}", binding.value_prefix)).ok()
+            });
+            match value {
+                Some(value) => {
+                    bound.insert(binding.key.clone(), value);
+                }
+                None => tracing::trace!("No value bound to metadata key: {}", binding.key),
+            }
+        }
+
+        bound
+    }
+}
+
+/// Binds metadata from the upgrade request with the [WsMetadataBinding] found in the extensions, if any. The bound
+/// metadata is kept in the extensions, and added to the metadata of each call.
+pub(crate) fn bind_metadata(
+    uri: &http::Uri,
+    headers: &HeaderMap,
+    extensions: &mut http::Extensions,
+) {
+    let Some(binding) = extensions.remove::<WsMetadataBinding>() else {
+        return;
+    };
+    let bound = binding.apply(uri, headers);
+    if !bound.is_empty() {
+        extensions.insert(BoundMetadata(bound));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metadata_binding() {
+        let binding = WsMetadataBinding::new()
+            .bearer_query_param("access_token")
+            .query_param("tenant", HeaderName::from_static("x-tenant"))
+            .protocol_token("trace.", HeaderName::from_static("x-trace"));
+        let uri: http::Uri = "/rooms/1/ws?tenant=a%20b&access_token=x.y".parse().unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            http::header::SEC_WEBSOCKET_PROTOCOL,
+            "tonic2axum.json, trace.abc".parse().unwrap(),
+        );
+
+        let bound = binding.apply(&uri, &headers);
+        assert_eq!(bound.len(), 3);
+        assert_eq!(bound["authorization"], "Bearer x.y");
+        assert_eq!(bound["x-tenant"], "a b");
+        assert_eq!(bound["x-trace"], "abc");
+
+        // Missing values aren't bound
+        let bound = binding.apply(&"/rooms/1/ws".parse().unwrap(), &HeaderMap::new());
+        assert!(bound.is_empty());
+    }
+
+    #[test]
+    fn test_bound_metadata_bypasses_policy() {
+        let mut extensions = http::Extensions::new();
+        extensions.insert(WsMetadataBinding::new().bearer_query_param("access_token"));
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            http::header::AUTHORIZATION,
+            "Bearer header".parse().unwrap(),
+        );
+        headers.insert("x-request-id", "1".parse().unwrap());
+
+        bind_metadata(
+            &"/ws?access_token=query".parse().unwrap(),
+            &headers,
+            &mut extensions,
+        );
+        let metadata = crate::headers::request_metadata(headers, &mut extensions);
+        assert_eq!(metadata.get("authorization").unwrap(), "Bearer query");
+        assert_eq!(metadata.get("x-request-id").unwrap(), "1");
+        assert!(extensions.get::<BoundMetadata>().is_none());
+    }
+}
@@ -0,0 +1,79 @@
+use std::pin::pin;
+
+use futures_core::Stream;
+use serde::Serialize;
+use serde_json::value::RawValue;
+
+use crate::{CollectLimits, ResponseOptions};
+
+/// Converts a Tonic stream response into an HTTP response. If requested by the options (see
+/// [ResponseOptions::collect_stream]), the stream is collected into a JSON array sent as a regular response, just like
+/// a unary response. Otherwise, the response is streamed by the given function (ie. [crate::make_stream_response_with]).
+pub async fn collect_stream_response<S, T, F>(
+    response: Result<tonic::Response<S>, tonic::Status>,
+    options: ResponseOptions,
+    stream_response: F,
+) -> http::Response<axum::body::Body>
+where
+    S: Stream<Item = Result<T, tonic::Status>> + Send + 'static,
+    T: Serialize + Send,
+    F: FnOnce(
+        Result<tonic::Response<S>, tonic::Status>,
+        ResponseOptions,
+    ) -> http::Response<axum::body::Body>,
+{
+    if !options.collect_stream {
+        return stream_response(response, options);
+    }
+
+    let response = match response {
+        Ok(response) => response,
+        Err(status) => return crate::make_err_response(status, &options),
+    };
+    let (meta, stream, ext) = response.into_parts();
+    match collect_items(stream, options.collect_limits).await {
+        Ok(items) => {
+            crate::make_response_with(Ok(tonic::Response::from_parts(meta, items, ext)), options)
+        }
+        Err(status) => crate::make_err_response(status, &options),
+    }
+}
+
+/// Serializes the items of the stream as they arrive, so the size of the resulting JSON array is known
+async fn collect_items<S, T>(
+    stream: S,
+    limits: CollectLimits,
+) -> Result<Vec<Box<RawValue>>, tonic::Status>
+where
+    S: Stream<Item = Result<T, tonic::Status>>,
+    T: Serialize,
+{
+    let mut stream = pin!(stream);
+    let mut items = Vec::new();
+    // Opening and closing brackets
+    let mut size = 2;
+
+    while let Some(item) = std::future::poll_fn(|cx| stream.as_mut().poll_next(cx)).await {
+        let item = serde_json::value::to_raw_value(&item?)
+            .map_err(|err| tonic::Status::internal(err.to_string()))?;
+
+        if items.len() == limits.max_items {
+            return Err(tonic::Status::resource_exhausted(format!(
+                "Stream response exceeds the limit of {} items",
+                limits.max_items
+            )));
+        }
+        // Each item after the first is preceded by a comma
+        size += item.get().len() + usize::from(!items.is_empty());
+        if size > limits.max_bytes {
+            return Err(tonic::Status::resource_exhausted(format!(
+                "Stream response exceeds the limit of {} bytes",
+                limits.max_bytes
+            )));
+        }
+
+        items.push(item);
+    }
+
+    Ok(items)
+}
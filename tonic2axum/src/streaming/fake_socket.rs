@@ -0,0 +1,73 @@
+use axum::extract::ws::Message;
+use tokio::sync::mpsc;
+
+use crate::streaming::ws::{WsProtocol, WsSink, WsStream};
+
+/// Creates a socket made of channels for the tests, returning its client end, and the stream and sink of the server end
+pub(crate) fn fake_socket(protocol: WsProtocol) -> (FakeClient, WsStream, WsSink) {
+    let (sender, mut incoming) = mpsc::unbounded_channel();
+    let stream = WsStream::new(futures_util::stream::poll_fn(move |cx| {
+        incoming.poll_recv(cx).map(|message| message.map(Ok))
+    }));
+
+    let (outgoing, receiver) = mpsc::unbounded_channel();
+    let sink = futures_util::sink::unfold(outgoing, |outgoing, message: Message| async move {
+        // The test may no longer be listening
+        let _ = outgoing.send(message);
+        Ok::<_, axum::Error>(outgoing)
+    });
+
+    let client = FakeClient {
+        sender: Some(sender),
+        receiver,
+    };
+    (client, stream, WsSink::new(sink, protocol))
+}
+
+/// The client end of a fake socket
+pub(crate) struct FakeClient {
+    sender: Option<mpsc::UnboundedSender<Message>>,
+    receiver: mpsc::UnboundedReceiver<Message>,
+}
+
+impl FakeClient {
+    /// Sends a message to the server
+    pub(crate) fn send(&self, message: Message) {
+        if let Some(sender) = &self.sender {
+            let _ = sender.send(message);
+        }
+    }
+
+    /// Sends a JSON value to the server in a text message
+    pub(crate) fn send_json(&self, json: serde_json::Value) {
+        self.send(Message::Text(json.to_string().into()));
+    }
+
+    /// Ends the stream of the server, as if the connection was lost
+    pub(crate) fn disconnect(&mut self) {
+        self.sender = None;
+    }
+
+    /// Receives the next message sent by the server
+    pub(crate) async fn recv(&mut self) -> Option<Message> {
+        self.receiver.recv().await
+    }
+
+    /// Receives the next text message sent by the server as JSON, skipping any other message
+    pub(crate) async fn recv_json(&mut self) -> Option<serde_json::Value> {
+        loop {
+            if let Message::Text(text) = self.recv().await? {
+                return Some(serde_json::from_str(&text).unwrap());
+            }
+        }
+    }
+
+    /// Returns the messages sent by the server so far
+    pub(crate) fn sent(&mut self) -> Vec<Message> {
+        let mut sent = Vec::new();
+        while let Ok(message) = self.receiver.try_recv() {
+            sent.push(message);
+        }
+        sent
+    }
+}
@@ -1,36 +1,332 @@
-use axum::response::IntoResponse as _;
-use axum_extra::extract::JsonLines;
-use futures_core::Stream;
-use serde::Serialize;
-use tonic::metadata::MetadataMap;
+use std::{
+    convert::Infallible,
+    future::Future as _,
+    pin::Pin,
+    task::{Context, Poll, ready},
+    time::Duration,
+};
 
-use crate::streaming::FakeGrpcFrameStreamingHelper;
+use axum::{body::BodyDataStream, response::IntoResponse as _};
+use bytes::{Buf as _, Bytes, BytesMut};
+use futures_core::Stream;
+use http_body::Frame;
+use serde::{Serialize, de::DeserializeOwned};
+use tokio::time::{Instant, Sleep};
 
-// Alternative designs to consider (that are less hacky):
-// 1. Use JsonLines<T>, but use something like bitcode in Body to serialize, and then deserialize the T in the Decoder.
-//
-// 2. Use a raw axum Body as the request, return the bytes from BodyDataStream in Body trait (using a bytes::Chain to
-// prepend a gRPC header), do JSON deserialization in the Decoder.
-// Update: Actually, #2 is probably not possible without tracking the JSON lines and buffering the input since we would need to
-// know how long the input is to prepend the gRPC header.
+use crate::{
+    Heartbeat, ResponseHeaderPolicy, ResponseOptions, StreamFormat,
+    streaming::{
+        JsonDecoder, MapDecoder, RawMessage, StreamLimits, new_streaming, status::status_json,
+    },
+};
 
-/// Converts a JSON Lines request into a Tonic streaming request
-pub fn make_stream_request<T: Send + 'static>(
+/// Converts a JSON Lines request body into a Tonic streaming request. A body with a `Content-Type` of
+/// `application/json` is also read as JSON Lines, as clients commonly send it with JSON Lines bodies (see
+/// [make_stream_request_with] to read it as a JSON array). A length-prefixed protobuf body (see
+/// [crate::PROTOBUF_STREAM_CONTENT_TYPE]) isn't supported here (see [crate::make_negotiated_stream_request]), so the
+/// request stream fails with `INVALID_ARGUMENT`.
+pub fn make_stream_request<T: DeserializeOwned + Send + 'static>(
     headers: http::HeaderMap,
     extensions: http::Extensions,
-    lines: JsonLines<T>,
+    body: axum::body::Body,
 ) -> tonic::Request<tonic::Streaming<T>> {
-    let metadata = MetadataMap::from_headers(headers);
-    // HACK: Unfortunately, Streaming requires a real gRPC frame, so we need to fake it, by returning a fake frame from the
-    // Body impl while prepping the real items from JsonLines to return by Decoder.
-    let helper = FakeGrpcFrameStreamingHelper::new(lines);
-    // Since we use items polled by Body, but returned by Decoder, we need to clone the helper to use as both parameters
-    // to the Streaming constructor.
-    let streaming = tonic::Streaming::new_request(helper.clone(), helper, None, None);
+    make_stream_request_with(headers, extensions, body, false, |item| item)
+}
+
+/// Converts a JSON request body into a Tonic streaming request (see [make_stream_request]), passing each message
+/// through the transform function. This allows values from the path or query string to be merged into each message.
+/// When `json_array` is set, a body with a `Content-Type` of `application/json` is read as a single JSON array instead
+/// of JSON Lines (see [StreamFormat::from_content_type]).
+pub fn make_stream_request_with<T, U, F>(
+    headers: http::HeaderMap,
+    mut extensions: http::Extensions,
+    body: axum::body::Body,
+    json_array: bool,
+    transform: F,
+) -> tonic::Request<tonic::Streaming<U>>
+where
+    T: DeserializeOwned + Send + 'static,
+    U: Send + 'static,
+    F: FnMut(T) -> U + Send + 'static,
+{
+    let format = StreamFormat::from_content_type(&headers, json_array);
+    let limits = StreamLimits::from_extensions(&extensions);
+    let metadata = crate::headers::request_metadata(headers, &mut extensions);
+    // Each message is handed to Streaming as a gRPC frame holding the raw JSON, which is deserialized by the decoder
+    let decoder = MapDecoder::new(JsonDecoder::new(), transform);
+    let body = body.into_data_stream();
+    let streaming = match format {
+        StreamFormat::JsonArray => new_streaming(decoder, JsonArray::new(body, limits), limits),
+        StreamFormat::JsonLines => new_streaming(decoder, JsonLines::new(body, limits), limits),
+        StreamFormat::Protobuf => {
+            let status = tonic::Status::invalid_argument(format!(
+                "Unsupported request content type: {}",
+                StreamFormat::Protobuf.content_type()
+            ));
+            new_streaming(decoder, Rejected(Some(status)), limits)
+        }
+    };
     tonic::Request::from_parts(metadata, extensions, streaming)
 }
 
-/// Converts a Tonic stream response into a JSON Lines HTTP response
+/// A request stream that fails with the given status without reading the body
+struct Rejected(Option<tonic::Status>);
+
+impl Stream for Rejected {
+    type Item = Result<RawMessage, tonic::Status>;
+
+    fn poll_next(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Poll::Ready(self.get_mut().0.take().map(Err))
+    }
+}
+
+// *** JsonLines ***
+
+/// Splits a JSON Lines body into messages (one per non-blank line). Lines are sliced from the body chunks, so only
+/// lines spanning multiple chunks need to be buffered.
+struct JsonLines {
+    body: BodyDataStream,
+    limits: StreamLimits,
+    chunk: Bytes,
+    partial: BytesMut,
+    done: bool,
+}
+
+impl JsonLines {
+    fn new(body: BodyDataStream, limits: StreamLimits) -> Self {
+        Self {
+            body,
+            limits,
+            chunk: Bytes::new(),
+            partial: BytesMut::new(),
+            done: false,
+        }
+    }
+
+    fn line_message(line: Bytes) -> Option<RawMessage> {
+        let start = line.iter().position(|b| !b.is_ascii_whitespace())?;
+        let end = line.iter().rposition(|b| !b.is_ascii_whitespace())? + 1;
+        Some(RawMessage::new(line.slice(start..end)))
+    }
+}
+
+impl Stream for JsonLines {
+    type Item = Result<RawMessage, tonic::Status>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            if !this.chunk.is_empty() {
+                match memchr::memchr(b'\n', &this.chunk) {
+                    // Complete line - prepend the start of the line from previous chunks (if any)
+                    Some(pos) => {
+                        let mut line = this.chunk.split_to(pos + 1);
+                        if !this.partial.is_empty() {
+                            this.partial.extend_from_slice(&line);
+                            line = this.partial.split().freeze();
+                        }
+                        match Self::line_message(line) {
+                            Some(message) => return Poll::Ready(Some(Ok(message))),
+                            None => continue,
+                        }
+                    }
+                    // Incomplete line - save it until the rest arrives (unless it is already too long)
+                    None => {
+                        this.partial.extend_from_slice(&this.chunk);
+                        this.chunk.clear();
+                        if this.partial.len() > this.limits.max_message_size {
+                            this.done = true;
+                            this.partial.clear();
+                            return Poll::Ready(Some(Err(this.limits.message_too_large())));
+                        }
+                    }
+                }
+            }
+
+            // The last line doesn't need a trailing newline
+            if this.done {
+                let line = this.partial.split().freeze();
+                return Poll::Ready(Self::line_message(line).map(Ok));
+            }
+
+            match ready!(Pin::new(&mut this.body).poll_next(cx)) {
+                Some(Ok(chunk)) => this.chunk = chunk,
+                Some(Err(err)) => {
+                    this.done = true;
+                    this.partial.clear();
+                    return Poll::Ready(Some(Err(tonic::Status::internal(err.to_string()))));
+                }
+                None => this.done = true,
+            }
+        }
+    }
+}
+
+// *** JsonArray ***
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ArrayState {
+    // Before the opening bracket
+    Start,
+    // Within the array, scanning the next element
+    Elements,
+    // After the closing bracket
+    End,
+}
+
+/// Splits a JSON array body into messages (one per element). The elements are found by tracking nesting and strings,
+/// so they are only parsed once by the decoder.
+struct JsonArray {
+    body: BodyDataStream,
+    limits: StreamLimits,
+    buf: BytesMut,
+    state: ArrayState,
+    // Scan position within the buffer and the element's nesting depth and string state at that point
+    pos: usize,
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+    elements: usize,
+    done: bool,
+}
+
+impl JsonArray {
+    fn new(body: BodyDataStream, limits: StreamLimits) -> Self {
+        Self {
+            body,
+            limits,
+            buf: BytesMut::new(),
+            state: ArrayState::Start,
+            pos: 0,
+            depth: 0,
+            in_string: false,
+            escaped: false,
+            elements: 0,
+            done: false,
+        }
+    }
+
+    fn invalid(&mut self, message: &str) -> Option<Result<RawMessage, tonic::Status>> {
+        self.fail(tonic::Status::invalid_argument(message.to_string()))
+    }
+
+    fn fail(&mut self, status: tonic::Status) -> Option<Result<RawMessage, tonic::Status>> {
+        self.state = ArrayState::End;
+        self.done = true;
+        self.buf.clear();
+        Some(Err(status))
+    }
+
+    /// Scans the buffered data, returning the next message (or error) if one is complete
+    fn scan(&mut self) -> Option<Result<RawMessage, tonic::Status>> {
+        loop {
+            match self.state {
+                ArrayState::Start | ArrayState::End => {
+                    let pos = self.buf.iter().position(|b| !b.is_ascii_whitespace());
+                    let Some(pos) = pos else {
+                        self.buf.clear();
+                        return None;
+                    };
+                    if self.state == ArrayState::End {
+                        return self.invalid("Unexpected data after the JSON array");
+                    }
+                    if self.buf[pos] != b'[' {
+                        return self.invalid("Expected a JSON array");
+                    }
+                    self.buf.advance(pos + 1);
+                    self.state = ArrayState::Elements;
+                }
+                ArrayState::Elements => {
+                    let delimiter = self.scan_element()?;
+                    let element = self.buf.split_to(self.pos).freeze();
+                    self.buf.advance(1);
+                    self.pos = 0;
+
+                    let message = JsonLines::line_message(element);
+                    if delimiter == b']' {
+                        self.state = ArrayState::End;
+                        // Only an empty array may have no elements
+                        if message.is_none() && self.elements == 0 {
+                            continue;
+                        }
+                    }
+                    return match message {
+                        Some(message) => {
+                            self.elements += 1;
+                            Some(Ok(message))
+                        }
+                        None => self.invalid("Empty element in JSON array"),
+                    };
+                }
+            }
+        }
+    }
+
+    /// Advances the scan position to the delimiter (`,` or `]`) ending the current element, if it has been received
+    fn scan_element(&mut self) -> Option<u8> {
+        while let Some(&b) = self.buf.get(self.pos) {
+            if self.in_string {
+                match b {
+                    _ if self.escaped => self.escaped = false,
+                    b'\\' => self.escaped = true,
+                    b'"' => self.in_string = false,
+                    _ => {}
+                }
+            } else {
+                match b {
+                    b'"' => self.in_string = true,
+                    b'[' | b'{' => self.depth += 1,
+                    b',' | b']' if self.depth == 0 => return Some(b),
+                    b']' | b'}' => self.depth = self.depth.saturating_sub(1),
+                    _ => {}
+                }
+            }
+            self.pos += 1;
+        }
+        None
+    }
+}
+
+impl Stream for JsonArray {
+    type Item = Result<RawMessage, tonic::Status>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            if let Some(result) = this.scan() {
+                return Poll::Ready(Some(result));
+            }
+            // Whatever remains is the start of the next element
+            if this.buf.len() > this.limits.max_message_size {
+                let status = this.limits.message_too_large();
+                return Poll::Ready(this.fail(status));
+            }
+
+            if this.done {
+                // An empty body is treated as an empty array
+                return Poll::Ready(match this.state {
+                    ArrayState::Elements => this.invalid("Incomplete JSON array"),
+                    ArrayState::Start | ArrayState::End => None,
+                });
+            }
+
+            match ready!(Pin::new(&mut this.body).poll_next(cx)) {
+                Some(Ok(chunk)) => this.buf.extend_from_slice(&chunk),
+                Some(Err(err)) => {
+                    this.done = true;
+                    this.buf.clear();
+                    return Poll::Ready(Some(Err(tonic::Status::internal(err.to_string()))));
+                }
+                None => this.done = true,
+            }
+        }
+    }
+}
+
+/// Converts a Tonic stream response into a JSON Lines HTTP response using the default [ResponseOptions] (see
+/// [make_stream_response_with]).
 pub fn make_stream_response<S, T>(
     response: Result<tonic::Response<S>, tonic::Status>,
 ) -> http::Response<axum::body::Body>
@@ -38,20 +334,331 @@ where
     S: Stream<Item = Result<T, tonic::Status>> + Send + 'static,
     T: Serialize + Send,
 {
-    match response {
-        Ok(response) => make_ok_stream_response(response),
-        Err(status) => crate::make_err_response(status),
-    }
+    make_stream_response_with(response, ResponseOptions::default())
 }
 
-fn make_ok_stream_response<S, T>(response: tonic::Response<S>) -> http::Response<axum::body::Body>
+/// Converts a Tonic stream response into a JSON HTTP response: a JSON array if the stream format in the options is
+/// [StreamFormat::JsonArray], otherwise JSON Lines. Initial metadata is sent as HTTP headers and the final status along
+/// with any trailing metadata is sent as HTTP trailers.
+pub fn make_stream_response_with<S, T>(
+    response: Result<tonic::Response<S>, tonic::Status>,
+    options: ResponseOptions,
+) -> http::Response<axum::body::Body>
 where
     S: Stream<Item = Result<T, tonic::Status>> + Send + 'static,
     T: Serialize + Send,
 {
-    let (meta, stream, ext) = response.into_parts();
-    let headers = meta.into_headers();
-    let lines = JsonLines::new(stream);
+    let envelope = options.stream_envelope;
+    match options.stream_format {
+        StreamFormat::JsonArray => {
+            let encoder = JsonArrayEncoder {
+                envelope,
+                started: false,
+            };
+            make_encoded_stream_response(response, options, StreamFormat::JsonArray, encoder)
+        }
+        StreamFormat::JsonLines | StreamFormat::Protobuf => {
+            let encoder = JsonLinesEncoder { envelope };
+            make_encoded_stream_response(response, options, StreamFormat::JsonLines, encoder)
+        }
+    }
+}
 
-    (http::StatusCode::OK, headers, ext, lines).into_response()
+pub(crate) fn make_encoded_stream_response<S, T, E>(
+    response: Result<tonic::Response<S>, tonic::Status>,
+    options: ResponseOptions,
+    format: StreamFormat,
+    encoder: E,
+) -> http::Response<axum::body::Body>
+where
+    S: Stream<Item = Result<T, tonic::Status>> + Send + 'static,
+    E: StreamEncoder<T> + Send + Unpin + 'static,
+{
+    let (meta, stream, ext) = match response {
+        Ok(response) => response.into_parts(),
+        Err(status) => return crate::make_err_response(status, &options),
+    };
+
+    let mut headers = http::HeaderMap::new();
+    options.header_policy.apply_headers(&meta, &mut headers);
+    headers.insert(
+        http::header::CONTENT_TYPE,
+        http::HeaderValue::from_static(format.content_type()),
+    );
+    make_body_response(headers, ext, stream, options, encoder)
+}
+
+/// Builds a successful stream response with the given headers, encoding the stream into the body
+pub(crate) fn make_body_response<S, T, E>(
+    headers: http::HeaderMap,
+    ext: http::Extensions,
+    stream: S,
+    options: ResponseOptions,
+    encoder: E,
+) -> http::Response<axum::body::Body>
+where
+    S: Stream<Item = Result<T, tonic::Status>> + Send + 'static,
+    E: StreamEncoder<T> + Send + Unpin + 'static,
+{
+    let heartbeat = options.heartbeat.as_ref().and_then(|heartbeat| {
+        let data = encoder.encode_heartbeat(heartbeat)?;
+        Some((HeartbeatTimer::new(heartbeat.interval), data))
+    });
+    let body = StreamBody::new(stream, encoder, options.header_policy, heartbeat);
+
+    (
+        http::StatusCode::OK,
+        headers,
+        ext,
+        axum::body::Body::new(body),
+    )
+        .into_response()
+}
+
+// *** StreamEncoder ***
+
+/// Encodes the items of a stream response into body data
+pub(crate) trait StreamEncoder<T> {
+    fn encode_item(&mut self, item: T) -> Result<Bytes, tonic::Status>;
+
+    /// Data sent after the last item, before the trailers (if any)
+    fn encode_end(&mut self, _status: &tonic::Status) -> Option<Bytes> {
+        None
+    }
+
+    /// No-op data sent while the stream is idle, if the format has any
+    fn encode_heartbeat(&self, _heartbeat: &Heartbeat) -> Option<Bytes> {
+        None
+    }
+}
+
+/// The grpc-gateway style envelope of a streamed message: `{"result": ...}` or `{"error": ...}`
+#[derive(Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Envelope<'a, T> {
+    Result(&'a T),
+    Error(serde_json::Value),
+}
+
+fn json_item<T: Serialize>(item: &T, envelope: bool) -> Result<Vec<u8>, tonic::Status> {
+    let json = if envelope {
+        serde_json::to_vec(&Envelope::Result(item))
+    } else {
+        serde_json::to_vec(item)
+    };
+    json.map_err(|err| tonic::Status::internal(err.to_string()))
+}
+
+fn json_error(status: &tonic::Status) -> Vec<u8> {
+    // A status is already a JSON value, so it can't fail to serialize
+    serde_json::to_vec(&Envelope::<()>::Error(status_json(status))).unwrap_or_default()
+}
+
+/// Encodes each item as a JSON line, optionally wrapped in a `{"result": ...}` envelope. In envelope mode, an error is
+/// sent as a final `{"error": ...}` line.
+struct JsonLinesEncoder {
+    envelope: bool,
+}
+
+impl<T: Serialize> StreamEncoder<T> for JsonLinesEncoder {
+    fn encode_item(&mut self, item: T) -> Result<Bytes, tonic::Status> {
+        let mut line = json_item(&item, self.envelope)?;
+        line.push(b'\n');
+        Ok(line.into())
+    }
+
+    fn encode_end(&mut self, status: &tonic::Status) -> Option<Bytes> {
+        (self.envelope && status.code() != tonic::Code::Ok).then(|| {
+            let mut line = json_error(status);
+            line.push(b'\n');
+            line.into()
+        })
+    }
+
+    fn encode_heartbeat(&self, heartbeat: &Heartbeat) -> Option<Bytes> {
+        match &heartbeat.keepalive {
+            Some(json) => Some(format!("{json}\n").into()),
+            None => Some(Bytes::from_static(b"\n")),
+        }
+    }
+}
+
+/// Encodes the items as the elements of a JSON array, optionally wrapped in a `{"result": ...}` envelope. An error is
+/// always sent as a final `{"error": ...}` element, since the array must be closed either way.
+struct JsonArrayEncoder {
+    envelope: bool,
+    started: bool,
+}
+
+impl JsonArrayEncoder {
+    fn separator(&mut self) -> u8 {
+        if std::mem::replace(&mut self.started, true) {
+            b','
+        } else {
+            b'['
+        }
+    }
+}
+
+impl<T: Serialize> StreamEncoder<T> for JsonArrayEncoder {
+    fn encode_item(&mut self, item: T) -> Result<Bytes, tonic::Status> {
+        let json = json_item(&item, self.envelope)?;
+        let mut element = Vec::with_capacity(json.len() + 1);
+        element.push(self.separator());
+        element.extend_from_slice(&json);
+        Ok(element.into())
+    }
+
+    fn encode_end(&mut self, status: &tonic::Status) -> Option<Bytes> {
+        let mut end = Vec::new();
+        if status.code() != tonic::Code::Ok {
+            end.push(self.separator());
+            end.extend_from_slice(&json_error(status));
+        } else if !self.started {
+            end.push(b'[');
+        }
+        end.push(b']');
+        Some(end.into())
+    }
+
+    fn encode_heartbeat(&self, _heartbeat: &Heartbeat) -> Option<Bytes> {
+        // Whitespace is allowed anywhere between the tokens of the array
+        Some(Bytes::from_static(b"\n"))
+    }
+}
+
+// *** HeartbeatTimer ***
+
+/// Fires after the stream has been idle for the heartbeat interval
+pub(crate) struct HeartbeatTimer {
+    interval: Duration,
+    sleep: Pin<Box<Sleep>>,
+}
+
+impl HeartbeatTimer {
+    pub(crate) fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            sleep: Box::pin(tokio::time::sleep(interval)),
+        }
+    }
+
+    /// Restart the interval, since the stream is no longer idle
+    pub(crate) fn reset(&mut self) {
+        let deadline = Instant::now() + self.interval;
+        self.sleep.as_mut().reset(deadline);
+    }
+
+    /// Ready when a heartbeat is due (the interval is then restarted)
+    pub(crate) fn poll_due(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        ready!(self.sleep.as_mut().poll(cx));
+        self.reset();
+        Poll::Ready(())
+    }
+}
+
+// *** StreamBody ***
+
+/// A stream response body that ends with the gRPC status and trailing metadata as HTTP trailers
+struct StreamBody<S, E> {
+    stream: Pin<Box<S>>,
+    encoder: E,
+    header_policy: ResponseHeaderPolicy,
+    heartbeat: Option<(HeartbeatTimer, Bytes)>,
+    trailers: Option<http::HeaderMap>,
+    done: bool,
+}
+
+impl<S, E> StreamBody<S, E> {
+    fn new(
+        stream: S,
+        encoder: E,
+        header_policy: ResponseHeaderPolicy,
+        heartbeat: Option<(HeartbeatTimer, Bytes)>,
+    ) -> Self {
+        Self {
+            stream: Box::pin(stream),
+            encoder,
+            header_policy,
+            heartbeat,
+            trailers: None,
+            done: false,
+        }
+    }
+
+    fn make_trailers(&self, status: &tonic::Status) -> http::HeaderMap {
+        // Let tonic build the status trailers (without metadata) so they are encoded the same way as for gRPC. This
+        // can't fail since the message is percent encoded and the details are base64 encoded.
+        let mut trailers = http::HeaderMap::new();
+        let details = Bytes::copy_from_slice(status.details());
+        let _ = tonic::Status::with_details(status.code(), status.message(), details)
+            .add_header(&mut trailers);
+        self.header_policy
+            .apply_trailers(status.metadata(), &mut trailers);
+        trailers
+    }
+}
+
+impl<S, T, E> http_body::Body for StreamBody<S, E>
+where
+    S: Stream<Item = Result<T, tonic::Status>> + Send + 'static,
+    E: StreamEncoder<T> + Unpin,
+{
+    type Data = Bytes;
+    type Error = Infallible;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        // Final data was sent - the trailers follow
+        if let Some(trailers) = self.trailers.take() {
+            self.done = true;
+            return Poll::Ready(Some(Ok(Frame::trailers(trailers))));
+        }
+        if self.done {
+            return Poll::Ready(None);
+        }
+
+        let next = match self.stream.as_mut().poll_next(cx) {
+            Poll::Ready(next) => next,
+            // Stream is idle - send a heartbeat if one is due
+            Poll::Pending => {
+                return match &mut self.heartbeat {
+                    Some((timer, data)) => timer
+                        .poll_due(cx)
+                        .map(|()| Some(Ok(Frame::data(data.clone())))),
+                    None => Poll::Pending,
+                };
+            }
+        };
+        if let Some((timer, _)) = &mut self.heartbeat {
+            timer.reset();
+        }
+
+        let status = match next {
+            // Stream has another item - encode it
+            Some(Ok(item)) => match self.encoder.encode_item(item) {
+                Ok(data) => return Poll::Ready(Some(Ok(Frame::data(data)))),
+                Err(status) => status,
+            },
+            // Stream failed - the error becomes the final status
+            Some(Err(status)) => status,
+            // Stream ended normally
+            None => tonic::Status::ok(""),
+        };
+
+        let trailers = self.make_trailers(&status);
+        if let Some(data) = self.encoder.encode_end(&status) {
+            self.trailers = Some(trailers);
+            return Poll::Ready(Some(Ok(Frame::data(data))));
+        }
+
+        self.done = true;
+        Poll::Ready(Some(Ok(Frame::trailers(trailers))))
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.done && self.trailers.is_none()
+    }
 }
@@ -0,0 +1,391 @@
+use std::time::Duration;
+
+use axum::extract::ws::{Message, WebSocket};
+use bytes::Bytes;
+use futures_core::Stream;
+use futures_util::StreamExt as _;
+use tokio::{
+    sync::mpsc::{self, error::TrySendError},
+    time::Instant,
+};
+
+use crate::streaming::{
+    limits::{RateLimiter, WsLimits},
+    ws::{
+        WsClose, WsProtocol, WsSink, WsStream, finish_ws, run_until_closed, ws_read_error_to_status,
+    },
+};
+
+// Messages read from the socket but not yet taken by the call (unless limited)
+const MESSAGE_BUFFER: usize = 32;
+
+/// Keepalive for WebSocket connections, which detects dead peers and idle connections. The keepalive can be installed
+/// with an [axum::Extension] layer, on the router or on individual routes. When one is found, the socket is read
+/// continuously (so pongs are seen even while the call isn't reading) and pings are sent at the ping interval. If the
+/// peer doesn't answer a ping within the pong timeout, the call is cancelled and the socket closed with `UNAVAILABLE`.
+/// A call that falls a full buffer of messages behind pauses the reading until it catches up (unless limited by
+/// [WsLimits::max_in_flight]), and the pong timeout is suspended meanwhile, as the peer has shown it is alive.
+/// If no messages are sent or received within the idle timeout (pings and pongs don't count), the call is cancelled
+/// and the socket closed with `DEADLINE_EXCEEDED`. Both close the socket with code 1013 (try again later).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WsKeepalive {
+    pub(crate) ping_interval: Option<Duration>,
+    pub(crate) pong_timeout: Duration,
+    pub(crate) idle_timeout: Option<Duration>,
+}
+
+impl WsKeepalive {
+    /// Create a keepalive with a pong timeout of 10 seconds, and no pings or idle timeout
+    pub const fn new() -> Self {
+        Self {
+            ping_interval: None,
+            pong_timeout: Duration::from_secs(10),
+            idle_timeout: None,
+        }
+    }
+
+    /// Set the interval at which pings are sent to the peer
+    pub const fn ping_interval(mut self, ping_interval: Duration) -> Self {
+        self.ping_interval = Some(ping_interval);
+        self
+    }
+
+    /// Set the maximum time to wait for a pong after sending a ping
+    pub const fn pong_timeout(mut self, pong_timeout: Duration) -> Self {
+        self.pong_timeout = pong_timeout;
+        self
+    }
+
+    /// Set the maximum time the connection can go without a message being sent or received
+    pub const fn idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = Some(idle_timeout);
+        self
+    }
+
+    pub(crate) fn from_extensions(extensions: &http::Extensions) -> Option<Self> {
+        extensions.get::<Self>().copied()
+    }
+}
+
+impl Default for WsKeepalive {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Runs a call on an upgraded socket, supervised by the keepalive and limits if there are any
+pub(crate) async fn run_ws<F, Fut>(
+    socket: WebSocket,
+    keepalive: Option<WsKeepalive>,
+    limits: Option<WsLimits>,
+    protocol: WsProtocol,
+    call: F,
+) where
+    F: FnOnce(WsStream, WsSink) -> Fut,
+    Fut: Future<Output = ()>,
+{
+    let (sink, stream) = socket.split();
+    let sink = WsSink::new(sink, protocol);
+    if keepalive.is_some() || limits.is_some() {
+        // The default keepalive doesn't send pings or time out
+        let keepalive = keepalive.unwrap_or_default();
+        run_supervised(stream, sink, keepalive, limits.unwrap_or_default(), call).await;
+    } else {
+        run_until_closed(WsStream::new(stream), &sink, |stream| {
+            call(stream, sink.share())
+        })
+        .await;
+    }
+}
+
+async fn run_supervised<I, F, Fut>(
+    incoming: I,
+    sink: WsSink,
+    keepalive: WsKeepalive,
+    limits: WsLimits,
+    call: F,
+) where
+    I: Stream<Item = Result<Message, axum::Error>> + Unpin,
+    F: FnOnce(WsStream, WsSink) -> Fut,
+    Fut: Future<Output = ()>,
+{
+    let buffer = limits.max_in_flight.unwrap_or(MESSAGE_BUFFER).max(1);
+    let (messages, receiver) = mpsc::channel(buffer);
+    let stream = WsStream::from_receiver(receiver);
+    let mut call = Box::pin(run_until_closed(stream, &sink, |stream| {
+        call(stream, sink.share())
+    }));
+
+    tokio::select! {
+        () = &mut call => {}
+        close = watch_ws(incoming, messages, &sink, keepalive, limits) => match close {
+            Some(close) => {
+                // Cancel the call before closing, so it can't send anything else
+                drop(call);
+                finish_ws(&sink, close).await;
+            }
+            // The peer closed the socket, so let the call finish up (or be cancelled, once its requests have ended)
+            None => call.await,
+        },
+    }
+}
+
+/// Reads the socket, forwarding messages to the call, until the peer closes it (`None`), or the keepalive fails or the
+/// limits are exceeded
+async fn watch_ws<I>(
+    mut incoming: I,
+    messages: mpsc::Sender<Result<Message, axum::Error>>,
+    sink: &WsSink,
+    keepalive: WsKeepalive,
+    limits: WsLimits,
+) -> Option<WsClose>
+where
+    I: Stream<Item = Result<Message, axum::Error>> + Unpin,
+{
+    let mut next_ping = keepalive
+        .ping_interval
+        .map(|interval| Instant::now() + interval);
+    let mut pong_deadline: Option<Instant> = None;
+    let mut rate_limiter = limits
+        .message_rate
+        .map(|(messages, per)| RateLimiter::new(messages, per));
+    // A message the call had no room for, which holds up reading the socket
+    let mut pending = None;
+
+    loop {
+        let idle_deadline = keepalive
+            .idle_timeout
+            .map(|idle_timeout| sink.last_activity() + idle_timeout);
+
+        tokio::select! {
+            permit = messages.reserve(), if pending.is_some() => {
+                // The call caught up (or stopped reading, in which case the message is dropped)
+                if let (Ok(permit), Some(message)) = (permit, pending.take()) {
+                    permit.send(message);
+                }
+                let now = Instant::now();
+                pong_deadline = pong_deadline.map(|_| now + keepalive.pong_timeout);
+            }
+            message = incoming.next(), if pending.is_none() => match message {
+                Some(Ok(Message::Pong(_))) => pong_deadline = None,
+                // Pings are answered by the socket itself
+                Some(Ok(Message::Ping(_))) => {}
+                Some(Err(err)) => {
+                    let status = ws_read_error_to_status(&err);
+                    if status.code() == tonic::Code::ResourceExhausted {
+                        return Some(status.into());
+                    }
+                    match forward(&messages, Err(err), &limits) {
+                        Ok(held) => pending = held,
+                        Err(close) => return Some(close),
+                    }
+                }
+                Some(Ok(message)) => {
+                    if matches!(message, Message::Text(_) | Message::Binary(_)) {
+                        sink.touch();
+                        if let Some(rate_limiter) = &mut rate_limiter
+                            && !rate_limiter.try_acquire()
+                        {
+                            return Some(WsClose::policy_violation(tonic::Status::resource_exhausted(
+                                "WebSocket message rate limit exceeded",
+                            )));
+                        }
+                    }
+                    match forward(&messages, Ok(message), &limits) {
+                        Ok(held) => pending = held,
+                        Err(close) => return Some(close),
+                    }
+                }
+                None => return None,
+            },
+            () = sleep_until(next_ping), if next_ping.is_some() => {
+                if let Err(err) = sink.send(Message::Ping(Bytes::new())).await {
+                    return Some(tonic::Status::unavailable(err.to_string()).into());
+                }
+                let now = Instant::now();
+                // An outstanding ping keeps its deadline
+                pong_deadline.get_or_insert(now + keepalive.pong_timeout);
+                next_ping = keepalive.ping_interval.map(|interval| now + interval);
+            }
+            () = sleep_until(pong_deadline), if pong_deadline.is_some() && pending.is_none() => {
+                return Some(tonic::Status::unavailable("WebSocket ping not answered").into());
+            }
+            () = sleep_until(idle_deadline), if idle_deadline.is_some() => {
+                // Messages may have been sent while sleeping
+                if let Some(idle_timeout) = keepalive.idle_timeout
+                    && sink.last_activity() + idle_timeout <= Instant::now()
+                {
+                    return Some(tonic::Status::deadline_exceeded("WebSocket idle timeout").into());
+                }
+            }
+        }
+    }
+}
+
+/// Forwards a message to the call without waiting, so a slow call doesn't hold up the pings and timeouts. A message the
+/// call has no room for is handed back to be sent once it catches up, unless the messages in flight are limited.
+fn forward(
+    messages: &mpsc::Sender<Result<Message, axum::Error>>,
+    message: Result<Message, axum::Error>,
+    limits: &WsLimits,
+) -> Result<Option<Result<Message, axum::Error>>, WsClose> {
+    match messages.try_send(message) {
+        Ok(()) => Ok(None),
+        Err(TrySendError::Full(_)) if limits.max_in_flight.is_some() => {
+            Err(WsClose::policy_violation(
+                tonic::Status::resource_exhausted("Too many WebSocket messages in flight"),
+            ))
+        }
+        Err(TrySendError::Full(message)) => Ok(Some(message)),
+        // The call may no longer be reading, but the socket is still read for pongs and the close
+        Err(TrySendError::Closed(_)) => Ok(None),
+    }
+}
+
+async fn sleep_until(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    };
+
+    use axum::extract::ws::close_code::{AGAIN, POLICY};
+
+    use super::*;
+    use crate::streaming::fake_socket::{FakeClient, fake_socket};
+
+    // Runs a never ending call with the keepalive and limits over a fake socket, returning its client end and a flag set
+    // once the call is cancelled
+    fn start_supervised(keepalive: WsKeepalive, limits: WsLimits) -> (FakeClient, Arc<AtomicBool>) {
+        let (client, stream, sink) = fake_socket(WsProtocol::default());
+
+        struct Cancelled(Arc<AtomicBool>);
+        impl Drop for Cancelled {
+            fn drop(&mut self) {
+                self.0.store(true, Ordering::SeqCst);
+            }
+        }
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let guard = Cancelled(cancelled.clone());
+        tokio::spawn(async move {
+            run_supervised(stream, sink, keepalive, limits, |stream, _| async move {
+                // Holds on to the messages without reading them
+                let _stream = stream;
+                let _guard = guard;
+                std::future::pending::<()>().await;
+            })
+            .await;
+        });
+        (client, cancelled)
+    }
+
+    fn assert_closed(message: Option<Message>, code: u16, cancelled: &AtomicBool) {
+        match message {
+            Some(Message::Close(Some(frame))) => assert_eq!(frame.code, code),
+            message => panic!("Expected a close frame, got: {message:?}"),
+        }
+        assert!(cancelled.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_keepalive_pong_timeout() {
+        let keepalive = WsKeepalive::new()
+            .ping_interval(Duration::from_millis(10))
+            .pong_timeout(Duration::from_millis(100));
+        let (mut client, cancelled) = start_supervised(keepalive, WsLimits::default());
+
+        // Answered pings keep the connection open
+        for _ in 0..3 {
+            assert!(matches!(client.recv().await, Some(Message::Ping(_))));
+            client.send(Message::Pong(Bytes::new()));
+        }
+        assert!(!cancelled.load(Ordering::SeqCst));
+
+        // Unanswered pings close it
+        let mut message = client.recv().await;
+        while let Some(Message::Ping(_)) = message {
+            message = client.recv().await;
+        }
+        assert_closed(message, AGAIN, &cancelled);
+    }
+
+    #[tokio::test]
+    async fn test_keepalive_idle_timeout() {
+        let keepalive = WsKeepalive::new().idle_timeout(Duration::from_millis(100));
+        let (mut client, cancelled) = start_supervised(keepalive, WsLimits::default());
+
+        // Messages from the client keep the connection open
+        for _ in 0..3 {
+            tokio::time::sleep(Duration::from_millis(40)).await;
+            client.send(Message::Text("{}".into()));
+        }
+        assert!(!cancelled.load(Ordering::SeqCst));
+
+        assert_closed(client.recv().await, AGAIN, &cancelled);
+    }
+
+    #[tokio::test]
+    async fn test_keepalive_call_behind() {
+        let keepalive = WsKeepalive::new()
+            .ping_interval(Duration::from_millis(10))
+            .pong_timeout(Duration::from_millis(20));
+        let (mut client, cancelled) = start_supervised(keepalive, WsLimits::default());
+
+        // The call never reads its messages, so it falls behind, but pings are still sent
+        for _ in 0..=MESSAGE_BUFFER {
+            client.send(Message::Text("{}".into()));
+        }
+        for _ in 0..3 {
+            assert!(matches!(client.recv().await, Some(Message::Ping(_))));
+        }
+        // The pong timeout is suspended while the socket isn't read
+        assert!(!cancelled.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_limits_message_rate() {
+        let limits = WsLimits::new().max_message_rate(2, Duration::from_millis(100));
+        let (mut client, cancelled) = start_supervised(WsKeepalive::new(), limits);
+
+        // Pings don't count towards the rate (each batch is read before the time moves on)
+        client.send(Message::Ping(Bytes::new()));
+        for _ in 0..2 {
+            client.send(Message::Text("{}".into()));
+        }
+        tokio::task::yield_now().await;
+        tokio::time::advance(Duration::from_millis(60)).await;
+        client.send(Message::Text("{}".into()));
+        tokio::task::yield_now().await;
+        tokio::time::advance(Duration::from_millis(20)).await;
+        assert!(!cancelled.load(Ordering::SeqCst));
+
+        // A burst over the rate closes it
+        for _ in 0..2 {
+            client.send(Message::Text("{}".into()));
+        }
+        assert_closed(client.recv().await, POLICY, &cancelled);
+    }
+
+    #[tokio::test]
+    async fn test_limits_max_in_flight() {
+        let limits = WsLimits::new().max_in_flight(2);
+        let (mut client, cancelled) = start_supervised(WsKeepalive::new(), limits);
+
+        // The call never reads its messages
+        for _ in 0..2 {
+            client.send(Message::Text("{}".into()));
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!cancelled.load(Ordering::SeqCst));
+
+        client.send(Message::Text("{}".into()));
+        assert_closed(client.recv().await, POLICY, &cancelled);
+    }
+}
@@ -0,0 +1,184 @@
+use std::time::Duration;
+
+/// Limits on the messages of a streaming request (HTTP or WebSocket). Limits can be installed with an
+/// [axum::Extension] layer, on the router or on individual routes. When none are found, [StreamLimits::default] is
+/// used. A message that is too large or too many messages end the stream with `RESOURCE_EXHAUSTED`, and not receiving
+/// a message within the idle timeout ends it with `DEADLINE_EXCEEDED`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StreamLimits {
+    pub(crate) max_message_size: usize,
+    pub(crate) max_messages: Option<usize>,
+    pub(crate) idle_timeout: Option<Duration>,
+}
+
+impl StreamLimits {
+    /// Create limits with a maximum message size of 4 MiB (the same as Tonic) and no message count limit or idle
+    /// timeout
+    pub const fn new() -> Self {
+        Self {
+            max_message_size: 4 * 1024 * 1024,
+            max_messages: None,
+            idle_timeout: None,
+        }
+    }
+
+    /// Set the maximum size of a single message in bytes (as received, ie. the JSON line or protobuf message)
+    pub const fn max_message_size(mut self, max_message_size: usize) -> Self {
+        self.max_message_size = max_message_size;
+        self
+    }
+
+    /// Set the maximum number of messages in the stream
+    pub const fn max_messages(mut self, max_messages: usize) -> Self {
+        self.max_messages = Some(max_messages);
+        self
+    }
+
+    /// Set the maximum time to wait for the next message (or the first one)
+    pub const fn idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = Some(idle_timeout);
+        self
+    }
+
+    pub(crate) fn from_extensions(extensions: &http::Extensions) -> Self {
+        extensions.get::<Self>().copied().unwrap_or_default()
+    }
+
+    pub(crate) fn message_too_large(&self) -> tonic::Status {
+        tonic::Status::resource_exhausted(format!(
+            "Message exceeds the limit of {} bytes",
+            self.max_message_size
+        ))
+    }
+}
+
+impl Default for StreamLimits {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Limits on the messages received on a WebSocket. Limits can be installed with an [axum::Extension] layer, on the
+/// router or on individual routes, and replace any set by the generated handler. The sizes are enforced by the socket
+/// while the messages are read, and a message or frame that is too large closes the socket with `RESOURCE_EXHAUSTED`
+/// (code 1009, message too big). The socket is read continuously (as with [crate::WsKeepalive]) to enforce the
+/// message rate and the number of messages waiting to be taken by the call, and exceeding either also closes it with
+/// `RESOURCE_EXHAUSTED` (code 1008, policy violation). Pings, pongs and close frames don't count towards the limits.
+#[cfg(feature = "ws-streaming")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WsLimits {
+    pub(crate) max_message_size: Option<usize>,
+    pub(crate) max_frame_size: Option<usize>,
+    pub(crate) message_rate: Option<(u32, Duration)>,
+    pub(crate) max_in_flight: Option<usize>,
+    pub(crate) max_mux_calls: Option<usize>,
+}
+
+#[cfg(feature = "ws-streaming")]
+impl WsLimits {
+    /// Create limits that leave the socket's default sizes in place, with no message rate or in-flight limit
+    pub const fn new() -> Self {
+        Self {
+            max_message_size: None,
+            max_frame_size: None,
+            message_rate: None,
+            max_in_flight: None,
+            max_mux_calls: None,
+        }
+    }
+
+    /// Set the maximum size of a message in bytes (after reassembling its frames)
+    pub const fn max_message_size(mut self, max_message_size: usize) -> Self {
+        self.max_message_size = Some(max_message_size);
+        self
+    }
+
+    /// Set the maximum size of a single frame in bytes
+    pub const fn max_frame_size(mut self, max_frame_size: usize) -> Self {
+        self.max_frame_size = Some(max_frame_size);
+        self
+    }
+
+    /// Set the maximum number of messages the client can send per period. Bursts of up to `messages` messages are
+    /// allowed, with the allowance refilled evenly over the period. Panics if `messages` or `per` is zero.
+    pub const fn max_message_rate(mut self, messages: u32, per: Duration) -> Self {
+        assert!(
+            messages > 0 && !per.is_zero(),
+            "The message rate must allow at least one message per a non-zero period"
+        );
+        self.message_rate = Some((messages, per));
+        self
+    }
+
+    /// Set the maximum number of messages read from the socket but not yet taken by the call (default: the socket
+    /// stops being read until the call catches up)
+    pub const fn max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.max_in_flight = Some(max_in_flight);
+        self
+    }
+
+    /// Set the maximum number of calls that can run at once on a multiplexed socket (default: 100). Starting another
+    /// call ends it with `RESOURCE_EXHAUSTED`, but leaves the socket open.
+    pub const fn max_mux_calls(mut self, max_mux_calls: usize) -> Self {
+        self.max_mux_calls = Some(max_mux_calls);
+        self
+    }
+
+    pub(crate) fn from_extensions(extensions: &http::Extensions) -> Option<Self> {
+        extensions.get::<Self>().copied()
+    }
+
+    /// Applies the size limits to the upgrade
+    pub(crate) fn apply(
+        &self,
+        mut ws_upgrade: axum::extract::WebSocketUpgrade,
+    ) -> axum::extract::WebSocketUpgrade {
+        if let Some(max_message_size) = self.max_message_size {
+            ws_upgrade = ws_upgrade.max_message_size(max_message_size);
+        }
+        if let Some(max_frame_size) = self.max_frame_size {
+            ws_upgrade = ws_upgrade.max_frame_size(max_frame_size);
+        }
+        ws_upgrade
+    }
+}
+
+// *** RateLimiter ***
+
+/// A token bucket that limits the rate of messages
+#[cfg(feature = "ws-streaming")]
+pub(crate) struct RateLimiter {
+    capacity: f64,
+    tokens: f64,
+    // Tokens added per second
+    refill_rate: f64,
+    last_refill: tokio::time::Instant,
+}
+
+#[cfg(feature = "ws-streaming")]
+impl RateLimiter {
+    pub(crate) fn new(messages: u32, per: Duration) -> Self {
+        let capacity = f64::from(messages);
+        Self {
+            capacity,
+            tokens: capacity,
+            refill_rate: capacity / per.as_secs_f64(),
+            last_refill: tokio::time::Instant::now(),
+        }
+    }
+
+    /// Takes a token for a message, returning false if there are none left
+    pub(crate) fn try_acquire(&mut self) -> bool {
+        let now = tokio::time::Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_rate).min(self.capacity);
+        self.last_refill = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
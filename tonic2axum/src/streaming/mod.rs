@@ -1,180 +1,309 @@
 use std::{
-    convert::Infallible,
+    future::Future as _,
+    marker::PhantomData,
     pin::Pin,
-    sync::{Arc, Mutex},
-    task::Poll,
+    task::{Context, Poll},
 };
 
+use bytes::{Buf as _, BufMut as _, Bytes, BytesMut};
 use futures_core::Stream;
 use http_body::Frame;
-use tonic::codec::{DecodeBuf, Decoder};
+use tokio::time::{Instant, Sleep};
 
+#[cfg(feature = "http-streaming")]
+mod collect;
 #[cfg(feature = "http-streaming")]
 mod http;
+mod limits;
+#[cfg(feature = "http-streaming")]
+mod proto;
+#[cfg(feature = "http-streaming")]
+mod raw;
+#[cfg(feature = "http-streaming")]
+mod sse;
+#[cfg(any(feature = "http-streaming", feature = "ws-streaming"))]
+mod status;
 
+#[cfg(feature = "ws-streaming")]
+mod bind;
+#[cfg(all(test, feature = "ws-streaming"))]
+mod fake_socket;
+#[cfg(feature = "ws-streaming")]
+mod keepalive;
+#[cfg(feature = "ws-streaming")]
+mod mux;
 #[cfg(feature = "ws-streaming")]
 mod ws;
 
 #[cfg(feature = "http-streaming")]
-pub use http::{make_stream_request, make_stream_response};
+pub use collect::collect_stream_response;
+#[cfg(feature = "http-streaming")]
+pub use http::{
+    make_stream_request, make_stream_request_with, make_stream_response, make_stream_response_with,
+};
+pub use limits::StreamLimits;
+#[cfg(feature = "ws-streaming")]
+pub use limits::WsLimits;
+#[cfg(feature = "http-streaming")]
+pub use proto::{
+    make_negotiated_stream_request, make_negotiated_stream_request_with,
+    make_negotiated_stream_response, make_proto_stream_request, make_proto_stream_response,
+};
+#[cfg(feature = "http-streaming")]
+pub use raw::{
+    OCTET_STREAM_CONTENT_TYPE, make_http_body_stream_request, make_http_body_stream_response,
+    make_raw_stream_request, make_raw_stream_response,
+};
+#[cfg(feature = "http-streaming")]
+pub use sse::make_sse_response;
 
+#[cfg(feature = "ws-streaming")]
+pub use bind::WsMetadataBinding;
+#[cfg(feature = "ws-streaming")]
+pub use keepalive::WsKeepalive;
+#[cfg(feature = "ws-streaming")]
+pub use mux::{
+    MuxCall, MuxSink, close_mux_call, make_mux_request, make_mux_stream_request,
+    process_mux_response, process_mux_stream_response, upgrade_to_ws_mux,
+};
 #[cfg(feature = "ws-streaming")]
 pub use ws::{
-    close_ws, make_ws_request, make_ws_stream_request, process_ws_response,
-    process_ws_stream_response, upgrade_to_ws,
+    WS_END_OF_INPUT_BINARY, WS_END_OF_INPUT_TEXT, WS_JSON_PROTOCOL, WS_PROTOBUF_PROTOCOL,
+    WsOptions, WsSink, WsStream, close_ws, make_ws_request, make_ws_stream_request,
+    make_ws_stream_request_with, process_ws_response, process_ws_stream_response,
+    process_ws_unary_calls, upgrade_to_ws,
 };
 
-// *** FakeGrpcFrameStreamingHelperInner ***
+// Compression flag (1 byte) + message length (4 bytes)
+const GRPC_HEADER_LEN: usize = 5;
 
-struct FakeGrpcFrameStreamingHelperInner<S, T>
-where
-    S: Stream<Item = Result<T, axum::Error>> + Send + 'static,
-    T: Send + 'static,
-{
-    lines: Pin<Box<S>>,
-    next: Option<Result<T, axum::Error>>,
-}
+// *** RawMessage ***
 
-impl<S, T> FakeGrpcFrameStreamingHelperInner<S, T>
-where
-    S: Stream<Item = Result<T, axum::Error>> + Send + 'static,
-    T: Send + 'static,
-{
-    pub fn new(stream: S) -> Self {
-        Self {
-            lines: Box::pin(stream),
-            next: None,
-        }
-    }
+/// An encoded message to be sent to [tonic::Streaming] as a gRPC frame. The optional tag is written as the first byte
+/// of the frame's message, which lets a decoder know how the rest of the message is encoded.
+struct RawMessage {
+    tag: Option<u8>,
+    payload: Bytes,
 }
 
-// *** EmptyGrpcFrame ***
-
-#[derive(Clone, Default)]
-struct EmptyGrpcFrame([u8; 5]);
-
-impl bytes::Buf for EmptyGrpcFrame {
-    fn remaining(&self) -> usize {
-        (&self.0[..]).remaining()
+impl RawMessage {
+    fn new(payload: Bytes) -> Self {
+        Self { tag: None, payload }
     }
 
-    fn chunk(&self) -> &[u8] {
-        &self.0[..]
-    }
-
-    fn advance(&mut self, cnt: usize) {
-        (&self.0[..]).advance(cnt);
+    #[cfg(feature = "ws-streaming")]
+    fn tagged(tag: u8, payload: Bytes) -> Self {
+        Self {
+            tag: Some(tag),
+            payload,
+        }
     }
 
-    fn copy_to_slice(&mut self, dst: &mut [u8]) {
-        (&self.0[..]).copy_to_slice(dst);
+    /// Encodes the message as an uncompressed gRPC (length-prefixed) frame
+    fn encode(&self) -> Result<Bytes, tonic::Status> {
+        let tag_len = usize::from(self.tag.is_some());
+        let len = u32::try_from(self.payload.len() + tag_len)
+            .map_err(|_| tonic::Status::resource_exhausted("Message too large"))?;
+
+        let mut frame = BytesMut::with_capacity(GRPC_HEADER_LEN + len as usize);
+        frame.put_u8(0);
+        frame.put_u32(len);
+        if let Some(tag) = self.tag {
+            frame.put_u8(tag);
+        }
+        frame.put_slice(&self.payload);
+        Ok(frame.freeze())
     }
 }
 
-// *** FakeGrpcFrameStreamingHelper ***
+// *** FrameBody ***
 
-struct FakeGrpcFrameStreamingHelper<S, T>
+/// Creates a [tonic::Streaming] that decodes the messages from the stream with the decoder, enforcing the limits
+fn new_streaming<D, S>(decoder: D, messages: S, limits: StreamLimits) -> tonic::Streaming<D::Item>
 where
-    S: Stream<Item = Result<T, axum::Error>> + Send + 'static,
-    T: Send + 'static,
+    D: tonic::codec::Decoder<Error = tonic::Status> + Send + 'static,
+    S: Stream<Item = Result<RawMessage, tonic::Status>> + Send + 'static,
 {
-    inner: Arc<Mutex<FakeGrpcFrameStreamingHelperInner<S, T>>>,
-    empty_frame: EmptyGrpcFrame,
+    // Message size is checked by FrameBody, so Tonic's limit only needs room for the tag
+    let max_frame_size = limits.max_message_size.saturating_add(1);
+    let body = FrameBody::new(messages, limits);
+    tonic::Streaming::new_request(decoder, body, None, Some(max_frame_size))
 }
 
-impl<S, T> FakeGrpcFrameStreamingHelper<S, T>
-where
-    S: Stream<Item = Result<T, axum::Error>> + Send + 'static,
-    T: Send + 'static,
-{
-    pub fn new(lines: S) -> Self {
+/// A request body that feeds the messages from a stream to [tonic::Streaming] as gRPC frames, to be decoded by a
+/// matching decoder. Errors from the stream (or exceeding the limits) end the request stream with that status.
+struct FrameBody<S> {
+    messages: Pin<Box<S>>,
+    limits: StreamLimits,
+    count: usize,
+    idle: Option<Pin<Box<Sleep>>>,
+    done: bool,
+}
+
+impl<S> FrameBody<S> {
+    fn new(messages: S, limits: StreamLimits) -> Self {
         Self {
-            inner: Arc::new(Mutex::new(FakeGrpcFrameStreamingHelperInner::new(lines))),
-            empty_frame: EmptyGrpcFrame::default(),
+            messages: Box::pin(messages),
+            limits,
+            count: 0,
+            idle: limits
+                .idle_timeout
+                .map(|timeout| Box::pin(tokio::time::sleep(timeout))),
+            done: false,
         }
     }
-}
 
-impl<S, T> Clone for FakeGrpcFrameStreamingHelper<S, T>
-where
-    S: Stream<Item = Result<T, axum::Error>> + Send + 'static,
-    T: Send + 'static,
-{
-    fn clone(&self) -> Self {
-        Self {
-            inner: self.inner.clone(),
-            empty_frame: self.empty_frame.clone(),
+    fn check_message(&mut self, message: &RawMessage) -> Result<(), tonic::Status> {
+        if message.payload.len() > self.limits.max_message_size {
+            return Err(self.limits.message_too_large());
         }
+
+        self.count += 1;
+        if let Some(max_messages) = self.limits.max_messages
+            && self.count > max_messages
+        {
+            return Err(tonic::Status::resource_exhausted(format!(
+                "Stream exceeds the limit of {max_messages} messages"
+            )));
+        }
+
+        if let (Some(idle), Some(timeout)) = (&mut self.idle, self.limits.idle_timeout) {
+            idle.as_mut().reset(Instant::now() + timeout);
+        }
+        Ok(())
+    }
+
+    /// Returns an error if the idle timeout has elapsed since the last message
+    fn poll_idle(&mut self, cx: &mut Context<'_>) -> Option<tonic::Status> {
+        let timeout = self.limits.idle_timeout?;
+        let idle = self.idle.as_mut()?;
+        idle.as_mut().poll(cx).is_ready().then(|| {
+            tonic::Status::deadline_exceeded(format!("No message received within {timeout:?}"))
+        })
     }
 }
 
-impl<S, T> tonic::transport::Body for FakeGrpcFrameStreamingHelper<S, T>
+impl<S> http_body::Body for FrameBody<S>
 where
-    S: Stream<Item = Result<T, axum::Error>> + Send + 'static,
-    T: Send + 'static,
+    S: Stream<Item = Result<RawMessage, tonic::Status>>,
 {
-    type Data = EmptyGrpcFrame;
-    type Error = Infallible;
+    type Data = Bytes;
+    type Error = tonic::Status;
 
     fn poll_frame(
-        self: std::pin::Pin<&mut Self>,
-        cx: &mut std::task::Context<'_>,
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
     ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
-        let mut inner = self.inner.lock().expect("poisoned lock");
-
-        // If we already have a buffered item, we don't need to poll again
-        if inner.next.is_some() {
-            return Poll::Ready(Some(Ok(Frame::data(self.empty_frame.clone()))));
+        if self.done {
+            return Poll::Ready(None);
         }
 
-        let lines = inner.lines.as_mut();
-
-        // Poll the stream for the next item
-        match lines.poll_next(cx) {
-            // Stream has more items
-            Poll::Ready(Some(item)) => {
-                // Store the item for decode to return
-                inner.next = Some(item);
-                Poll::Ready(Some(Ok(Frame::data(self.empty_frame.clone()))))
+        let result = match self.messages.as_mut().poll_next(cx) {
+            Poll::Ready(Some(Ok(message))) => self
+                .check_message(&message)
+                .and_then(|()| message.encode().map(Frame::data)),
+            Poll::Ready(Some(Err(status))) => Err(status),
+            Poll::Ready(None) => {
+                self.done = true;
+                return Poll::Ready(None);
             }
-            // Stream ended
-            Poll::Ready(None) => Poll::Ready(None),
-            // Not ready
-            Poll::Pending => Poll::Pending,
-        }
+            Poll::Pending => match self.poll_idle(cx) {
+                Some(status) => Err(status),
+                None => return Poll::Pending,
+            },
+        };
+
+        self.done = result.is_err();
+        Poll::Ready(Some(result))
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.done
+    }
+}
+
+// *** MapDecoder ***
+
+/// Transforms each item produced by a decoder
+#[cfg(any(feature = "http-streaming", feature = "ws-streaming"))]
+struct MapDecoder<D, F> {
+    decoder: D,
+    transform: F,
+}
+
+#[cfg(any(feature = "http-streaming", feature = "ws-streaming"))]
+impl<D, F> MapDecoder<D, F> {
+    fn new(decoder: D, transform: F) -> Self {
+        Self { decoder, transform }
     }
 }
 
-impl<S, T> Decoder for FakeGrpcFrameStreamingHelper<S, T>
+#[cfg(any(feature = "http-streaming", feature = "ws-streaming"))]
+impl<D, F, U> tonic::codec::Decoder for MapDecoder<D, F>
 where
-    S: Stream<Item = Result<T, axum::Error>> + Send + 'static,
-    T: Send + 'static,
+    D: tonic::codec::Decoder<Error = tonic::Status>,
+    F: FnMut(D::Item) -> U,
 {
-    type Item = T;
-
+    type Item = U;
     type Error = tonic::Status;
 
-    fn decode(&mut self, _src: &mut DecodeBuf<'_>) -> Result<Option<Self::Item>, Self::Error> {
-        let mut inner = self.inner.lock().expect("poisoned lock");
+    fn decode(
+        &mut self,
+        src: &mut tonic::codec::DecodeBuf<'_>,
+    ) -> Result<Option<Self::Item>, Self::Error> {
+        Ok(self.decoder.decode(src)?.map(&mut self.transform))
+    }
+}
+
+// *** JsonDecoder ***
+
+/// Deserializes the JSON held by each gRPC frame
+#[cfg(any(feature = "http-streaming", feature = "ws-streaming"))]
+struct JsonDecoder<T>(PhantomData<fn() -> T>);
+
+#[cfg(any(feature = "http-streaming", feature = "ws-streaming"))]
+impl<T> JsonDecoder<T> {
+    fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+#[cfg(any(feature = "http-streaming", feature = "ws-streaming"))]
+impl<T: serde::de::DeserializeOwned> tonic::codec::Decoder for JsonDecoder<T> {
+    type Item = T;
+    type Error = tonic::Status;
 
-        // Return the buffered item if available
-        inner
-            .next
-            .take()
-            .transpose()
-            .map_err(|e| tonic::Status::internal(e.to_string()))
+    fn decode(
+        &mut self,
+        src: &mut tonic::codec::DecodeBuf<'_>,
+    ) -> Result<Option<Self::Item>, Self::Error> {
+        // The frame is always contiguous since it is uncompressed
+        let len = src.remaining();
+        let result = serde_json::from_slice(&src.chunk()[..len]);
+        src.advance(len);
+        result
+            .map(Some)
+            .map_err(|err| tonic::Status::invalid_argument(err.to_string()))
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "http-streaming"))]
 mod tests {
-    use axum::extract::FromRequest as _;
-    use axum_extra::extract::JsonLines;
+    use std::pin::Pin;
+
     use bytes::Bytes;
-    use futures_util::StreamExt as _;
+    use futures_util::{StreamExt as _, future::poll_fn};
+    use http_body::Body as _;
     use serde::{Deserialize, Serialize};
 
-    use crate::make_stream_request;
+    use crate::{
+        CollectLimits, Heartbeat, ResponseOptions, StreamFormat, StreamLimits, collect_requested,
+        collect_stream_response, make_http_body_stream_request, make_http_body_stream_response,
+        make_negotiated_stream_request, make_negotiated_stream_request_with,
+        make_negotiated_stream_response, make_raw_stream_request, make_raw_stream_response,
+        make_sse_response, make_stream_request, make_stream_request_with,
+        make_stream_response_with,
+    };
 
     #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
     struct TestRequest {
@@ -203,17 +332,13 @@ mod tests {
             }
         };
 
-        // We have to build JsonLines this way because it has to be built from a request to get the correct type parameters.
         let body = axum::body::Body::from_stream(stream);
-        let request = http::Request::new(body);
-        let json_lines: JsonLines<TestRequest> =
-            JsonLines::from_request(request, &()).await.unwrap();
         let headers = http::HeaderMap::new();
         let extensions = http::Extensions::new();
 
-        let request = make_stream_request(headers, extensions, json_lines);
+        let request = make_stream_request(headers, extensions, body);
 
-        let mut streaming = request.into_inner();
+        let mut streaming: tonic::Streaming<TestRequest> = request.into_inner();
         let mut received_items = Vec::with_capacity(requests.len());
 
         while let Some(result) = streaming.next().await {
@@ -225,4 +350,629 @@ mod tests {
 
         assert_eq!(received_items, requests);
     }
+
+    #[tokio::test]
+    async fn test_make_stream_request_rejects_protobuf() {
+        let mut headers = http::HeaderMap::new();
+        headers.insert(
+            "content-type",
+            "application/x-protobuf-stream".parse().unwrap(),
+        );
+        let body = axum::body::Body::from(vec![0, 0, 0, 0]);
+
+        let mut streaming: tonic::Streaming<TestRequest> =
+            make_stream_request(headers, http::Extensions::new(), body).into_inner();
+        let status = streaming.next().await.unwrap().unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn test_make_stream_request_split_lines() {
+        // Lines split across chunks, blank lines, CRLF and no trailing newline
+        let chunks = ["{\"id\":", "1}\r\n\n  \n{\"id\"", ":2}\n{\"id\":3}"];
+        let stream = futures_util::stream::iter(chunks.map(Ok::<_, tonic::Status>));
+        let body = axum::body::Body::from_stream(stream);
+
+        let request: tonic::Request<tonic::Streaming<TestRequest>> =
+            make_stream_request(http::HeaderMap::new(), http::Extensions::new(), body);
+        let received_items: Vec<_> = request
+            .into_inner()
+            .map(|result| result.unwrap().id)
+            .collect()
+            .await;
+        assert_eq!(received_items, vec![1, 2, 3]);
+
+        // Invalid JSON fails the stream
+        let body = axum::body::Body::from("{\"id\":1}\nnot json\n");
+        let mut streaming: tonic::Streaming<TestRequest> =
+            make_stream_request(http::HeaderMap::new(), http::Extensions::new(), body).into_inner();
+        assert_eq!(streaming.next().await.unwrap().unwrap().id, 1);
+        let status = streaming.next().await.unwrap().unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn test_json_array_stream() {
+        // Elements split across chunks, with delimiters inside strings and nested values
+        let chunks = [" [{\"a\": \"],\\\"[\"}", ", [1, {\"b\": [2]}] ,", "3\n]\n"];
+        let stream = futures_util::stream::iter(chunks.map(Ok::<_, tonic::Status>));
+        let mut headers = http::HeaderMap::new();
+        headers.insert("content-type", "application/json".parse().unwrap());
+
+        let request: tonic::Request<tonic::Streaming<serde_json::Value>> = make_stream_request_with(
+            headers.clone(),
+            http::Extensions::new(),
+            axum::body::Body::from_stream(stream),
+            true,
+            |item| item,
+        );
+        let received_items: Vec<_> = request
+            .into_inner()
+            .map(|result| result.unwrap())
+            .collect()
+            .await;
+        let expected = serde_json::json!([{"a": "],\"["}, [1, {"b": [2]}], 3]);
+        assert_eq!(serde_json::Value::from(received_items), expected);
+
+        // Empty arrays have no items, and malformed arrays fail the stream
+        for (body, expected) in [
+            ("[ ]", vec![]),
+            ("[1,,2]", vec![Some(1)]),
+            ("[1,2", vec![Some(1)]),
+            ("[1] 2", vec![Some(1)]),
+            ("{\"id\":1}", vec![]),
+        ] {
+            let request: tonic::Request<tonic::Streaming<u32>> = make_stream_request_with(
+                headers.clone(),
+                http::Extensions::new(),
+                body.into(),
+                true,
+                |item| item,
+            );
+            // Only the first error matters (tonic repeats body errors)
+            let mut received_items: Vec<_> = request
+                .into_inner()
+                .map(|result| result.ok())
+                .collect()
+                .await;
+            let error_pos = received_items.iter().position(Option::is_none);
+            assert_eq!(error_pos.is_some(), body != "[ ]", "{body}");
+            received_items.truncate(error_pos.unwrap_or(received_items.len()));
+            assert_eq!(received_items, expected, "{body}");
+        }
+
+        // Unless JSON arrays are enabled, application/json bodies are JSON Lines
+        let body = axum::body::Body::from("{\"id\":1}\n{\"id\":2}\n");
+        let request: tonic::Request<tonic::Streaming<TestRequest>> =
+            make_stream_request(headers.clone(), http::Extensions::new(), body);
+        let received_items: Vec<_> = request
+            .into_inner()
+            .map(|result| result.unwrap().id)
+            .collect()
+            .await;
+        assert_eq!(received_items, vec![1, 2]);
+
+        // Response uses the format from the Accept header, ending with an error element on failure
+        let mut headers = http::HeaderMap::new();
+        headers.insert("accept", "application/json".parse().unwrap());
+        let stream = async_stream::stream! {
+            yield Ok(TestRequest { id: 1 });
+            yield Ok(TestRequest { id: 2 });
+            yield Err(tonic::Status::not_found("missing"));
+        };
+        let format = StreamFormat::from_accept_or(&headers, StreamFormat::JsonLines);
+        let options = ResponseOptions::default().stream_format(format);
+
+        let response = make_stream_response_with(Ok(tonic::Response::new(stream)), options);
+        assert_eq!(response.headers()["content-type"], "application/json");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(
+            body,
+            "[{\"id\":1},{\"id\":2},{\"error\":{\"code\":5,\"details\":[],\"message\":\"missing\"}}]"
+        );
+
+        let stream = futures_util::stream::empty::<Result<TestRequest, tonic::Status>>();
+        let options = ResponseOptions::default().stream_format(StreamFormat::JsonArray);
+        let response = make_stream_response_with(Ok(tonic::Response::new(stream)), options);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(body, "[]");
+    }
+
+    #[tokio::test]
+    async fn test_collect_stream_response() {
+        let uri: http::Uri = "/items?page=1&$stream=false".parse().unwrap();
+        assert!(collect_requested(&uri, &http::HeaderMap::new(), false));
+        let encoded: http::Uri = "/items?%24stream=false".parse().unwrap();
+        assert!(collect_requested(&encoded, &http::HeaderMap::new(), false));
+        // A request field named `stream` isn't mistaken for the parameter
+        let field: http::Uri = "/items?stream=false".parse().unwrap();
+        assert!(!collect_requested(&field, &http::HeaderMap::new(), false));
+
+        // A stream format preferred in the Accept header wins over the query parameter
+        let items_uri: http::Uri = "/items".parse().unwrap();
+        let mut headers = http::HeaderMap::new();
+        headers.insert(
+            "accept",
+            "application/x-ndjson, application/json".parse().unwrap(),
+        );
+        assert!(!collect_requested(&items_uri, &headers, false));
+        assert!(!collect_requested(&uri, &headers, false));
+        headers.insert("accept", "application/json".parse().unwrap());
+        assert!(collect_requested(&items_uri, &headers, false));
+
+        // With JSON array streams, application/json is streamed unless the query parameter asks otherwise
+        assert!(!collect_requested(&items_uri, &headers, true));
+        assert!(collect_requested(&uri, &headers, true));
+
+        let items = || {
+            let items = [1, 2, 3].map(|id| Ok(TestRequest { id }));
+            tonic::Response::new(futures_util::stream::iter(items))
+        };
+
+        let options = ResponseOptions::default().collect_stream(true);
+        let response =
+            collect_stream_response(Ok(items()), options, make_stream_response_with).await;
+        assert_eq!(response.status(), http::StatusCode::OK);
+        assert_eq!(response.headers()["content-type"], "application/json");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(body, "[{\"id\":1},{\"id\":2},{\"id\":3}]");
+
+        // Exactly at the limits, then over them
+        for (limits, status) in [
+            (
+                CollectLimits::new().max_items(3).max_bytes(28),
+                http::StatusCode::OK,
+            ),
+            (
+                CollectLimits::new().max_items(2),
+                http::StatusCode::TOO_MANY_REQUESTS,
+            ),
+            (
+                CollectLimits::new().max_bytes(27),
+                http::StatusCode::TOO_MANY_REQUESTS,
+            ),
+        ] {
+            let options = ResponseOptions::default()
+                .collect_stream(true)
+                .collect_limits(limits);
+            let response =
+                collect_stream_response(Ok(items()), options, make_stream_response_with).await;
+            assert_eq!(response.status(), status, "{limits:?}");
+        }
+
+        // Stream errors become error responses
+        let stream = async_stream::stream! {
+            yield Ok(TestRequest { id: 1 });
+            yield Err(tonic::Status::not_found("missing"));
+        };
+        let options = ResponseOptions::default().collect_stream(true);
+        let response = collect_stream_response(
+            Ok(tonic::Response::new(stream)),
+            options,
+            make_stream_response_with,
+        )
+        .await;
+        assert_eq!(response.status(), http::StatusCode::NOT_FOUND);
+
+        // Not collecting streams as usual
+        let response = collect_stream_response(
+            Ok(items()),
+            ResponseOptions::default(),
+            make_stream_response_with,
+        )
+        .await;
+        assert_eq!(response.headers()["content-type"], "application/x-ndjson");
+    }
+
+    #[tokio::test]
+    async fn test_negotiated_proto_stream() {
+        // Prost encodes a String as a `google.protobuf.StringValue` (field 1, omitted when empty), prefixed by the
+        // length
+        let message = |value: &str| match value.len() as u8 {
+            0 => vec![0, 0, 0, 0],
+            len => [&[0, 0, 0, len + 2, 0x0A, len], value.as_bytes()].concat(),
+        };
+        let data = [message("a"), message(""), message("bc")].concat();
+
+        // Frames split across chunks, including within the length prefix
+        let chunks = [data[..2].to_vec(), data[2..8].to_vec(), data[8..].to_vec()];
+        let stream = futures_util::stream::iter(chunks.map(Ok::<_, tonic::Status>));
+        let mut headers = http::HeaderMap::new();
+        headers.insert(
+            "content-type",
+            "application/x-protobuf-stream".parse().unwrap(),
+        );
+
+        let request: tonic::Request<tonic::Streaming<String>> = make_negotiated_stream_request(
+            headers.clone(),
+            http::Extensions::new(),
+            axum::body::Body::from_stream(stream),
+        );
+        let received_items: Vec<_> = request
+            .into_inner()
+            .map(|result| result.unwrap())
+            .collect()
+            .await;
+        assert_eq!(received_items, vec!["a", "", "bc"]);
+
+        // Truncated message fails the stream
+        let body = axum::body::Body::from(data[..data.len() - 1].to_vec());
+        let mut streaming: tonic::Streaming<String> =
+            make_negotiated_stream_request(headers, http::Extensions::new(), body).into_inner();
+        assert_eq!(streaming.next().await.unwrap().unwrap(), "a");
+        assert_eq!(streaming.next().await.unwrap().unwrap(), "");
+        let status = streaming.next().await.unwrap().unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+
+        // Response uses the format from the Accept header
+        let mut headers = http::HeaderMap::new();
+        headers.insert(
+            "accept",
+            "application/json, application/x-protobuf-stream;q=0.9"
+                .parse()
+                .unwrap(),
+        );
+        let stream = futures_util::stream::iter(["a", "", "bc"].map(|item| Ok(item.to_string())));
+        let options = ResponseOptions::default().stream_format(StreamFormat::from_accept(&headers));
+
+        let response = make_negotiated_stream_response(Ok(tonic::Response::new(stream)), options);
+        assert_eq!(
+            response.headers()["content-type"],
+            "application/x-protobuf-stream"
+        );
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(body, data);
+    }
+
+    #[tokio::test]
+    async fn test_make_stream_request_with_transform() {
+        // ie. a path parameter merged into each message
+        let offset = 10;
+        let body = axum::body::Body::from("{\"id\":1}\n{\"id\":2}\n");
+        let request = make_stream_request_with(
+            http::HeaderMap::new(),
+            http::Extensions::new(),
+            body,
+            false,
+            move |item: TestRequest| item.id + offset,
+        );
+
+        let received_items: Vec<_> = request
+            .into_inner()
+            .map(|result| result.unwrap())
+            .collect()
+            .await;
+        assert_eq!(received_items, vec![11, 12]);
+    }
+
+    #[tokio::test]
+    async fn test_negotiated_stream_request_with_transform() {
+        // ie. a path parameter merged into each message, whichever format the body uses
+        let suffix = "!";
+        let request = |headers, body| {
+            make_negotiated_stream_request_with(
+                headers,
+                http::Extensions::new(),
+                body,
+                false,
+                move |item: TestRequest| format!("{}{suffix}", item.id),
+                move |item: String| format!("{item}{suffix}"),
+            )
+        };
+
+        let body = axum::body::Body::from("{\"id\":1}\n{\"id\":2}\n");
+        let received_items: Vec<_> = request(http::HeaderMap::new(), body)
+            .into_inner()
+            .map(|result| result.unwrap())
+            .collect()
+            .await;
+        assert_eq!(received_items, vec!["1!", "2!"]);
+
+        let mut headers = http::HeaderMap::new();
+        headers.insert(
+            "content-type",
+            "application/x-protobuf-stream".parse().unwrap(),
+        );
+        let body = axum::body::Body::from(vec![0, 0, 0, 3, 0x0A, 1, b'a']);
+        let received_items: Vec<_> = request(headers, body)
+            .into_inner()
+            .map(|result| result.unwrap())
+            .collect()
+            .await;
+        assert_eq!(received_items, vec!["a!"]);
+    }
+
+    #[tokio::test]
+    async fn test_make_stream_request_limits() {
+        let first_error = async |limits: StreamLimits, body: axum::body::Body| {
+            let mut extensions = http::Extensions::new();
+            extensions.insert(limits);
+            let mut streaming: tonic::Streaming<TestRequest> =
+                make_stream_request(http::HeaderMap::new(), extensions, body).into_inner();
+            loop {
+                match streaming.next().await.unwrap() {
+                    Ok(_) => continue,
+                    Err(status) => return status,
+                }
+            }
+        };
+
+        // Line without a newline is rejected once it is too long, even before it is complete
+        let chunks = ["{\"id\":1}\n{\"id\":", "12345", "67890"];
+        let body = axum::body::Body::from_stream(futures_util::stream::iter(
+            chunks.map(Ok::<_, tonic::Status>),
+        ));
+        let status = first_error(StreamLimits::new().max_message_size(12), body).await;
+        assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+
+        let body = axum::body::Body::from("{\"id\":1}\n{\"id\":2}\n{\"id\":3}\n");
+        let status = first_error(StreamLimits::new().max_messages(2), body).await;
+        assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+
+        // Client never sends anything
+        let stream = futures_util::stream::pending::<Result<Bytes, tonic::Status>>();
+        let limits = StreamLimits::new().idle_timeout(std::time::Duration::from_millis(10));
+        let status = first_error(limits, axum::body::Body::from_stream(stream)).await;
+        assert_eq!(status.code(), tonic::Code::DeadlineExceeded);
+    }
+
+    #[tokio::test]
+    async fn test_raw_stream() {
+        // Body chunks become messages, split at the maximum message size
+        let mut extensions = http::Extensions::new();
+        extensions.insert(StreamLimits::new().max_message_size(4));
+        let chunks = ["abc", "defghij"];
+        let body = axum::body::Body::from_stream(futures_util::stream::iter(
+            chunks.map(Ok::<_, tonic::Status>),
+        ));
+        let request = make_raw_stream_request(http::HeaderMap::new(), extensions, body, |chunk| {
+            String::from_utf8(chunk.to_vec()).unwrap()
+        });
+        let messages: Vec<_> = request
+            .into_inner()
+            .map(|message| message.unwrap())
+            .collect()
+            .await;
+        assert_eq!(messages, ["abc", "defg", "hij"]);
+
+        // Content headers come from the initial metadata
+        let stream =
+            futures_util::stream::iter(["hello ", "world"].map(|item| Ok(item.to_string())));
+        let mut response = tonic::Response::new(stream);
+        let metadata = response.metadata_mut();
+        metadata.insert("content-type", "text/plain".parse().unwrap());
+        metadata.insert(
+            "content-disposition",
+            "attachment; filename=\"hello.txt\"".parse().unwrap(),
+        );
+        metadata.insert("content-length", "11".parse().unwrap());
+        metadata.insert("x-checksum", "abc".parse().unwrap());
+
+        let response = make_raw_stream_response(
+            Ok(response),
+            ResponseOptions::default(),
+            crate::OCTET_STREAM_CONTENT_TYPE,
+            Bytes::from,
+        );
+        let headers = response.headers();
+        assert_eq!(headers["content-type"], "text/plain");
+        assert_eq!(
+            headers["content-disposition"],
+            "attachment; filename=\"hello.txt\""
+        );
+        assert!(!headers.contains_key("content-length"));
+        assert_eq!(headers["grpc-metadata-x-checksum"], "abc");
+        assert!(!headers.contains_key("grpc-metadata-content-disposition"));
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(body, "hello world");
+    }
+
+    #[tokio::test]
+    async fn test_http_body_stream() {
+        // Each message carries the content type of the request
+        let mut headers = http::HeaderMap::new();
+        headers.insert("content-type", "text/csv".parse().unwrap());
+        let body = axum::body::Body::from("a,b");
+        let request = make_http_body_stream_request(
+            headers,
+            http::Extensions::new(),
+            body,
+            |content_type, data| (content_type, data),
+        );
+        let messages: Vec<_> = request
+            .into_inner()
+            .map(|message| message.unwrap())
+            .collect()
+            .await;
+        assert_eq!(messages, [("text/csv".to_string(), Bytes::from("a,b"))]);
+
+        // The content type comes from the first message
+        let stream = futures_util::stream::iter(
+            [("image/png", "ab"), ("", "cd")].map(Ok::<_, tonic::Status>),
+        );
+        let response = make_http_body_stream_response(
+            Ok(tonic::Response::new(stream)),
+            ResponseOptions::default(),
+            |(content_type, data): (&str, &str)| (content_type.to_string(), Bytes::from(data)),
+        )
+        .await;
+        assert_eq!(response.headers()["content-type"], "image/png");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(body, "abcd");
+
+        // An error in place of the first message is an error response
+        let stream = futures_util::stream::iter([Err::<(&str, &str), _>(
+            tonic::Status::permission_denied("denied"),
+        )]);
+        let response = make_http_body_stream_response(
+            Ok(tonic::Response::new(stream)),
+            ResponseOptions::default(),
+            |(content_type, data): (&str, &str)| (content_type.to_string(), Bytes::from(data)),
+        )
+        .await;
+        assert_eq!(response.status(), http::StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_make_stream_response_trailers() {
+        let stream = async_stream::stream! {
+            yield Ok(TestRequest { id: 1 });
+            let mut status = tonic::Status::not_found("missing");
+            status.metadata_mut().insert("x-reason", "gone".parse().unwrap());
+            yield Err(status);
+        };
+
+        let response =
+            make_stream_response_with(Ok(tonic::Response::new(stream)), ResponseOptions::default());
+        assert_eq!(response.headers()["content-type"], "application/x-ndjson");
+
+        let mut body = response.into_body();
+        let mut next_frame = async || {
+            poll_fn(|cx| Pin::new(&mut body).poll_frame(cx))
+                .await
+                .map(|frame| frame.unwrap())
+        };
+
+        let data = next_frame().await.unwrap().into_data().unwrap();
+        assert_eq!(data, "{\"id\":1}\n");
+
+        let trailers = next_frame().await.unwrap().into_trailers().unwrap();
+        assert_eq!(trailers["grpc-status"], "5");
+        assert_eq!(trailers["grpc-message"], "missing");
+        assert_eq!(trailers["grpc-trailer-x-reason"], "gone");
+
+        assert!(next_frame().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_make_stream_response_envelope() {
+        let stream = async_stream::stream! {
+            yield Ok(TestRequest { id: 1 });
+            yield Err(tonic::Status::not_found("missing"));
+        };
+
+        let options = ResponseOptions::default().stream_envelope(true);
+        let response = make_stream_response_with(Ok(tonic::Response::new(stream)), options);
+
+        let mut body = response.into_body();
+        let mut next_frame = async || {
+            poll_fn(|cx| Pin::new(&mut body).poll_frame(cx))
+                .await
+                .map(|frame| frame.unwrap())
+        };
+
+        let data = next_frame().await.unwrap().into_data().unwrap();
+        assert_eq!(data, "{\"result\":{\"id\":1}}\n");
+        let data = next_frame().await.unwrap().into_data().unwrap();
+        assert_eq!(
+            data,
+            "{\"error\":{\"code\":5,\"details\":[],\"message\":\"missing\"}}\n"
+        );
+
+        let trailers = next_frame().await.unwrap().into_trailers().unwrap();
+        assert_eq!(trailers["grpc-status"], "5");
+        assert!(next_frame().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_make_stream_response_envelope_serialize_error() {
+        // JSON object keys must be strings, so this fails to serialize
+        let stream = async_stream::stream! {
+            yield Ok::<_, tonic::Status>(std::collections::BTreeMap::from([((1, 2), 3)]));
+        };
+
+        let options = ResponseOptions::default().stream_envelope(true);
+        let response = make_stream_response_with(Ok(tonic::Response::new(stream)), options);
+
+        let body = axum::body::to_bytes(axum::body::Body::new(response.into_body()), usize::MAX)
+            .await
+            .unwrap();
+        let error: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error["error"]["code"], 13);
+    }
+
+    #[tokio::test]
+    async fn test_heartbeat() {
+        let slow_stream = || {
+            async_stream::stream! {
+                yield Ok::<_, tonic::Status>(TestRequest { id: 1 });
+                tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                yield Ok(TestRequest { id: 2 });
+            }
+        };
+        let heartbeat = Heartbeat::new(std::time::Duration::from_millis(20));
+
+        // Keepalive lines are sent between the items while the stream is idle
+        let options = ResponseOptions::default()
+            .heartbeat(heartbeat.clone().keepalive_object("{\"keepalive\":true}"));
+        let response = make_stream_response_with(Ok(tonic::Response::new(slow_stream())), options);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body = std::str::from_utf8(&body).unwrap();
+        let lines: Vec<_> = body.lines().collect();
+        assert_eq!(lines.first(), Some(&"{\"id\":1}"));
+        assert_eq!(lines.last(), Some(&"{\"id\":2}"));
+        assert!(lines.len() > 2);
+        assert!(
+            lines[1..lines.len() - 1]
+                .iter()
+                .all(|line| *line == "{\"keepalive\":true}")
+        );
+
+        // SSE heartbeats are comments
+        let options = ResponseOptions::default().heartbeat(heartbeat);
+        let response = make_sse_response(Ok(tonic::Response::new(slow_stream())), options);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body = std::str::from_utf8(&body).unwrap();
+        assert!(body.starts_with("event: message\ndata: {\"id\":1}\n\n:"));
+        assert!(body.contains("\n\nevent: message\ndata: {\"id\":2}\n\n"));
+    }
+
+    #[tokio::test]
+    async fn test_make_sse_response() {
+        let stream = async_stream::stream! {
+            yield Ok(TestRequest { id: 1 });
+            yield Err(tonic::Status::not_found("missing"));
+        };
+
+        let response =
+            make_sse_response(Ok(tonic::Response::new(stream)), ResponseOptions::default());
+        assert_eq!(response.headers()["content-type"], "text/event-stream");
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(
+            body,
+            "event: message\ndata: {\"id\":1}\n\nevent: error\ndata: {\"code\":5,\"details\":[],\"message\":\"missing\"}\n\n"
+        );
+
+        let stream = async_stream::stream! {
+            yield Ok::<_, tonic::Status>(TestRequest { id: 1 });
+        };
+        let response =
+            make_sse_response(Ok(tonic::Response::new(stream)), ResponseOptions::default());
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(
+            body.ends_with(b"event: end\ndata: {\"code\":0,\"details\":[],\"message\":\"\"}\n\n")
+        );
+    }
 }
@@ -0,0 +1,573 @@
+use std::{
+    collections::HashMap,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use axum::{
+    extract::{
+        WebSocketUpgrade,
+        ws::{Message, WebSocket},
+    },
+    response::Response,
+};
+use bytes::Bytes;
+use futures_core::Stream;
+use futures_util::StreamExt as _;
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::value::RawValue;
+use tokio::{
+    sync::mpsc::{self, error::TrySendError},
+    task::AbortHandle,
+};
+
+use crate::streaming::{
+    JsonDecoder, RawMessage, StreamLimits, WsLimits,
+    bind::bind_metadata,
+    keepalive::{WsKeepalive, run_ws},
+    new_streaming,
+    status::status_json,
+    ws::{WsProtocol, WsSink, WsStream},
+};
+
+// Frames waiting to be written to the socket, shared by all calls on a connection. This is also the number of request
+// messages a call can have waiting to be taken.
+const FRAME_BUFFER: usize = 32;
+
+// Calls that can run at once on a connection, unless set by [WsLimits::max_mux_calls]
+const DEFAULT_MAX_CALLS: usize = 100;
+
+// *** Upgrade ***
+
+/// Upgrades the connection to a WebSocket that multiplexes calls to the methods of a service. Each call is started by
+/// the client with a `{"id": 1, "method": "Name"}` text frame (which can also hold the first message), followed by any
+/// number of `{"id": 1, "message": {...}}` frames and a final `{"id": 1, "end": true}` frame (or `"end": true` in the
+/// last message frame) once there are no more request messages. A call is cancelled with `{"id": 1, "cancel": true}`.
+/// Messages are JSON and calls run concurrently, with the server tagging each response frame with the id of its call:
+/// `{"id": 1, "message": {...}}` for each response message, ending with `{"id": 1, "status": {...}}` holding the final
+/// status of the call. Call ids can be reused once a call has ended.
+///
+/// Starting a call while the maximum number of calls are running (see [WsLimits::max_mux_calls]) ends it right away with
+/// `RESOURCE_EXHAUSTED`, as does sending a call more request messages than it has taken so far plus a small buffer.
+///
+/// The dispatcher starts each call, typically matching its [MuxCall::method] to the method to call. The headers and
+/// extensions of the upgrade request apply to every call, and calls still running when the connection is closed are
+/// cancelled. If a [WsKeepalive] or [WsLimits] are installed, they supervise the connection, and if a
+/// [crate::WsMetadataBinding] is installed, the values it binds from the upgrade request are added to the metadata of
+/// every call.
+pub async fn upgrade_to_ws_mux<D, Fut>(
+    ws_upgrade: WebSocketUpgrade,
+    uri: http::Uri,
+    headers: http::HeaderMap,
+    mut extensions: http::Extensions,
+    dispatcher: D,
+) -> Response
+where
+    D: Fn(MuxCall) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    bind_metadata(&uri, &headers, &mut extensions);
+    let limits = WsLimits::from_extensions(&extensions);
+    let ws_upgrade = match limits {
+        Some(limits) => limits.apply(ws_upgrade),
+        None => ws_upgrade,
+    };
+    ws_upgrade.on_upgrade(move |socket: WebSocket| async move {
+        let keepalive = WsKeepalive::from_extensions(&extensions);
+        // Calls are ended with status frames of their own
+        run_ws(
+            socket,
+            keepalive,
+            limits,
+            WsProtocol::default(),
+            |stream, sink| run_mux(stream, sink, headers, extensions, dispatcher),
+        )
+        .await;
+    })
+}
+
+// The state of a running call, which is cancelled when dropped
+struct CallHandle {
+    // Dropped once the client ends the request messages
+    messages: Option<mpsc::Sender<Bytes>>,
+    task: AbortHandle,
+}
+
+impl Drop for CallHandle {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn run_mux<D, Fut>(
+    mut incoming: WsStream,
+    outgoing: WsSink,
+    headers: http::HeaderMap,
+    extensions: http::Extensions,
+    dispatcher: D,
+) where
+    D: Fn(MuxCall) -> Fut,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let max_calls = WsLimits::from_extensions(&extensions)
+        .and_then(|limits| limits.max_mux_calls)
+        .unwrap_or(DEFAULT_MAX_CALLS);
+
+    // All frames are written by a single task, so calls only wait on each other when the buffer is full
+    let (frames, mut pending) = mpsc::channel::<Message>(FRAME_BUFFER);
+    let writer = tokio::spawn(async move {
+        while let Some(frame) = pending.recv().await {
+            if let Err(err) = outgoing.send(frame).await {
+                tracing::trace!("Error sending multiplexed WS frame: {}", err);
+                break;
+            }
+        }
+        if let Err(err) = outgoing.close().await {
+            tracing::trace!("Close handshake completed: {}", err);
+        }
+    });
+
+    let mut calls: HashMap<u64, CallHandle> = HashMap::new();
+
+    while let Some(message) = incoming.next().await {
+        let text = match message {
+            Ok(Message::Text(text)) => text,
+            Ok(Message::Binary(_)) => {
+                tracing::warn!("Ignoring binary frame on multiplexed WebSocket");
+                continue;
+            }
+            Ok(Message::Close(_)) => break,
+            // Ping/pong
+            Ok(_) => continue,
+            Err(err) => {
+                tracing::trace!("Error receiving multiplexed WS frame: {}", err);
+                break;
+            }
+        };
+        let frame = match MuxFrame::parse(&text) {
+            Ok(frame) => frame,
+            Err(err) => {
+                tracing::warn!("Ignoring invalid multiplexed WS frame: {}", err);
+                continue;
+            }
+        };
+        let id = frame.id;
+
+        // Ended calls are forgotten, so their ids can be reused
+        calls.retain(|_, call| !call.task.is_finished());
+
+        if frame.cancel {
+            if calls.remove(&id).is_some() {
+                let sink = MuxSink::new(id, frames.clone());
+                sink.finish(tonic::Status::cancelled("Call cancelled"))
+                    .await;
+            }
+            continue;
+        }
+
+        if let Some(method) = frame.method {
+            if calls.contains_key(&id) {
+                let sink = MuxSink::new(id, frames.clone());
+                sink.finish(tonic::Status::already_exists("Call id already in use"))
+                    .await;
+                continue;
+            }
+            if calls.len() >= max_calls {
+                let sink = MuxSink::new(id, frames.clone());
+                sink.finish(tonic::Status::resource_exhausted(format!(
+                    "Exceeded the limit of {max_calls} concurrent calls"
+                )))
+                .await;
+                continue;
+            }
+
+            let (messages, receiver) = mpsc::channel(FRAME_BUFFER);
+            let call = MuxCall {
+                method,
+                headers: headers.clone(),
+                extensions: extensions.clone(),
+                messages: MuxMessages(receiver),
+                sink: MuxSink::new(id, frames.clone()),
+            };
+            let task = tokio::spawn(dispatcher(call)).abort_handle();
+            calls.insert(
+                id,
+                CallHandle {
+                    messages: Some(messages),
+                    task,
+                },
+            );
+        }
+
+        // Messages for unknown calls are ignored, as the call may have just ended
+        if let Some(call) = calls.get_mut(&id) {
+            if let Some(message) = frame.message
+                && let Some(messages) = &call.messages
+                // The call may have ended without reading all its messages, so only a full buffer is an error
+                && let Err(TrySendError::Full(_)) =
+                    messages.try_send(Bytes::copy_from_slice(message.get().as_bytes()))
+            {
+                calls.remove(&id);
+                let sink = MuxSink::new(id, frames.clone());
+                sink.finish(tonic::Status::resource_exhausted(
+                    "Too many request messages waiting to be taken by the call",
+                ))
+                .await;
+                continue;
+            }
+            if frame.end {
+                call.messages = None;
+            }
+        }
+    }
+
+    // The writer ends once the cancelled calls have dropped their sinks
+    drop(calls);
+    drop(frames);
+    let _ = writer.await;
+}
+
+// *** MuxFrame ***
+
+/// A frame sent by the client
+struct MuxFrame<'a> {
+    id: u64,
+    method: Option<String>,
+    message: Option<&'a RawValue>,
+    end: bool,
+    cancel: bool,
+}
+
+impl<'a> MuxFrame<'a> {
+    fn parse(text: &'a str) -> Result<Self, serde_json::Error> {
+        let mut fields: HashMap<&str, &RawValue> = serde_json::from_str(text)?;
+        let mut field = |name| fields.remove(name).map(|value| value.get());
+
+        let id = serde_json::from_str(field("id").unwrap_or("null"))?;
+        let method = field("method").map(serde_json::from_str).transpose()?;
+        let end = field("end").map(serde_json::from_str).transpose()?;
+        let cancel = field("cancel").map(serde_json::from_str).transpose()?;
+        Ok(Self {
+            id,
+            method,
+            message: fields.remove("message"),
+            end: end.unwrap_or_default(),
+            cancel: cancel.unwrap_or_default(),
+        })
+    }
+}
+
+// *** MuxCall ***
+
+/// A call started on a multiplexed WebSocket connection (see [upgrade_to_ws_mux])
+pub struct MuxCall {
+    method: String,
+    headers: http::HeaderMap,
+    extensions: http::Extensions,
+    messages: MuxMessages,
+    sink: MuxSink,
+}
+
+impl MuxCall {
+    /// The name of the called method, as given in the proto file
+    pub fn method(&self) -> &str {
+        &self.method
+    }
+
+    /// The headers of the upgrade request, which apply to every call (ie. to determine its timeout)
+    pub fn headers(&self) -> &http::HeaderMap {
+        &self.headers
+    }
+}
+
+/// The request messages of a call, as received from the client
+struct MuxMessages(mpsc::Receiver<Bytes>);
+
+impl Stream for MuxMessages {
+    type Item = Result<RawMessage, tonic::Status>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.0
+            .poll_recv(cx)
+            .map(|message| message.map(|message| Ok(RawMessage::new(message))))
+    }
+}
+
+// *** MuxSink ***
+
+/// Sends the response frames of a call, tagged with its id
+pub struct MuxSink {
+    id: u64,
+    frames: mpsc::Sender<Message>,
+}
+
+impl MuxSink {
+    fn new(id: u64, frames: mpsc::Sender<Message>) -> Self {
+        Self { id, frames }
+    }
+
+    async fn send_frame<T: Serialize>(
+        &self,
+        frame: MuxResponseFrame<T>,
+    ) -> Result<(), tonic::Status> {
+        let frame = serde_json::to_string(&frame)
+            .map_err(|err| tonic::Status::internal(err.to_string()))?;
+        self.frames
+            .send(Message::Text(frame.into()))
+            .await
+            .map_err(|_| tonic::Status::cancelled("WebSocket closed"))
+    }
+
+    async fn send_message<T: Serialize>(&self, message: T) -> Result<(), tonic::Status> {
+        self.send_frame(MuxResponseFrame {
+            id: self.id,
+            message: Some(message),
+            status: None,
+        })
+        .await
+    }
+
+    async fn finish(self, status: tonic::Status) {
+        let frame = MuxResponseFrame::<()> {
+            id: self.id,
+            message: None,
+            status: Some(status_json(&status)),
+        };
+        // Nobody is left to tell if the connection is closed
+        let _ = self.send_frame(frame).await;
+    }
+}
+
+/// A frame sent by the server, holding either a response message or the final status of a call
+#[derive(Serialize)]
+struct MuxResponseFrame<T> {
+    id: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<T>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    status: Option<serde_json::Value>,
+}
+
+// *** Client functions ***
+
+/// Converts the request messages of a multiplexed call into a Tonic streaming request, returning it along with the
+/// sink for the response
+pub fn make_mux_stream_request<T: DeserializeOwned + Send + 'static>(
+    call: MuxCall,
+) -> (tonic::Request<tonic::Streaming<T>>, MuxSink) {
+    let MuxCall {
+        headers,
+        mut extensions,
+        messages,
+        sink,
+        ..
+    } = call;
+    let limits = StreamLimits::from_extensions(&extensions);
+    let metadata = crate::headers::request_metadata(headers, &mut extensions);
+    let streaming = new_streaming(JsonDecoder::new(), messages, limits);
+    (
+        tonic::Request::from_parts(metadata, extensions, streaming),
+        sink,
+    )
+}
+
+/// Converts the first request message of a multiplexed call into a Tonic request, returning it along with the sink for
+/// the response. If the client sends no message or an invalid one, the call is ended with an error status and `None` is
+/// returned.
+pub async fn make_mux_request<T: DeserializeOwned>(
+    call: MuxCall,
+) -> Option<(tonic::Request<T>, MuxSink)> {
+    let MuxCall {
+        headers,
+        mut extensions,
+        mut messages,
+        sink,
+        ..
+    } = call;
+
+    let result = match messages.0.recv().await {
+        Some(message) => serde_json::from_slice(&message)
+            .map_err(|err| tonic::Status::invalid_argument(err.to_string())),
+        None => Err(tonic::Status::aborted("No request received")),
+    };
+    match result {
+        Ok(message) => {
+            let metadata = crate::headers::request_metadata(headers, &mut extensions);
+            Some((
+                tonic::Request::from_parts(metadata, extensions, message),
+                sink,
+            ))
+        }
+        Err(status) => {
+            sink.finish(status).await;
+            None
+        }
+    }
+}
+
+/// Ends a multiplexed call with the given status without calling a method (for example, when the method is unknown)
+pub async fn close_mux_call(call: MuxCall, status: tonic::Status) {
+    call.sink.finish(status).await;
+}
+
+// *** Server functions ***
+
+/// Sends a Tonic response as the response of a multiplexed call, followed by the final status
+pub async fn process_mux_response<T: Serialize>(
+    response: Result<tonic::Response<T>, tonic::Status>,
+    sink: MuxSink,
+) {
+    let status = match response {
+        Ok(response) => match sink.send_message(response.into_inner()).await {
+            Ok(()) => tonic::Status::ok(""),
+            Err(status) => status,
+        },
+        Err(status) => status,
+    };
+    sink.finish(status).await;
+}
+
+/// Sends the messages of a Tonic stream response as the responses of a multiplexed call, followed by the final status
+pub async fn process_mux_stream_response<S, T>(
+    response: Result<tonic::Response<S>, tonic::Status>,
+    sink: MuxSink,
+) where
+    S: Stream<Item = Result<T, tonic::Status>> + Send + 'static,
+    T: Serialize,
+{
+    let status = match response {
+        Ok(response) => {
+            let stream = response.into_inner();
+            futures_util::pin_mut!(stream);
+            loop {
+                match stream.next().await {
+                    Some(Ok(message)) => {
+                        if let Err(status) = sink.send_message(message).await {
+                            break status;
+                        }
+                    }
+                    Some(Err(status)) => break status,
+                    None => break tonic::Status::ok(""),
+                }
+            }
+        }
+        Err(status) => status,
+    };
+    sink.finish(status).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{Value, json};
+
+    use super::*;
+    use crate::streaming::fake_socket::{FakeClient, fake_socket};
+
+    // Runs a multiplexed connection over a fake socket, returning its client end
+    fn start_mux(extensions: http::Extensions) -> FakeClient {
+        let (client, stream, sink) = fake_socket(WsProtocol::default());
+        let dispatcher = |call: MuxCall| async move {
+            match call.method() {
+                // Echoes each message back
+                "Echo" => {
+                    let (request, sink) = make_mux_stream_request::<Value>(call);
+                    let stream = request.into_inner();
+                    process_mux_stream_response(Ok(tonic::Response::new(stream)), sink).await;
+                }
+                // Never responds
+                "Wait" => {
+                    let (_request, _sink) = make_mux_stream_request::<Value>(call);
+                    std::future::pending::<()>().await;
+                }
+                "Unary" => {
+                    if let Some((request, sink)) = make_mux_request::<Value>(call).await {
+                        process_mux_response(Ok(tonic::Response::new(request.into_inner())), sink)
+                            .await;
+                    }
+                }
+                _ => close_mux_call(call, tonic::Status::unimplemented("Unknown method")).await,
+            }
+        };
+        tokio::spawn(run_mux(
+            stream,
+            sink,
+            http::HeaderMap::new(),
+            extensions,
+            dispatcher,
+        ));
+        client
+    }
+
+    #[tokio::test]
+    async fn test_mux_calls() {
+        let mut client = start_mux(http::Extensions::new());
+
+        // Calls run concurrently: the waiting call doesn't hold up the echo call
+        client.send_json(json!({ "id": 1, "method": "Wait" }));
+        client.send_json(json!({ "id": 2, "method": "Echo", "message": { "a": 1 } }));
+        assert_eq!(
+            client.recv_json().await,
+            Some(json!({ "id": 2, "message": { "a": 1 } }))
+        );
+        client.send_json(json!({ "id": 2, "message": { "b": 2 }, "end": true }));
+        assert_eq!(
+            client.recv_json().await,
+            Some(json!({ "id": 2, "message": { "b": 2 } }))
+        );
+        let frame = client.recv_json().await.unwrap();
+        assert_eq!(frame["id"], 2);
+        assert_eq!(frame["status"]["code"], 0);
+
+        // Cancelling a call ends it with a cancelled status
+        client.send_json(json!({ "id": 1, "cancel": true }));
+        let frame = client.recv_json().await.unwrap();
+        assert_eq!(frame["id"], 1);
+        assert_eq!(frame["status"]["code"], tonic::Code::Cancelled as i32);
+
+        client.send_json(json!({ "id": 3, "method": "Unary", "message": "hi" }));
+        assert_eq!(
+            client.recv_json().await,
+            Some(json!({ "id": 3, "message": "hi" }))
+        );
+        assert_eq!(client.recv_json().await.unwrap()["status"]["code"], 0);
+
+        client.send_json(json!({ "id": 4, "method": "Nope" }));
+        let frame = client.recv_json().await.unwrap();
+        assert_eq!(frame["id"], 4);
+        assert_eq!(frame["status"]["code"], tonic::Code::Unimplemented as i32);
+    }
+
+    #[tokio::test]
+    async fn test_mux_limits() {
+        let mut extensions = http::Extensions::new();
+        extensions.insert(WsLimits::new().max_mux_calls(1));
+        let mut client = start_mux(extensions);
+
+        client.send_json(json!({ "id": 1, "method": "Wait" }));
+        client.send_json(json!({ "id": 2, "method": "Wait" }));
+        let frame = client.recv_json().await.unwrap();
+        assert_eq!(frame["id"], 2);
+        assert_eq!(
+            frame["status"]["code"],
+            tonic::Code::ResourceExhausted as i32
+        );
+
+        // The waiting call never takes its messages, so they overflow the buffer
+        for _ in 0..=FRAME_BUFFER {
+            client.send_json(json!({ "id": 1, "message": {} }));
+        }
+        let frame = client.recv_json().await.unwrap();
+        assert_eq!(frame["id"], 1);
+        assert_eq!(
+            frame["status"]["code"],
+            tonic::Code::ResourceExhausted as i32
+        );
+
+        // The ended call no longer counts towards the limit
+        client.send_json(json!({ "id": 3, "method": "Unary", "message": "hi" }));
+        assert_eq!(
+            client.recv_json().await,
+            Some(json!({ "id": 3, "message": "hi" }))
+        );
+    }
+}
@@ -0,0 +1,266 @@
+use std::{
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+
+use axum::body::BodyDataStream;
+use bytes::{Buf as _, BufMut as _, Bytes, BytesMut};
+use futures_core::Stream;
+use serde::{Serialize, de::DeserializeOwned};
+use tonic::codec::{DecodeBuf, Decoder};
+
+use crate::{
+    ResponseOptions, StreamFormat,
+    streaming::{
+        MapDecoder, RawMessage, StreamLimits,
+        http::{StreamEncoder, make_encoded_stream_response},
+        new_streaming,
+    },
+};
+
+// Message length as a 4-byte big endian integer
+const LENGTH_PREFIX_LEN: usize = 4;
+
+/// Converts a length-prefixed protobuf request body into a Tonic streaming request
+pub fn make_proto_stream_request<T: prost::Message + Default + Send + 'static>(
+    headers: http::HeaderMap,
+    mut extensions: http::Extensions,
+    body: axum::body::Body,
+) -> tonic::Request<tonic::Streaming<T>> {
+    let limits = StreamLimits::from_extensions(&extensions);
+    let metadata = crate::headers::request_metadata(headers, &mut extensions);
+    let messages = LengthPrefixed::new(body.into_data_stream(), limits);
+    let streaming = new_streaming(ProtoDecoder::new(), messages, limits);
+    tonic::Request::from_parts(metadata, extensions, streaming)
+}
+
+/// Converts a streaming request body into a Tonic streaming request, using the format given by the `Content-Type`
+/// header: length-prefixed protobuf for [crate::PROTOBUF_STREAM_CONTENT_TYPE], otherwise JSON (see
+/// [crate::make_stream_request]).
+pub fn make_negotiated_stream_request<T>(
+    headers: http::HeaderMap,
+    extensions: http::Extensions,
+    body: axum::body::Body,
+) -> tonic::Request<tonic::Streaming<T>>
+where
+    T: prost::Message + DeserializeOwned + Default + Send + 'static,
+{
+    match StreamFormat::from_content_type(&headers, false) {
+        StreamFormat::JsonLines | StreamFormat::JsonArray => {
+            super::make_stream_request(headers, extensions, body)
+        }
+        StreamFormat::Protobuf => make_proto_stream_request(headers, extensions, body),
+    }
+}
+
+/// Converts a streaming request body into a Tonic streaming request like [make_negotiated_stream_request], passing each
+/// message through a transform. JSON messages are decoded as `T` and protobuf messages as `P`, so each format has its
+/// own transform (ie. to merge values from the path or query string into each message). When `json_array` is set, a
+/// body with a `Content-Type` of `application/json` is read as a single JSON array (see
+/// [crate::make_stream_request_with]).
+pub fn make_negotiated_stream_request_with<T, P, U, F, G>(
+    headers: http::HeaderMap,
+    mut extensions: http::Extensions,
+    body: axum::body::Body,
+    json_array: bool,
+    json_transform: F,
+    proto_transform: G,
+) -> tonic::Request<tonic::Streaming<U>>
+where
+    T: DeserializeOwned + Send + 'static,
+    P: prost::Message + Default + Send + 'static,
+    U: Send + 'static,
+    F: FnMut(T) -> U + Send + 'static,
+    G: FnMut(P) -> U + Send + 'static,
+{
+    match StreamFormat::from_content_type(&headers, json_array) {
+        StreamFormat::JsonLines | StreamFormat::JsonArray => {
+            super::make_stream_request_with(headers, extensions, body, json_array, json_transform)
+        }
+        StreamFormat::Protobuf => {
+            let limits = StreamLimits::from_extensions(&extensions);
+            let metadata = crate::headers::request_metadata(headers, &mut extensions);
+            let messages = LengthPrefixed::new(body.into_data_stream(), limits);
+            let decoder = MapDecoder::new(ProtoDecoder::new(), proto_transform);
+            let streaming = new_streaming(decoder, messages, limits);
+            tonic::Request::from_parts(metadata, extensions, streaming)
+        }
+    }
+}
+
+// *** LengthPrefixed ***
+
+/// Splits a length-prefixed body into messages. Messages are sliced from the body chunks, so only messages spanning
+/// multiple chunks need to be buffered.
+struct LengthPrefixed {
+    body: BodyDataStream,
+    limits: StreamLimits,
+    chunk: Bytes,
+    partial: BytesMut,
+    done: bool,
+}
+
+impl LengthPrefixed {
+    fn new(body: BodyDataStream, limits: StreamLimits) -> Self {
+        Self {
+            body,
+            limits,
+            chunk: Bytes::new(),
+            partial: BytesMut::new(),
+            done: false,
+        }
+    }
+
+    /// The length of the frame (prefix + message) starting the buffer, if the prefix is complete
+    fn frame_len(buf: &[u8]) -> Option<usize> {
+        let prefix = buf.get(..LENGTH_PREFIX_LEN)?;
+        let len = u32::from_be_bytes(prefix.try_into().ok()?);
+        Some(LENGTH_PREFIX_LEN + len as usize)
+    }
+
+    fn frame_message(mut frame: Bytes) -> RawMessage {
+        frame.advance(LENGTH_PREFIX_LEN);
+        RawMessage::new(frame)
+    }
+}
+
+impl Stream for LengthPrefixed {
+    type Item = Result<RawMessage, tonic::Status>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            // Reject messages that are too large before buffering them
+            let frame_len = match this.partial.is_empty() {
+                true => Self::frame_len(&this.chunk),
+                false => Self::frame_len(&this.partial),
+            };
+            if frame_len.is_some_and(|len| len - LENGTH_PREFIX_LEN > this.limits.max_message_size) {
+                this.done = true;
+                this.chunk.clear();
+                this.partial.clear();
+                return Poll::Ready(Some(Err(this.limits.message_too_large())));
+            }
+
+            if !this.chunk.is_empty() {
+                // Complete frame within the chunk
+                if this.partial.is_empty()
+                    && let Some(len) = Self::frame_len(&this.chunk)
+                    && this.chunk.len() >= len
+                {
+                    let frame = this.chunk.split_to(len);
+                    return Poll::Ready(Some(Ok(Self::frame_message(frame))));
+                }
+
+                // Incomplete frame - buffer only what is needed to complete the prefix and then the message
+                let needed = match Self::frame_len(&this.partial) {
+                    Some(len) => len - this.partial.len(),
+                    None => LENGTH_PREFIX_LEN - this.partial.len(),
+                };
+                let available = this.chunk.split_to(needed.min(this.chunk.len()));
+                this.partial.extend_from_slice(&available);
+
+                if Self::frame_len(&this.partial) == Some(this.partial.len()) {
+                    let frame = this.partial.split().freeze();
+                    return Poll::Ready(Some(Ok(Self::frame_message(frame))));
+                }
+                continue;
+            }
+
+            if this.done {
+                if this.partial.is_empty() {
+                    return Poll::Ready(None);
+                }
+                this.partial.clear();
+                return Poll::Ready(Some(Err(tonic::Status::invalid_argument(
+                    "Protobuf stream ended with an incomplete message",
+                ))));
+            }
+
+            match ready!(Pin::new(&mut this.body).poll_next(cx)) {
+                Some(Ok(chunk)) => this.chunk = chunk,
+                Some(Err(err)) => {
+                    this.done = true;
+                    this.partial.clear();
+                    return Poll::Ready(Some(Err(tonic::Status::internal(err.to_string()))));
+                }
+                None => this.done = true,
+            }
+        }
+    }
+}
+
+// *** ProtoDecoder ***
+
+/// Decodes the protobuf message held by each gRPC frame
+struct ProtoDecoder<T>(PhantomData<fn() -> T>);
+
+impl<T> ProtoDecoder<T> {
+    fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<T: prost::Message + Default> Decoder for ProtoDecoder<T> {
+    type Item = T;
+    type Error = tonic::Status;
+
+    fn decode(&mut self, src: &mut DecodeBuf<'_>) -> Result<Option<Self::Item>, Self::Error> {
+        T::decode(src)
+            .map(Some)
+            .map_err(|err| tonic::Status::invalid_argument(err.to_string()))
+    }
+}
+
+/// Converts a Tonic stream response into a length-prefixed protobuf HTTP response. Initial metadata is sent as HTTP
+/// headers and the final status along with any trailing metadata is sent as HTTP trailers.
+pub fn make_proto_stream_response<S, T>(
+    response: Result<tonic::Response<S>, tonic::Status>,
+    options: ResponseOptions,
+) -> http::Response<axum::body::Body>
+where
+    S: Stream<Item = Result<T, tonic::Status>> + Send + 'static,
+    T: prost::Message,
+{
+    make_encoded_stream_response(response, options, StreamFormat::Protobuf, ProtoEncoder)
+}
+
+/// Converts a Tonic stream response into an HTTP response using the stream format in the options (see
+/// [ResponseOptions::stream_format]), including length-prefixed protobuf
+pub fn make_negotiated_stream_response<S, T>(
+    response: Result<tonic::Response<S>, tonic::Status>,
+    options: ResponseOptions,
+) -> http::Response<axum::body::Body>
+where
+    S: Stream<Item = Result<T, tonic::Status>> + Send + 'static,
+    T: prost::Message + Serialize + Send,
+{
+    match options.stream_format {
+        StreamFormat::JsonLines | StreamFormat::JsonArray => {
+            super::make_stream_response_with(response, options)
+        }
+        StreamFormat::Protobuf => make_proto_stream_response(response, options),
+    }
+}
+
+// *** ProtoEncoder ***
+
+/// Encodes each item as a length-prefixed protobuf message
+struct ProtoEncoder;
+
+impl<T: prost::Message> StreamEncoder<T> for ProtoEncoder {
+    fn encode_item(&mut self, item: T) -> Result<Bytes, tonic::Status> {
+        let len = item.encoded_len();
+        let prefix = u32::try_from(len).map_err(|_| {
+            tonic::Status::resource_exhausted(format!("Message too large: {len} bytes"))
+        })?;
+
+        let mut buf = BytesMut::with_capacity(LENGTH_PREFIX_LEN + len);
+        buf.put_u32(prefix);
+        item.encode(&mut buf)
+            .map_err(|err| tonic::Status::internal(err.to_string()))?;
+        Ok(buf.freeze())
+    }
+}
@@ -0,0 +1,238 @@
+use std::{
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+
+use axum::body::BodyDataStream;
+use bytes::{Buf as _, Bytes};
+use futures_core::Stream;
+use tonic::codec::{DecodeBuf, Decoder};
+
+use crate::{
+    ResponseOptions,
+    streaming::{
+        MapDecoder, RawMessage, StreamLimits,
+        http::{StreamEncoder, make_body_response},
+        new_streaming,
+    },
+};
+
+/// Content type of raw byte streams, unless the method sets another one
+pub const OCTET_STREAM_CONTENT_TYPE: &str = "application/octet-stream";
+
+// Initial metadata keys that are sent verbatim as the headers describing a raw body. The content length isn't one of
+// them, as the body is streamed and a length that doesn't match the messages would corrupt the response.
+const RAW_BODY_METADATA: [http::HeaderName; 2] = [
+    http::header::CONTENT_TYPE,
+    http::header::CONTENT_DISPOSITION,
+];
+
+/// Converts a raw request body into a Tonic streaming request. Each body chunk becomes a message via the transform
+/// (which typically sets the bytes field of the request message). Chunks larger than the maximum message size (see
+/// [StreamLimits]) are split, so the size limit doesn't apply to the body as a whole, but the message count limit
+/// does.
+pub fn make_raw_stream_request<T, F>(
+    headers: http::HeaderMap,
+    mut extensions: http::Extensions,
+    body: axum::body::Body,
+    transform: F,
+) -> tonic::Request<tonic::Streaming<T>>
+where
+    T: Send + 'static,
+    F: FnMut(Bytes) -> T + Send + 'static,
+{
+    let limits = StreamLimits::from_extensions(&extensions);
+    let metadata = crate::headers::request_metadata(headers, &mut extensions);
+
+    let messages = RawChunks {
+        body: body.into_data_stream(),
+        chunk: Bytes::new(),
+        max_size: limits.max_message_size.max(1),
+    };
+    let decoder = MapDecoder::new(BytesDecoder, transform);
+    let streaming = new_streaming(decoder, messages, limits);
+    tonic::Request::from_parts(metadata, extensions, streaming)
+}
+
+/// Converts a raw request body into a Tonic streaming request of `google.api.HttpBody` messages. Each body chunk
+/// becomes a message via the transform, which is given the content type of the request along with the chunk. Limits
+/// apply as for [make_raw_stream_request].
+pub fn make_http_body_stream_request<T, F>(
+    headers: http::HeaderMap,
+    extensions: http::Extensions,
+    body: axum::body::Body,
+    mut transform: F,
+) -> tonic::Request<tonic::Streaming<T>>
+where
+    T: Send + 'static,
+    F: FnMut(String, Bytes) -> T + Send + 'static,
+{
+    let content_type = crate::request_content_type(&headers);
+    make_raw_stream_request(headers, extensions, body, move |data| {
+        transform(content_type.clone(), data)
+    })
+}
+
+// *** RawChunks ***
+
+/// Turns each body chunk into a message, splitting chunks larger than the maximum size (without copying)
+struct RawChunks {
+    body: BodyDataStream,
+    chunk: Bytes,
+    max_size: usize,
+}
+
+impl Stream for RawChunks {
+    type Item = Result<RawMessage, tonic::Status>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        while this.chunk.is_empty() {
+            match ready!(Pin::new(&mut this.body).poll_next(cx)) {
+                Some(Ok(chunk)) => this.chunk = chunk,
+                Some(Err(err)) => {
+                    return Poll::Ready(Some(Err(tonic::Status::internal(err.to_string()))));
+                }
+                None => return Poll::Ready(None),
+            }
+        }
+
+        let len = this.max_size.min(this.chunk.len());
+        Poll::Ready(Some(Ok(RawMessage::new(this.chunk.split_to(len)))))
+    }
+}
+
+// *** BytesDecoder ***
+
+/// Passes the bytes of each gRPC frame through as is
+struct BytesDecoder;
+
+impl Decoder for BytesDecoder {
+    type Item = Bytes;
+    type Error = tonic::Status;
+
+    fn decode(&mut self, src: &mut DecodeBuf<'_>) -> Result<Option<Self::Item>, Self::Error> {
+        Ok(Some(src.copy_to_bytes(src.remaining())))
+    }
+}
+
+/// Converts a Tonic stream response into a raw HTTP response body, writing the bytes taken from each message by the
+/// given function as is. The content type defaults to the given one, but the service can override it, and set the
+/// `Content-Disposition` header, via initial metadata with the same names. Other initial metadata is sent as HTTP
+/// headers, and the final status along with any trailing metadata is sent as HTTP trailers. The body is always sent
+/// without a `Content-Length`, so trailers can follow it.
+pub fn make_raw_stream_response<S, T, F>(
+    response: Result<tonic::Response<S>, tonic::Status>,
+    options: ResponseOptions,
+    content_type: &'static str,
+    chunk: F,
+) -> http::Response<axum::body::Body>
+where
+    S: Stream<Item = Result<T, tonic::Status>> + Send + 'static,
+    F: FnMut(T) -> Bytes + Send + Unpin + 'static,
+{
+    let (mut meta, stream, ext) = match response {
+        Ok(response) => response.into_parts(),
+        Err(status) => return crate::make_err_response(status, &options),
+    };
+
+    let mut headers = http::HeaderMap::new();
+    headers.insert(
+        http::header::CONTENT_TYPE,
+        http::HeaderValue::from_static(content_type),
+    );
+    for name in RAW_BODY_METADATA {
+        if let Some(value) = meta.remove(name.as_str())
+            && let Ok(value) = http::HeaderValue::from_bytes(value.as_encoded_bytes())
+        {
+            headers.insert(name, value);
+        }
+    }
+    options.header_policy.apply_headers(&meta, &mut headers);
+    make_body_response(headers, ext, stream, options, RawEncoder(chunk))
+}
+
+/// Converts a Tonic stream response of `google.api.HttpBody` messages into a raw HTTP response body. The content type
+/// is taken from the first message (which is awaited before the response is sent), and the data of each message is
+/// written as is. An empty content type falls back to `application/octet-stream`. Metadata, the final status and
+/// errors are handled as for [make_raw_stream_response], except that an error in place of the first message is sent
+/// as an error response.
+pub async fn make_http_body_stream_response<S, T, F>(
+    response: Result<tonic::Response<S>, tonic::Status>,
+    options: ResponseOptions,
+    mut parts: F,
+) -> http::Response<axum::body::Body>
+where
+    S: Stream<Item = Result<T, tonic::Status>> + Send + 'static,
+    F: FnMut(T) -> (String, Bytes) + Send + Unpin + 'static,
+{
+    let (meta, stream, ext) = match response {
+        Ok(response) => response.into_parts(),
+        Err(status) => return crate::make_err_response(status, &options),
+    };
+
+    let mut stream = Box::pin(stream);
+    let (content_type, first) = match std::future::poll_fn(|cx| stream.as_mut().poll_next(cx)).await
+    {
+        Some(Ok(message)) => {
+            let (content_type, data) = parts(message);
+            (content_type, Some(data))
+        }
+        Some(Err(status)) => return crate::make_err_response(status, &options),
+        None => (String::new(), None),
+    };
+
+    let mut headers = http::HeaderMap::new();
+    let content_type = http::HeaderValue::from_str(&content_type)
+        .ok()
+        .filter(|value| !value.is_empty())
+        .unwrap_or(http::HeaderValue::from_static(OCTET_STREAM_CONTENT_TYPE));
+    headers.insert(http::header::CONTENT_TYPE, content_type);
+    options.header_policy.apply_headers(&meta, &mut headers);
+
+    let chunks = HttpBodyChunks {
+        first,
+        stream,
+        parts,
+    };
+    make_body_response(headers, ext, chunks, options, RawEncoder(|data| data))
+}
+
+// *** HttpBodyChunks ***
+
+/// Yields the data of each message, starting with the data of the (already received) first message
+struct HttpBodyChunks<S, F> {
+    first: Option<Bytes>,
+    stream: Pin<Box<S>>,
+    parts: F,
+}
+
+impl<S, T, F> Stream for HttpBodyChunks<S, F>
+where
+    S: Stream<Item = Result<T, tonic::Status>>,
+    F: FnMut(T) -> (String, Bytes) + Unpin,
+{
+    type Item = Result<Bytes, tonic::Status>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        if let Some(first) = this.first.take() {
+            return Poll::Ready(Some(Ok(first)));
+        }
+        let item = ready!(this.stream.as_mut().poll_next(cx));
+        Poll::Ready(item.map(|item| item.map(|message| (this.parts)(message).1)))
+    }
+}
+
+// *** RawEncoder ***
+
+/// Writes the bytes taken from each item as is
+struct RawEncoder<F>(F);
+
+impl<T, F: FnMut(T) -> Bytes> StreamEncoder<T> for RawEncoder<F> {
+    fn encode_item(&mut self, item: T) -> Result<Bytes, tonic::Status> {
+        Ok((self.0)(item))
+    }
+}
@@ -0,0 +1,115 @@
+use std::{
+    convert::Infallible,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use axum::response::{
+    IntoResponse as _,
+    sse::{Event, Sse},
+};
+use futures_core::Stream;
+use serde::Serialize;
+
+use crate::{
+    ResponseOptions,
+    streaming::{http::HeartbeatTimer, status::status_json},
+};
+
+const MESSAGE_EVENT: &str = "message";
+const ERROR_EVENT: &str = "error";
+const END_EVENT: &str = "end";
+
+/// Converts a Tonic stream response into a Server-Sent Events HTTP response. Initial metadata is sent as HTTP headers.
+/// Each message is sent as a `message` event, a mid-stream error as a final `error` event holding the status, and
+/// normal completion as an `end` event (holding the `OK` status). If a [crate::Heartbeat] is set in the options, a
+/// comment is sent while the stream is idle.
+pub fn make_sse_response<S, T>(
+    response: Result<tonic::Response<S>, tonic::Status>,
+    options: ResponseOptions,
+) -> http::Response<axum::body::Body>
+where
+    S: Stream<Item = Result<T, tonic::Status>> + Send + 'static,
+    T: Serialize + Send,
+{
+    match response {
+        Ok(response) => {
+            let (meta, stream, ext) = response.into_parts();
+            let mut headers = http::HeaderMap::new();
+            options.header_policy.apply_headers(&meta, &mut headers);
+
+            let heartbeat = options
+                .heartbeat
+                .map(|heartbeat| HeartbeatTimer::new(heartbeat.interval));
+            (headers, ext, Sse::new(SseEvents::new(stream, heartbeat))).into_response()
+        }
+        Err(status) => crate::make_err_response(status, &options),
+    }
+}
+
+fn status_event(event: &'static str, status: &tonic::Status) -> Event {
+    Event::default()
+        .event(event)
+        .data(status_json(status).to_string())
+}
+
+// *** SseEvents ***
+
+/// Maps a Tonic response stream into SSE events, ending after the first error
+struct SseEvents<S> {
+    stream: Pin<Box<S>>,
+    heartbeat: Option<HeartbeatTimer>,
+    done: bool,
+}
+
+impl<S> SseEvents<S> {
+    fn new(stream: S, heartbeat: Option<HeartbeatTimer>) -> Self {
+        Self {
+            stream: Box::pin(stream),
+            heartbeat,
+            done: false,
+        }
+    }
+}
+
+impl<S, T> Stream for SseEvents<S>
+where
+    S: Stream<Item = Result<T, tonic::Status>> + Send + 'static,
+    T: Serialize + Send,
+{
+    type Item = Result<Event, Infallible>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.done {
+            return Poll::Ready(None);
+        }
+
+        let next = match self.stream.as_mut().poll_next(cx) {
+            Poll::Ready(next) => next,
+            // Stream is idle - send a heartbeat comment if one is due
+            Poll::Pending => {
+                return match &mut self.heartbeat {
+                    Some(timer) => timer
+                        .poll_due(cx)
+                        .map(|()| Some(Ok(Event::default().comment("")))),
+                    None => Poll::Pending,
+                };
+            }
+        };
+        if let Some(timer) = &mut self.heartbeat {
+            timer.reset();
+        }
+
+        let event = match next {
+            Some(Ok(item)) => match Event::default().event(MESSAGE_EVENT).json_data(&item) {
+                Ok(event) => return Poll::Ready(Some(Ok(event))),
+                Err(err) => status_event(ERROR_EVENT, &tonic::Status::internal(err.to_string())),
+            },
+            Some(Err(status)) => status_event(ERROR_EVENT, &status),
+            None => status_event(END_EVENT, &tonic::Status::ok("")),
+        };
+
+        self.done = true;
+        Poll::Ready(Some(Ok(event)))
+    }
+}
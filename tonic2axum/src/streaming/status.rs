@@ -0,0 +1,106 @@
+use base64::Engine as _;
+use prost::Message as _;
+
+/// `google.rpc.Status` from google/rpc/status.proto
+#[derive(Clone, PartialEq, prost::Message)]
+struct RpcStatus {
+    #[prost(int32, tag = "1")]
+    code: i32,
+    #[prost(string, tag = "2")]
+    message: String,
+    #[prost(message, repeated, tag = "3")]
+    details: Vec<ProtoAny>,
+}
+
+/// `google.protobuf.Any` from google/protobuf/any.proto
+#[derive(Clone, PartialEq, prost::Message)]
+struct ProtoAny {
+    #[prost(string, tag = "1")]
+    type_url: String,
+    #[prost(bytes = "vec", tag = "2")]
+    value: Vec<u8>,
+}
+
+/// The JSON representation of a gRPC status sent by the streaming transports (in the style of grpc-gateway). The
+/// details are decoded from the `google.rpc.Status` held by the status, with each detail holding its `@type` and the
+/// message as standard base64 `value`.
+pub(crate) fn status_json(status: &tonic::Status) -> serde_json::Value {
+    let details: Vec<_> = status_details(status)
+        .into_iter()
+        .map(|any| {
+            serde_json::json!({
+                "@type": any.type_url,
+                "value": base64::engine::general_purpose::STANDARD.encode(any.value),
+            })
+        })
+        .collect();
+
+    serde_json::json!({
+        "code": status.code() as i32,
+        "message": status.message(),
+        "details": details,
+    })
+}
+
+/// Encodes a gRPC status as a `google.rpc.Status` message, with the details taken from the `google.rpc.Status` held by
+/// the status
+#[cfg(feature = "ws-streaming")]
+pub(crate) fn status_proto(status: &tonic::Status) -> bytes::Bytes {
+    RpcStatus {
+        code: status.code() as i32,
+        message: status.message().to_owned(),
+        details: status_details(status),
+    }
+    .encode_to_vec()
+    .into()
+}
+
+/// Decodes the details held by the status, ignoring them if they are not a valid `google.rpc.Status`
+fn status_details(status: &tonic::Status) -> Vec<ProtoAny> {
+    RpcStatus::decode(status.details())
+        .map(|status| status.details)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_json() {
+        let status = tonic::Status::not_found("missing");
+        assert_eq!(
+            status_json(&status),
+            serde_json::json!({"code": 5, "message": "missing", "details": []})
+        );
+
+        // google.rpc.Status { code: 5, details: [Any { type_url: "t", value: "ab" }] }
+        let details = [
+            0x08, 0x05, 0x1A, 0x07, 0x0A, 0x01, b't', 0x12, 0x02, b'a', b'b',
+        ];
+        let status =
+            tonic::Status::with_details(tonic::Code::NotFound, "missing", details.to_vec().into());
+        assert_eq!(
+            status_json(&status)["details"],
+            serde_json::json!([{"@type": "t", "value": "YWI="}])
+        );
+    }
+
+    #[cfg(feature = "ws-streaming")]
+    #[test]
+    fn test_status_proto() {
+        // google.rpc.Status { code: 5, message: "missing", details: [Any { type_url: "t", value: "ab" }] }
+        let details = [
+            0x08, 0x05, 0x1A, 0x07, 0x0A, 0x01, b't', 0x12, 0x02, b'a', b'b',
+        ];
+        let status =
+            tonic::Status::with_details(tonic::Code::NotFound, "missing", details.to_vec().into());
+        let mut expected = vec![0x08, 0x05, 0x12, 0x07];
+        expected.extend_from_slice(b"missing");
+        expected.extend_from_slice(&details[2..]);
+        assert_eq!(status_proto(&status), expected);
+
+        // Default values are not encoded
+        assert!(status_proto(&tonic::Status::ok("")).is_empty());
+    }
+}
@@ -2,54 +2,435 @@ use axum::{
     extract::{
         WebSocketUpgrade,
         ws::{
-            CloseFrame, Message, WebSocket,
+            CloseFrame, Message,
             close_code::{AGAIN, AWAY, ERROR, INVALID, NORMAL, POLICY, SIZE, UNSUPPORTED},
         },
     },
     response::Response,
 };
-use bytes::BytesMut;
-use futures_core::Stream;
-use futures_util::{
-    SinkExt as _, StreamExt as _,
-    stream::{SplitSink, SplitStream},
+use std::{
+    marker::PhantomData,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
 };
+
+use bytes::{Buf as _, BytesMut};
+use futures_core::Stream;
+use futures_util::{Sink, SinkExt as _, StreamExt as _};
 use serde::{Serialize, de::DeserializeOwned};
-use tonic::metadata::MetadataMap;
+use tokio::{
+    sync::{Mutex, mpsc, oneshot},
+    time::Instant,
+};
+use tonic::{
+    codec::{DecodeBuf, Decoder},
+    metadata::MetadataMap,
+};
+
+use crate::{
+    ResponseHeaderPolicy,
+    streaming::{
+        MapDecoder, RawMessage, StreamLimits, WsLimits,
+        bind::bind_metadata,
+        keepalive::{WsKeepalive, run_ws},
+        new_streaming,
+        status::{status_json, status_proto},
+    },
+};
+
+// Frame tags that tell the decoder how the message was encoded
+const JSON_TAG: u8 = 0;
+const PROTOBUF_TAG: u8 = 1;
 
-use crate::streaming::FakeGrpcFrameStreamingHelper;
+/// WebSocket subprotocol for messages encoded as JSON (sent in text frames)
+pub const WS_JSON_PROTOCOL: &str = "tonic2axum.json";
+/// WebSocket subprotocol for messages encoded as protobuf (sent in binary frames)
+pub const WS_PROTOBUF_PROTOCOL: &str = "tonic2axum.proto";
+
+/// Text frame sent by the client to end its request messages while keeping the socket open for the response (a
+/// half-close). It isn't valid JSON, so it can't be mistaken for a message.
+pub const WS_END_OF_INPUT_TEXT: &str = "tonic2axum.end";
+/// Binary frame sent by the client to end its request messages while keeping the socket open for the response (a
+/// half-close). Field number 0 is invalid in protobuf, so it can't be mistaken for a message.
+pub const WS_END_OF_INPUT_BINARY: &[u8] = &[0];
 
 // *** Upgrade ***
 
+/// Upgrades the connection to a WebSocket and runs the callback with it. The encoding of the messages sent to the client
+/// is negotiated via the `Sec-WebSocket-Protocol` header: the first of the client's subprotocols that is supported (see
+/// [WS_JSON_PROTOCOL] and [WS_PROTOBUF_PROTOCOL]) is selected and echoed back. Without one, the given encoding is used.
+/// Messages from the client can use either encoding regardless. If a [WsKeepalive] or [WsLimits] are installed, they
+/// supervise the call, and [WsOptions] can be installed to change the protocol. If a [crate::WsMetadataBinding] is
+/// installed, values from the query string (taken from the `uri`) and subprotocols are bound to the request metadata.
 pub async fn upgrade_to_ws<C, Fut>(
     ws_upgrade: WebSocketUpgrade,
+    uri: http::Uri,
     headers: http::HeaderMap,
-    extensions: http::Extensions,
+    mut extensions: http::Extensions,
     protobuf: bool,
     callback: C,
 ) -> Response
 where
-    C: FnOnce(
-            http::HeaderMap,
-            http::Extensions,
-            SplitStream<WebSocket>,
-            SplitSink<WebSocket, Message>,
-            bool,
-        ) -> Fut
-        + Send
-        + 'static,
+    C: FnOnce(http::HeaderMap, http::Extensions, WsStream, WsSink, bool) -> Fut + Send + 'static,
     Fut: Future<Output = ()> + Send + 'static,
 {
+    let limits = WsLimits::from_extensions(&extensions);
+    let ws_upgrade = match limits {
+        Some(limits) => limits.apply(ws_upgrade),
+        None => ws_upgrade,
+    };
+    let (ws_upgrade, protobuf) = match negotiate_ws_protocol(&headers) {
+        Some(protocol) => (
+            ws_upgrade.protocols([protocol]),
+            protocol == WS_PROTOBUF_PROTOCOL,
+        ),
+        None => (ws_upgrade, protobuf),
+    };
+    bind_metadata(&uri, &headers, &mut extensions);
+
     ws_upgrade.on_upgrade(move |socket| async move {
-        let (sink, stream) = socket.split();
-        callback(headers, extensions, stream, sink, protobuf).await;
+        let keepalive = WsKeepalive::from_extensions(&extensions);
+        let protocol = WsProtocol::new(protobuf, &extensions);
+        run_ws(socket, keepalive, limits, protocol, |stream, sink| {
+            callback(headers, extensions, stream, sink, protobuf)
+        })
+        .await;
     })
 }
 
+/// Options for the protocol spoken on WebSockets upgraded by [upgrade_to_ws]. The options can be installed with an
+/// [axum::Extension] layer, on the router or on individual routes.
+///
+/// By default, each JSON message is sent as is. Once either option is enabled, every JSON frame is wrapped in an
+/// envelope named after its kind, so frames can't be mistaken for messages: `{"result": ...}` for a response message,
+/// `{"metadata": ...}` for a header frame and `{"status": ...}` for a status or trailer frame. Protobuf messages are
+/// sent in binary frames as is.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WsOptions {
+    pub(crate) status_frame: bool,
+    pub(crate) metadata_frames: bool,
+}
+
+impl WsOptions {
+    /// Create options for the default protocol
+    pub const fn new() -> Self {
+        Self {
+            status_frame: false,
+            metadata_frames: false,
+        }
+    }
+
+    /// Set whether to send the status of a failed call as a final message before closing the socket (default: false).
+    /// The close frame's reason is limited to 123 bytes and can't hold the status details, so the message holds the full
+    /// status: a `google.rpc.Status` in the negotiated encoding (JSON `{"status": {...}}` in the style of grpc-gateway,
+    /// with each detail's `@type` and base64 `value`). It is only sent when the socket is closed with an error code, so
+    /// a protobuf status is the binary frame right before the close frame.
+    pub const fn status_frame(mut self, enable: bool) -> Self {
+        self.status_frame = enable;
+        self
+    }
+
+    /// Set whether to send the metadata of each call in frames of their own, mirroring gRPC (default: false). A header
+    /// frame `{"metadata": {...}}` with the initial metadata is sent before the response messages, and a trailer frame
+    /// `{"status": {..., "metadata": {...}}}` with the final status (as for [Self::status_frame]) and its trailing
+    /// metadata is sent after them (on its own if the call fails before responding). Both are JSON text frames, even
+    /// when messages are protobuf, and the metadata is filtered by the [crate::ResponseHeaderPolicy] (without
+    /// prefixes), with repeated keys joined by commas. The trailer frame replaces the status frame.
+    pub const fn metadata_frames(mut self, enable: bool) -> Self {
+        self.metadata_frames = enable;
+        self
+    }
+
+    pub(crate) fn from_extensions(extensions: &http::Extensions) -> Self {
+        extensions.get::<Self>().copied().unwrap_or_default()
+    }
+}
+
+/// How the metadata and status of calls are sent on a socket, decided when it is upgraded
+#[derive(Clone, Debug, Default)]
+pub(crate) struct WsProtocol {
+    protobuf: bool,
+    status_frame: bool,
+    // The policy for the metadata, when sent in frames of its own
+    metadata_policy: Option<ResponseHeaderPolicy>,
+}
+
+impl WsProtocol {
+    fn new(protobuf: bool, extensions: &http::Extensions) -> Self {
+        let options = WsOptions::from_extensions(extensions);
+        Self {
+            protobuf,
+            status_frame: options.status_frame,
+            metadata_policy: options.metadata_frames.then(|| {
+                extensions
+                    .get::<ResponseHeaderPolicy>()
+                    .cloned()
+                    .unwrap_or_default()
+            }),
+        }
+    }
+
+    /// Whether frames other than messages can be sent, so JSON frames are wrapped in their envelope
+    fn envelope(&self) -> bool {
+        self.status_frame || self.metadata_policy.is_some()
+    }
+}
+
+/// The envelope of a JSON frame (see [WsOptions])
+#[derive(Serialize)]
+#[serde(rename_all = "lowercase")]
+enum WsFrame<'a, T> {
+    Result(&'a T),
+    Metadata(serde_json::Value),
+    Status(serde_json::Value),
+}
+
+impl WsFrame<'_, ()> {
+    fn into_message(self) -> Message {
+        // Metadata and statuses are already JSON values, so they can't fail to serialize
+        Message::Text(serde_json::to_string(&self).unwrap_or_default().into())
+    }
+}
+
+/// Returns the first subprotocol requested by the client that is supported, if any
+fn negotiate_ws_protocol(headers: &http::HeaderMap) -> Option<&'static str> {
+    headers
+        .get_all(http::header::SEC_WEBSOCKET_PROTOCOL)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .find_map(|protocol| match protocol.trim() {
+            WS_JSON_PROTOCOL => Some(WS_JSON_PROTOCOL),
+            WS_PROTOBUF_PROTOCOL => Some(WS_PROTOBUF_PROTOCOL),
+            _ => None,
+        })
+}
+
+// *** WsStream ***
+
+/// The messages received on an upgraded WebSocket
+pub struct WsStream {
+    stream: Pin<Box<dyn Stream<Item = Result<Message, axum::Error>> + Send>>,
+    // Hands the stream back once the client ends its requests, so the socket is still read for the close
+    rest: Option<oneshot::Sender<WsStream>>,
+}
+
+impl WsStream {
+    pub(crate) fn new(
+        stream: impl Stream<Item = Result<Message, axum::Error>> + Send + 'static,
+    ) -> Self {
+        Self {
+            stream: Box::pin(stream),
+            rest: None,
+        }
+    }
+
+    pub(crate) fn from_receiver(
+        mut receiver: mpsc::Receiver<Result<Message, axum::Error>>,
+    ) -> Self {
+        Self::new(futures_util::stream::poll_fn(move |cx| {
+            receiver.poll_recv(cx)
+        }))
+    }
+}
+
+impl Stream for WsStream {
+    type Item = Result<Message, axum::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.stream.as_mut().poll_next(cx)
+    }
+}
+
+/// Runs a call on a socket until it finishes, or until the client closes the socket after ending its requests (see
+/// [WS_END_OF_INPUT_TEXT]), in which case the call is cancelled. Until then, the request stream reads the socket.
+pub(crate) async fn run_until_closed<F, Fut>(stream: WsStream, sink: &WsSink, call: F)
+where
+    F: FnOnce(WsStream) -> Fut,
+    Fut: Future<Output = ()>,
+{
+    let (rest, handed_back) = oneshot::channel();
+    let stream = WsStream {
+        rest: Some(rest),
+        ..stream
+    };
+
+    tokio::select! {
+        () = call(stream) => {}
+        () = wait_for_close(handed_back) => {
+            // The close from the client is answered by the socket when the sink is closed
+            if let Err(err) = sink.close().await {
+                tracing::trace!("Close handshake completed: {}", err);
+            }
+        }
+    }
+}
+
+/// Reads the rest of the stream once it is handed back, returning when the client closes the socket
+async fn wait_for_close(handed_back: oneshot::Receiver<WsStream>) {
+    // Never handed back while the call reads the requests itself
+    let Ok(mut stream) = handed_back.await else {
+        return std::future::pending().await;
+    };
+    while let Some(message) = stream.next().await {
+        if matches!(message, Ok(Message::Close(_)) | Err(_)) {
+            return;
+        }
+    }
+}
+
+// *** WsSink ***
+
+type BoxSink = Pin<Box<dyn Sink<Message, Error = axum::Error> + Send>>;
+
+/// Sends messages on an upgraded WebSocket. The sink can be shared with the keepalive, which also sends pings and tracks
+/// when a message was last sent or received.
+pub struct WsSink {
+    sink: Arc<Mutex<BoxSink>>,
+    last_activity: Arc<std::sync::Mutex<Instant>>,
+    protocol: WsProtocol,
+}
+
+impl WsSink {
+    pub(crate) fn new(
+        sink: impl Sink<Message, Error = axum::Error> + Send + 'static,
+        protocol: WsProtocol,
+    ) -> Self {
+        Self {
+            sink: Arc::new(Mutex::new(Box::pin(sink))),
+            last_activity: Arc::new(std::sync::Mutex::new(Instant::now())),
+            protocol,
+        }
+    }
+
+    pub(crate) fn share(&self) -> Self {
+        Self {
+            sink: self.sink.clone(),
+            last_activity: self.last_activity.clone(),
+            protocol: self.protocol.clone(),
+        }
+    }
+
+    pub(crate) async fn send(&self, message: Message) -> Result<(), axum::Error> {
+        let data = matches!(message, Message::Text(_) | Message::Binary(_));
+        self.sink.lock().await.send(message).await?;
+        if data {
+            self.touch();
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn close(&self) -> Result<(), axum::Error> {
+        self.sink.lock().await.close().await
+    }
+
+    /// Sends the header frame with the initial metadata of a call, if metadata frames are enabled
+    async fn send_headers(&self, metadata: &MetadataMap) -> Result<(), axum::Error> {
+        let Some(policy) = &self.protocol.metadata_policy else {
+            return Ok(());
+        };
+        let frame = WsFrame::Metadata(metadata_json(policy, metadata));
+        self.send(frame.into_message()).await
+    }
+
+    /// Sends the final status of a call: the trailer frame if metadata frames are enabled, or otherwise the status
+    /// frame of a failed call if enabled
+    async fn send_status(&self, status: &tonic::Status) -> Result<(), axum::Error> {
+        let message = if let Some(policy) = &self.protocol.metadata_policy {
+            let mut json = status_json(status);
+            json["metadata"] = metadata_json(policy, status.metadata());
+            WsFrame::Status(json).into_message()
+        } else if self.protocol.status_frame && status.code() != tonic::Code::Ok {
+            if self.protocol.protobuf {
+                Message::Binary(status_proto(status))
+            } else {
+                WsFrame::Status(status_json(status)).into_message()
+            }
+        } else {
+            return Ok(());
+        };
+        self.send(message).await
+    }
+
+    /// Sends the metadata of each call in frames of its own, even if metadata frames aren't enabled
+    fn with_metadata_frames(mut self, extensions: &http::Extensions) -> Self {
+        if self.protocol.metadata_policy.is_none() {
+            self.protocol.metadata_policy = Some(
+                extensions
+                    .get::<ResponseHeaderPolicy>()
+                    .cloned()
+                    .unwrap_or_default(),
+            );
+        }
+        self
+    }
+
+    /// Records that a message was sent or received
+    pub(crate) fn touch(&self) {
+        *self.last_activity.lock().unwrap() = Instant::now();
+    }
+
+    pub(crate) fn last_activity(&self) -> Instant {
+        *self.last_activity.lock().unwrap()
+    }
+}
+
 // *** Shared functions ***
 
-async fn finish_ws(ws: &mut SplitSink<WebSocket, Message>, status: tonic::Status) {
-    let frame = tonic_status_to_ws_close_frame(status);
+fn metadata_json(policy: &ResponseHeaderPolicy, metadata: &MetadataMap) -> serde_json::Value {
+    let mut headers = http::HeaderMap::new();
+    policy.apply_metadata(metadata, &mut headers);
+
+    let mut json = serde_json::Map::new();
+    for name in headers.keys() {
+        let values: Vec<_> = headers
+            .get_all(name)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .collect();
+        json.insert(name.to_string(), values.join(", ").into());
+    }
+    json.into()
+}
+
+/// The status a socket is closed with, and its close code
+pub(crate) struct WsClose {
+    status: tonic::Status,
+    code: u16,
+}
+
+impl WsClose {
+    /// Closes the socket with the status, but with the policy violation code instead of the one for the status
+    pub(crate) fn policy_violation(status: tonic::Status) -> Self {
+        Self {
+            status,
+            code: POLICY,
+        }
+    }
+}
+
+impl From<tonic::Status> for WsClose {
+    fn from(status: tonic::Status) -> Self {
+        let code = tonic_status_to_ws_close_code(&status);
+        Self { status, code }
+    }
+}
+
+pub(crate) async fn finish_ws(ws: &WsSink, close: impl Into<WsClose>) {
+    let close = close.into();
+    if let Err(err) = ws.send_status(&close.status).await {
+        tracing::error!("Error sending status frame: {}", err);
+    }
+    close_socket(ws, close).await;
+}
+
+/// Closes the socket with the close code and the reason for the status
+async fn close_socket(ws: &WsSink, close: WsClose) {
+    let frame = CloseFrame {
+        code: close.code,
+        reason: close.status.message().into(),
+    };
     if let Err(err) = ws.send(Message::Close(Some(frame))).await {
         tracing::error!("Error sending close frame: {}", err);
     }
@@ -58,8 +439,8 @@ async fn finish_ws(ws: &mut SplitSink<WebSocket, Message>, status: tonic::Status
     }
 }
 
-fn tonic_status_to_ws_close_frame(status: tonic::Status) -> CloseFrame {
-    let code = match status.code() {
+fn tonic_status_to_ws_close_code(status: &tonic::Status) -> u16 {
+    match status.code() {
         tonic::Code::Ok => NORMAL,
         tonic::Code::Cancelled => AWAY,
         tonic::Code::InvalidArgument => INVALID,
@@ -70,15 +451,24 @@ fn tonic_status_to_ws_close_frame(status: tonic::Status) -> CloseFrame {
         tonic::Code::DeadlineExceeded => AGAIN,
         tonic::Code::Unavailable => AGAIN,
         _ => ERROR,
-    };
-    CloseFrame {
-        code,
-        reason: status.message().into(),
+    }
+}
+
+/// Converts an error reading the socket into a status. Messages or frames over the size limits are reported as
+/// `RESOURCE_EXHAUSTED`, so the socket is closed with the message too big code. Axum doesn't expose the error type of
+/// its WebSockets, so these are recognized by the message of tungstenite's capacity error.
+pub(crate) fn ws_read_error_to_status(err: &axum::Error) -> tonic::Status {
+    const CAPACITY_ERROR: &str = "Space limit exceeded: ";
+
+    let message = err.to_string();
+    match message.strip_prefix(CAPACITY_ERROR) {
+        Some(reason) => tonic::Status::resource_exhausted(reason),
+        None => tonic::Status::internal(message),
     }
 }
 
 async fn send_ws_msg<T: Send + prost::Message + Serialize + 'static>(
-    ws: &mut SplitSink<WebSocket, Message>,
+    ws: &WsSink,
     msg: T,
     protobuf: bool,
 ) -> Result<(), axum::Error> {
@@ -87,85 +477,170 @@ async fn send_ws_msg<T: Send + prost::Message + Serialize + 'static>(
         msg.encode(&mut buf).map_err(axum::Error::new)?;
         ws.send(Message::Binary(buf.freeze())).await?;
     } else {
-        let text = serde_json::to_string(&msg).map_err(axum::Error::new)?;
+        let text = if ws.protocol.envelope() {
+            serde_json::to_string(&WsFrame::Result(&msg))
+        } else {
+            serde_json::to_string(&msg)
+        };
+        let text = text.map_err(axum::Error::new)?;
         ws.send(Message::Text(text.into())).await?;
     }
     Ok(())
 }
 
 /// Closes a WebSocket connection with the given tonic status
-pub async fn close_ws(mut ws: SplitSink<WebSocket, Message>, status: tonic::Status) {
-    finish_ws(&mut ws, status).await;
+pub async fn close_ws(ws: WsSink, status: tonic::Status) {
+    finish_ws(&ws, status).await;
 }
 
 // *** Client functions ***
 
-/// Converts a web socket request into a Tonic streaming request
+/// Converts a web socket request into a Tonic streaming request. The request stream ends when the socket is closed or
+/// the client sends [WS_END_OF_INPUT_TEXT] or [WS_END_OF_INPUT_BINARY], in which case the socket stays open for the
+/// response. The socket is still read after the end of input, and the call is cancelled if the client closes it.
 pub fn make_ws_stream_request<T: Send + Default + DeserializeOwned + prost::Message + 'static>(
     headers: http::HeaderMap,
     extensions: http::Extensions,
-    ws: SplitStream<WebSocket>,
+    ws: WsStream,
 ) -> tonic::Request<tonic::Streaming<T>> {
-    let metadata = MetadataMap::from_headers(headers);
-    // HACK: Unfortunately, Streaming requires a real gRPC frame, so we need to fake it, by returning a fake frame from the
-    // Body impl while prepping the real items from the web socket stream to return by Decoder.
-    let helper = FakeGrpcFrameStreamingHelper::new(convert_stream(ws));
-    // Since we use items polled by Body, but returned by Decoder, we need to clone the helper to use as both parameters
-    // to the Streaming constructor.
-    let streaming = tonic::Streaming::new_request(helper.clone(), helper, None, None);
+    make_ws_stream_request_with(headers, extensions, ws, |item| item)
+}
+
+/// Converts a web socket request into a Tonic streaming request (see [make_ws_stream_request]), passing each message
+/// through the transform function. This allows values from the path to be bound into each message.
+pub fn make_ws_stream_request_with<T, U, F>(
+    headers: http::HeaderMap,
+    mut extensions: http::Extensions,
+    ws: WsStream,
+    transform: F,
+) -> tonic::Request<tonic::Streaming<U>>
+where
+    T: Send + Default + DeserializeOwned + prost::Message + 'static,
+    U: Send + 'static,
+    F: FnMut(T) -> U + Send + 'static,
+{
+    let limits = StreamLimits::from_extensions(&extensions);
+    let metadata = crate::headers::request_metadata(headers, &mut extensions);
+    // Each message is handed to Streaming as a gRPC frame holding the raw message (tagged with its encoding), which is
+    // decoded by the decoder
+    let decoder = MapDecoder::new(WsDecoder::new(), transform);
+    let streaming = new_streaming(decoder, convert_stream(ws), limits);
     tonic::Request::from_parts(metadata, extensions, streaming)
 }
 
-fn convert_stream<T: Send + Default + DeserializeOwned + prost::Message + 'static>(
-    ws: SplitStream<WebSocket>,
-) -> impl Stream<Item = Result<T, axum::Error>> {
-    ws.filter_map(|message| async move {
-        match message {
-            // Text frame - decode as JSON
-            Ok(Message::Text(message)) => {
-                Some(serde_json::from_str(&message).map_err(axum::Error::new))
+fn convert_stream(
+    ws: WsStream,
+) -> impl Stream<Item = Result<RawMessage, tonic::Status>> + Send + 'static {
+    futures_util::stream::unfold(ws, |mut ws| async move {
+        loop {
+            let message = ws.next().await?;
+            // The end of input ends the request stream, but not the socket, which is handed back to be watched for
+            // the close
+            if is_end_of_input(&message) {
+                if let Some(rest) = ws.rest.take() {
+                    let _ = rest.send(ws);
+                }
+                return None;
             }
-            // Binary frame - decode as protobuf
-            Ok(Message::Binary(message)) => Some(T::decode(message).map_err(axum::Error::new)),
-            // Close frame with error code
-            Ok(Message::Close(Some(close_frame))) => ws_code_to_error(close_frame.code).map(Err),
-            // Something else - skip it
-            Ok(_) => None,
-            // Error - return it
-            Err(e) => Some(Err(e)),
+
+            let item = match message {
+                // Text frame - JSON
+                Ok(Message::Text(message)) => Ok(RawMessage::tagged(JSON_TAG, message.into())),
+                // Binary frame - protobuf
+                Ok(Message::Binary(message)) => Ok(RawMessage::tagged(PROTOBUF_TAG, message)),
+                // Close frame with error code
+                Ok(Message::Close(Some(close_frame))) => match ws_code_to_error(close_frame.code) {
+                    Some(status) => Err(status),
+                    None => continue,
+                },
+                // Something else - skip it
+                Ok(_) => continue,
+                // Error - return it
+                Err(e) => Err(ws_read_error_to_status(&e)),
+            };
+            return Some((item, ws));
         }
     })
 }
 
-fn ws_code_to_error(code: u16) -> Option<axum::Error> {
+fn is_end_of_input(message: &Result<Message, axum::Error>) -> bool {
+    match message {
+        Ok(Message::Text(text)) => text.as_str() == WS_END_OF_INPUT_TEXT,
+        Ok(Message::Binary(data)) => data.as_ref() == WS_END_OF_INPUT_BINARY,
+        _ => false,
+    }
+}
+
+fn ws_code_to_error(code: u16) -> Option<tonic::Status> {
     match code {
         NORMAL => None,
-        code => Some(axum::Error::new(format!(
+        code => Some(tonic::Status::internal(format!(
             "WebSocket closed with code: {}",
             code
         ))),
     }
 }
 
+// *** WsDecoder ***
+
+/// Decodes the JSON or protobuf message held by each (tagged) gRPC frame
+struct WsDecoder<T>(PhantomData<fn() -> T>);
+
+impl<T> WsDecoder<T> {
+    fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<T: Default + DeserializeOwned + prost::Message> Decoder for WsDecoder<T> {
+    type Item = T;
+    type Error = tonic::Status;
+
+    fn decode(&mut self, src: &mut DecodeBuf<'_>) -> Result<Option<Self::Item>, Self::Error> {
+        if !src.has_remaining() {
+            return Err(tonic::Status::internal("Missing message tag"));
+        }
+
+        match src.get_u8() {
+            JSON_TAG => {
+                let json = src.copy_to_bytes(src.remaining());
+                serde_json::from_slice(&json)
+                    .map(Some)
+                    .map_err(|err| tonic::Status::invalid_argument(err.to_string()))
+            }
+            PROTOBUF_TAG => T::decode(src)
+                .map(Some)
+                .map_err(|err| tonic::Status::invalid_argument(err.to_string())),
+            tag => Err(tonic::Status::internal(format!(
+                "Unknown message tag: {tag}"
+            ))),
+        }
+    }
+}
+
 /// Processes a Tonic response into a WebSocket response
 pub async fn process_ws_response<T: Send + prost::Message + Serialize + 'static>(
     response: Result<tonic::Response<T>, tonic::Status>,
-    mut ws: SplitSink<WebSocket, Message>,
+    ws: WsSink,
     protobuf: bool,
 ) {
-    let status = handle_ws_response(response, &mut ws, protobuf).await;
-    finish_ws(&mut ws, status).await;
+    let status = handle_ws_response(response, &ws, protobuf).await;
+    finish_ws(&ws, status).await;
 }
 
 async fn handle_ws_response<T: Send + prost::Message + Serialize + 'static>(
     response: Result<tonic::Response<T>, tonic::Status>,
-    ws: &mut SplitSink<WebSocket, Message>,
+    ws: &WsSink,
     protobuf: bool,
 ) -> tonic::Status {
     match response {
         Ok(response) => {
-            let msg = response.into_inner();
-            match send_ws_msg(ws, msg, protobuf).await {
+            let (metadata, msg, _) = response.into_parts();
+            let sent = async {
+                ws.send_headers(&metadata).await?;
+                send_ws_msg(ws, msg, protobuf).await
+            };
+            match sent.await {
                 Ok(()) => tonic::Status::ok(""),
                 Err(err) => {
                     tracing::error!("Error sending WS response: {}", err);
@@ -182,19 +657,19 @@ async fn handle_ws_response<T: Send + prost::Message + Serialize + 'static>(
 /// Processes a Tonic stream response into a WebSocket response
 pub async fn process_ws_stream_response<S, T>(
     response: Result<tonic::Response<S>, tonic::Status>,
-    mut ws: SplitSink<WebSocket, Message>,
+    ws: WsSink,
     protobuf: bool,
 ) where
     S: Stream<Item = Result<T, tonic::Status>> + Send + 'static,
     T: prost::Message + Serialize + Send + 'static,
 {
-    let status = handle_ws_stream_response(response, &mut ws, protobuf).await;
-    finish_ws(&mut ws, status).await;
+    let status = handle_ws_stream_response(response, &ws, protobuf).await;
+    finish_ws(&ws, status).await;
 }
 
 async fn handle_ws_stream_response<S, T>(
     response: Result<tonic::Response<S>, tonic::Status>,
-    ws: &mut SplitSink<WebSocket, Message>,
+    ws: &WsSink,
     protobuf: bool,
 ) -> tonic::Status
 where
@@ -203,7 +678,11 @@ where
 {
     match response {
         Ok(response) => {
-            let stream = response.into_inner();
+            let (metadata, stream, _) = response.into_parts();
+            if let Err(err) = ws.send_headers(&metadata).await {
+                tracing::error!("Error sending WS response: {}", err);
+                return tonic::Status::internal(err.to_string());
+            }
             futures_util::pin_mut!(stream);
             while let Some(msg) = stream.next().await {
                 match msg {
@@ -226,23 +705,74 @@ where
 pub async fn make_ws_request<T: Send + Default + prost::Message + DeserializeOwned + 'static>(
     headers: http::HeaderMap,
     extensions: http::Extensions,
-    mut ws: SplitStream<WebSocket>,
+    mut ws: WsStream,
 ) -> Option<tonic::Request<T>> {
-    let metadata = MetadataMap::from_headers(headers);
+    next_ws_request(headers, extensions, &mut ws).await
+}
+
+/// Runs sequential unary calls over a WebSocket. Each message from the client is converted into a Tonic request and
+/// handled, with the response sent back before the next message is read. The socket always uses metadata frames (see
+/// [WsOptions::metadata_frames]), so each call ends with its own trailer frame holding its status, and a call that
+/// fails (including one whose request can't be decoded) doesn't end the socket. The socket is closed normally once the
+/// client closes it or sends [WS_END_OF_INPUT_TEXT] or [WS_END_OF_INPUT_BINARY], and with an error if it fails.
+pub async fn process_ws_unary_calls<T, U, H, Fut>(
+    headers: http::HeaderMap,
+    extensions: http::Extensions,
+    mut stream: WsStream,
+    sink: WsSink,
+    protobuf: bool,
+    mut handler: H,
+) where
+    T: Send + Default + prost::Message + DeserializeOwned + 'static,
+    U: Send + prost::Message + Serialize + 'static,
+    H: FnMut(tonic::Request<T>) -> Fut,
+    Fut: Future<Output = Result<tonic::Response<U>, tonic::Status>>,
+{
+    let sink = sink.with_metadata_frames(&extensions);
+    let status = loop {
+        let Some(message) = stream.next().await else {
+            break tonic::Status::ok("");
+        };
+        let call_status = match convert_ws_to_item(message) {
+            WsItem::Request(item) => {
+                let mut extensions = extensions.clone();
+                let metadata = crate::headers::request_metadata(headers.clone(), &mut extensions);
+                let request = tonic::Request::from_parts(metadata, extensions, item);
+                handle_ws_response(handler(request).await, &sink, protobuf).await
+            }
+            WsItem::Invalid(status) => status,
+            WsItem::Skip => continue,
+            WsItem::End => break tonic::Status::ok(""),
+            WsItem::Failed(status) => break status,
+        };
+        if let Err(err) = sink.send_status(&call_status).await {
+            break tonic::Status::internal(err.to_string());
+        }
+    };
+    // Each call has already sent its status
+    close_socket(&sink, status.into()).await;
+}
+
+async fn next_ws_request<T: Send + Default + prost::Message + DeserializeOwned + 'static>(
+    headers: http::HeaderMap,
+    mut extensions: http::Extensions,
+    ws: &mut WsStream,
+) -> Option<tonic::Request<T>> {
+    let metadata = crate::headers::request_metadata(headers, &mut extensions);
 
     while let Some(message) = ws.next().await {
         match convert_ws_to_item(message) {
             // Item received - return it
-            Ok((Some(item), _)) => {
+            WsItem::Request(item) => {
                 return Some(tonic::Request::from_parts(metadata, extensions, item));
             }
             // Normal end of stream - all done
-            Ok((None, true)) => return None,
+            WsItem::End => return None,
             // No-op - continue
-            Ok((None, false)) => continue,
+            WsItem::Skip => continue,
             // Error - all done
-            Err(e) => {
-                tracing::error!("Error converting WS message to item: {}", e);
+            WsItem::Invalid(status) | WsItem::Failed(status) => {
+                tracing::error!("Error converting WS message to item: {}", status);
                 return None;
             }
         }
@@ -251,30 +781,324 @@ pub async fn make_ws_request<T: Send + Default + prost::Message + DeserializeOwn
     None
 }
 
+/// A message received on a socket, converted into a request message
+enum WsItem<T> {
+    Request(T),
+    // The message couldn't be decoded, which only fails the call it was meant for
+    Invalid(tonic::Status),
+    // Ping/pong
+    Skip,
+    // The client ended its requests or closed the socket normally
+    End,
+    // The socket failed or was closed with an error code
+    Failed(tonic::Status),
+}
+
 fn convert_ws_to_item<T: Send + Default + prost::Message + DeserializeOwned + 'static>(
     result: Result<Message, axum::Error>,
-) -> Result<(Option<T>, bool), axum::Error> {
+) -> WsItem<T> {
+    if is_end_of_input(&result) {
+        return WsItem::End;
+    }
+
+    let invalid = |err: &dyn std::fmt::Display| tonic::Status::invalid_argument(err.to_string());
     match result {
         // Text frame - decode as JSON
-        Ok(Message::Text(message)) => serde_json::from_str(&message)
-            .map(|msg| (Some(msg), false))
-            .map_err(axum::Error::new),
+        Ok(Message::Text(message)) => match serde_json::from_str(&message) {
+            Ok(msg) => WsItem::Request(msg),
+            Err(err) => WsItem::Invalid(invalid(&err)),
+        },
         // Binary frame - decode as protobuf
-        Ok(Message::Binary(message)) => T::decode(message)
-            .map(|msg| (Some(msg), false))
-            .map_err(axum::Error::new),
-        // Normal end of stream
-        Ok(Message::Close(Some(close_frame))) if close_frame.code == NORMAL => Ok((None, true)),
-        // Normal end of stream
-        Ok(Message::Close(None)) => Ok((None, true)),
-        // Close frame with error code
-        Ok(Message::Close(Some(close_frame))) => Err(axum::Error::new(format!(
-            "WebSocket closed with code: {}",
-            close_frame.code
-        ))),
+        Ok(Message::Binary(message)) => match T::decode(message) {
+            Ok(msg) => WsItem::Request(msg),
+            Err(err) => WsItem::Invalid(invalid(&err)),
+        },
+        // Close frame - normal end of stream, unless it has an error code
+        Ok(Message::Close(close_frame)) => {
+            match close_frame.and_then(|close_frame| ws_code_to_error(close_frame.code)) {
+                Some(status) => WsItem::Failed(status),
+                None => WsItem::End,
+            }
+        }
         // Something else - skip it
-        Ok(_) => Ok((None, false)),
+        Ok(_) => WsItem::Skip,
         // Error - return it
-        Err(e) => Err(e),
+        Err(e) => WsItem::Failed(ws_read_error_to_status(&e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::streaming::fake_socket::fake_socket;
+
+    async fn finish(protocol: WsProtocol, status: tonic::Status) -> Vec<Message> {
+        let (mut client, _, sink) = fake_socket(protocol);
+        finish_ws(&sink, status).await;
+        client.sent()
+    }
+
+    fn status_frames(protobuf: bool) -> WsProtocol {
+        WsProtocol {
+            protobuf,
+            status_frame: true,
+            metadata_policy: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_finish_ws_status_frame() {
+        let status = || tonic::Status::not_found("missing");
+        let close = Message::Close(Some(CloseFrame {
+            code: ERROR,
+            reason: "missing".into(),
+        }));
+
+        assert_eq!(
+            finish(WsProtocol::default(), status()).await,
+            vec![close.clone()]
+        );
+        assert_eq!(
+            finish(status_frames(false), status()).await,
+            [
+                Message::Text(r#"{"status":{"code":5,"details":[],"message":"missing"}}"#.into()),
+                close.clone()
+            ]
+        );
+        assert_eq!(
+            finish(status_frames(true), status()).await,
+            [Message::Binary(status_proto(&status())), close]
+        );
+        // Successful calls just close normally
+        assert_eq!(
+            finish(status_frames(false), tonic::Status::ok("")).await,
+            [Message::Close(Some(CloseFrame {
+                code: NORMAL,
+                reason: "".into()
+            }))]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_end_of_input() {
+        for end in [
+            Message::Text(WS_END_OF_INPUT_TEXT.into()),
+            Message::Binary(WS_END_OF_INPUT_BINARY.into()),
+        ] {
+            let (client, stream, _) = fake_socket(WsProtocol::default());
+            for message in [
+                Message::Text("\"a\"".into()),
+                end,
+                Message::Text("\"b\"".into()),
+            ] {
+                client.send(message);
+            }
+
+            // The stream ends without waiting for the socket to close
+            let request = make_ws_stream_request::<String>(
+                http::HeaderMap::new(),
+                http::Extensions::new(),
+                stream,
+            );
+            let messages: Vec<_> = request.into_inner().collect().await;
+            assert_eq!(messages.len(), 1);
+            assert_eq!(messages[0].as_ref().unwrap(), "a");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_close_after_end_of_input() {
+        let (client, stream, sink) = fake_socket(WsProtocol::default());
+        for message in [
+            Message::Text("\"a\"".into()),
+            Message::Text(WS_END_OF_INPUT_TEXT.into()),
+            Message::Text("\"b\"".into()),
+            Message::Close(None),
+        ] {
+            client.send(message);
+        }
+
+        // The call never finishes responding, but is cancelled by the close
+        let call = run_until_closed(stream, &sink, |stream| async move {
+            let request = make_ws_stream_request::<String>(
+                http::HeaderMap::new(),
+                http::Extensions::new(),
+                stream,
+            );
+            let messages: Vec<_> = request.into_inner().collect().await;
+            assert_eq!(messages.len(), 1);
+            std::future::pending::<()>().await;
+        });
+        tokio::time::timeout(std::time::Duration::from_secs(5), call)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_metadata_frames() {
+        let protocol = || WsProtocol {
+            metadata_policy: Some(ResponseHeaderPolicy::default()),
+            ..WsProtocol::default()
+        };
+        let text = |json: serde_json::Value| Message::Text(json.to_string().into());
+        let normal = Message::Close(Some(CloseFrame {
+            code: NORMAL,
+            reason: "".into(),
+        }));
+
+        let mut response = tonic::Response::new(futures_util::stream::iter([
+            Ok("a".to_string()),
+            Ok("b".to_string()),
+        ]));
+        response.metadata_mut().insert("x-id", "1".parse().unwrap());
+        let (mut client, _, sink) = fake_socket(protocol());
+        process_ws_stream_response(Ok(response), sink, false).await;
+        assert_eq!(
+            client.sent(),
+            [
+                text(serde_json::json!({"metadata": {"x-id": "1"}})),
+                text(serde_json::json!({"result": "a"})),
+                text(serde_json::json!({"result": "b"})),
+                text(serde_json::json!({
+                    "status": {"code": 0, "message": "", "details": [], "metadata": {}},
+                })),
+                normal,
+            ]
+        );
+
+        // A call that fails before responding only sends the trailer frame
+        let mut status = tonic::Status::not_found("missing");
+        status.metadata_mut().insert("x-id", "2".parse().unwrap());
+        let (mut client, _, sink) = fake_socket(protocol());
+        process_ws_response(Err::<tonic::Response<String>, _>(status), sink, false).await;
+        let sent = client.sent();
+        assert_eq!(
+            sent[0],
+            text(serde_json::json!({
+                "status": {"code": 5, "message": "missing", "details": [], "metadata": {"x-id": "2"}},
+            }))
+        );
+        assert_eq!(sent.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_unary_calls() {
+        let text = |json: serde_json::Value| Message::Text(json.to_string().into());
+        let (mut client, stream, sink) = fake_socket(WsProtocol::default());
+        for message in ["\"a\"", "\"fail\"", "\"b\""] {
+            client.send(Message::Text(message.into()));
+        }
+        client.disconnect();
+
+        // A failed call sends its status, and the next call still runs
+        process_ws_unary_calls(
+            http::HeaderMap::new(),
+            http::Extensions::new(),
+            stream,
+            sink,
+            false,
+            |request: tonic::Request<String>| async move {
+                match request.into_inner() {
+                    message if message == "fail" => Err(tonic::Status::not_found("missing")),
+                    message => Ok(tonic::Response::new(message)),
+                }
+            },
+        )
+        .await;
+        let ok = || {
+            text(serde_json::json!({
+                "status": {"code": 0, "message": "", "details": [], "metadata": {}},
+            }))
+        };
+        assert_eq!(
+            client.sent(),
+            [
+                text(serde_json::json!({"metadata": {}})),
+                text(serde_json::json!({"result": "a"})),
+                ok(),
+                text(serde_json::json!({
+                    "status": {"code": 5, "message": "missing", "details": [], "metadata": {}},
+                })),
+                text(serde_json::json!({"metadata": {}})),
+                text(serde_json::json!({"result": "b"})),
+                ok(),
+                Message::Close(Some(CloseFrame {
+                    code: NORMAL,
+                    reason: "".into(),
+                })),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ws_read_error_to_status() {
+        use tokio_tungstenite::tungstenite;
+
+        // The error is raised by a real socket, as Axum's WebSockets don't expose their error type
+        let app = axum::Router::new()
+            .route(
+                "/",
+                axum::routing::get(
+                    |ws: WebSocketUpgrade,
+                     uri: http::Uri,
+                     headers: http::HeaderMap,
+                     extensions: http::Extensions| async move {
+                        upgrade_to_ws(ws, uri, headers, extensions, false, |_, _, stream, _, _| {
+                            // Holds on to the messages without reading them
+                            let _stream = stream;
+                            std::future::pending()
+                        })
+                        .await
+                    },
+                ),
+            )
+            .layer(axum::Extension(WsLimits::new().max_message_size(16)));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let tcp = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let (mut client, _) = tokio_tungstenite::client_async(format!("ws://{addr}/"), tcp)
+            .await
+            .unwrap();
+        client
+            .send(tungstenite::Message::text("x".repeat(32)))
+            .await
+            .unwrap();
+
+        let message = client.next().await.unwrap().unwrap();
+        match message {
+            tungstenite::Message::Close(Some(frame)) => {
+                assert_eq!(u16::from(frame.code), SIZE);
+                assert_eq!(frame.reason.as_str(), "Message too long: 32 > 16");
+            }
+            message => panic!("Expected a close frame, got: {message:?}"),
+        }
+
+        let status = ws_read_error_to_status(&axum::Error::new(std::io::Error::other("broken")));
+        assert_eq!(status.code(), tonic::Code::Internal);
+    }
+
+    #[test]
+    fn test_negotiate_ws_protocol() {
+        let negotiate = |values: &[&str]| {
+            let mut headers = http::HeaderMap::new();
+            for value in values {
+                headers.append(http::header::SEC_WEBSOCKET_PROTOCOL, value.parse().unwrap());
+            }
+            negotiate_ws_protocol(&headers)
+        };
+
+        assert_eq!(negotiate(&[]), None);
+        assert_eq!(negotiate(&["chat, superchat"]), None);
+        // The client's order of preference wins
+        assert_eq!(
+            negotiate(&["chat, tonic2axum.proto, tonic2axum.json"]),
+            Some(WS_PROTOBUF_PROTOCOL)
+        );
+        assert_eq!(
+            negotiate(&["chat", "tonic2axum.json"]),
+            Some(WS_JSON_PROTOCOL)
+        );
     }
 }